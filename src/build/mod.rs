@@ -12,7 +12,29 @@ use anyhow::{anyhow, Result};
 // Internal build logic, called by both `build` and `publish`
 // TODO: return a struct containing optional build info and construct output at command layer
 pub fn build_target(target: &Target) -> Result<String> {
+    build(target, false)
+}
+
+// Builds the Worker for `wrangler dev`, where Rust projects are built without
+// optimizations so rebuilding them on changes is incremental
+pub fn build_dev_target(target: &Target) -> Result<String> {
+    build(target, true)
+}
+
+// The arguments to wasm-pack to build a Rust Worker
+pub fn wasm_pack_args(dev: bool) -> Vec<&'static str> {
+    let mut args = vec!["build", "--target", "no-modules"];
+    if dev {
+        args.push("--dev");
+    }
+    args
+}
+
+fn build(target: &Target, dev: bool) -> Result<String> {
     let target_type = &target.target_type;
+    if target.minify && target_type != &TargetType::Webpack {
+        StdErr::warn("minify is only supported for webpack projects, minify the script in your build instead");
+    }
     match target_type {
         TargetType::JavaScript => match &target.build {
             None => {
@@ -45,22 +67,29 @@ pub fn build_target(target: &Target) -> Result<String> {
             })?;
 
             let binary_path = install::install_wasm_pack()?;
-            let args = ["build", "--target", "no-modules"];
+            let args = wasm_pack_args(dev);
 
             let command = command(&args, &binary_path);
             let command_name = format!("{:?}", command);
 
-            StdErr::working("Compiling your project to WebAssembly...");
+            if dev {
+                StdErr::working("Compiling your project to WebAssembly, without optimizations...");
+            } else {
+                StdErr::working("Compiling your project to WebAssembly...");
+            }
             commands::run(command, &command_name)?;
             let msg = "Build succeeded".to_string();
             Ok(msg)
         }
         TargetType::Webpack => match wranglerjs::run_build(target) {
             Ok(output) => {
-                let msg = format!(
+                let mut msg = format!(
                     "Built successfully, built project size is {}",
                     output.project_size()
                 );
+                if let Some(minified) = output.minified_size() {
+                    msg = format!("{}\n{}", msg, minified);
+                }
                 Ok(msg)
             }
             Err(e) => Err(e),
@@ -81,3 +110,20 @@ pub fn command(args: &[&str], binary_path: &Path) -> Command {
     c.args(args);
     c
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_rust_without_optimizations_in_dev() {
+        assert_eq!(
+            wasm_pack_args(false),
+            vec!["build", "--target", "no-modules"]
+        );
+        assert_eq!(
+            wasm_pack_args(true),
+            vec!["build", "--target", "no-modules", "--dev"]
+        );
+    }
+}
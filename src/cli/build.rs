@@ -8,6 +8,6 @@ use anyhow::Result;
 pub fn build(cli_params: &Cli) -> Result<()> {
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
     build_target(&target).map(|msg| StdOut::success(&msg))
 }
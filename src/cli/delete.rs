@@ -0,0 +1,37 @@
+use super::Cli;
+use crate::commands::{self, delete::DeleteOptions};
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn delete(routes: bool, durable_objects: bool, yes: bool, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+
+    let zone_id = if routes {
+        let zone_id = manifest
+            .get_environment(cli_params.environment())?
+            .and_then(|e| e.zone_id.clone())
+            .or_else(|| manifest.zone_id.clone());
+        if zone_id.is_none() {
+            anyhow::bail!("You must specify a zone_id in your configuration file to delete the routes of your worker");
+        }
+        zone_id
+    } else {
+        None
+    };
+
+    commands::delete::delete(
+        &user,
+        &target,
+        DeleteOptions {
+            zone_id,
+            durable_objects,
+            yes,
+        },
+    )
+}
@@ -1,21 +1,147 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use std::time::Duration;
 
 use super::Cli;
-use crate::commands::{self, dev::Protocol};
+use crate::commands::{
+    self,
+    dev::{
+        harness, hotkeys,
+        local::{self, Services},
+        record, LiveReload, Protocol, Recorder, Repl,
+    },
+    tail::{
+        self,
+        websocket::{TailFormat, TailOptions},
+    },
+};
+use crate::deploy::DeployTarget;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::styles;
 
 use anyhow::Result;
+use serde::Serialize;
+use serde_json::Map;
+use structopt::StructOpt;
+use tokio::sync::{mpsc, oneshot};
 
-pub fn dev(
-    host: Option<String>,
-    mut ip: Option<IpAddr>,
-    mut port: Option<u16>,
-    mut local_protocol: Option<Protocol>,
-    mut upstream_protocol: Option<Protocol>,
-    cli_params: &Cli,
-) -> Result<()> {
+#[derive(Debug, Clone, StructOpt)]
+pub struct Dev {
+    /// Host to forward requests to, defaults to the zone of project or to
+    /// tutorial.cloudflareworkers.com if unauthenticated.
+    #[structopt(long, short = "h")]
+    pub host: Option<String>,
+
+    /// IP to listen on. Defaults to 127.0.0.1, use 0.0.0.0 to test from other devices on your network
+    #[structopt(long, short = "i")]
+    pub ip: Option<IpAddr>,
+
+    /// Port to listen on. Defaults to 8787
+    #[structopt(long, short = "p")]
+    pub port: Option<u16>,
+
+    /// Sets the protocol on which the wrangler dev listens, by default this is http
+    /// but can be set to https
+    #[structopt(name = "local-protocol")]
+    pub local_protocol: Option<Protocol>,
+
+    /// Sets the protocol on which requests are sent to the host, by default this is https
+    /// but can be set to http
+    #[structopt(name = "upstream-protocol")]
+    pub upstream_protocol: Option<Protocol>,
+
+    /// Run the worker on this machine with Miniflare, instead of the Cloudflare preview service.
+    /// Miniflare is downloaded with npx the first time, after which this works offline
+    #[structopt(long)]
+    pub local: bool,
+
+    /// Reload pages that are open in a browser each time the worker is rebuilt
+    #[structopt(long)]
+    pub live_reload: bool,
+
+    /// Serve an inspector for the worker, so it can be debugged with Chrome DevTools
+    #[structopt(long)]
+    pub inspect: bool,
+
+    /// Open a prompt that evaluates JavaScript in the global scope of the worker
+    #[structopt(long)]
+    pub repl: bool,
+
+    /// Log each fetch() the worker makes, with its status and how long it took
+    #[structopt(long)]
+    pub log_subrequests: bool,
+
+    /// Port to serve the inspector on
+    #[structopt(long, default_value = "9229")]
+    pub inspector_port: u16,
+
+    /// Run another worker alongside this one with --local, from the directory of its project
+    /// e.g. `--mount auth=../auth-worker`
+    #[structopt(long = "mount", parse(try_from_str = parse_key_value), requires = "local")]
+    pub mounts: Vec<(String, String)>,
+
+    /// Bind a mounted worker as a service, e.g. `--service AUTH=auth`
+    #[structopt(long = "service", parse(try_from_str = parse_key_value), requires = "local")]
+    pub services: Vec<(String, String)>,
+
+    /// Simulate a property of `request.cf` with --local, e.g. `--cf country=GB`
+    #[structopt(long = "cf", parse(try_from_str = parse_key_value), requires = "local")]
+    pub cf: Vec<(String, String)>,
+
+    /// Where to keep the state of KV, Durable Objects and the Cache API with --local.
+    /// Defaults to .wrangler/state
+    #[structopt(long, parse(from_os_str), requires = "local")]
+    pub persist_to: Option<PathBuf>,
+
+    /// Fire scheduled events on the schedules of [triggers] crons with --local
+    #[structopt(long, requires = "local")]
+    pub cron: bool,
+
+    /// Stream the logs of a deployed worker into the dev output, tagged with its name
+    #[structopt(long)]
+    pub tail: Option<String>,
+
+    /// Send the requests of a TOML or JSON file to the worker, check their responses
+    /// and exit, with an error if any of them failed
+    #[structopt(long, parse(from_os_str))]
+    pub test: Option<PathBuf>,
+
+    /// Print the URL of the dev server as JSON, for tools that run wrangler dev
+    #[structopt(long, possible_value = "json")]
+    pub output: Option<String>,
+
+    /// Record the requests to the dev server to a file, to replay them later
+    #[structopt(long, parse(from_os_str))]
+    pub record: Option<PathBuf>,
+
+    /// Replay the requests of a recording against the dev server once it starts
+    #[structopt(long, parse(from_os_str))]
+    pub replay: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct DevOutput {
+    url: String,
+}
+
+pub fn dev(args: Dev, cli_params: &Cli) -> Result<()> {
     log::info!("Starting dev server");
     let manifest = Manifest::new(&cli_params.config)?;
+    // with `--output json`, stdout only has the URL, so everything else goes to stderr
+    let output = match args.output.as_deref() {
+        Some("json") => Output::Json,
+        _ => Output::PlainText,
+    };
+
+    let mut ip = args.ip;
+    let mut port = args.port;
+    let mut local_protocol = args.local_protocol;
+    let mut upstream_protocol = args.upstream_protocol;
+    let mut persist_to = args.persist_to;
+    let mut cf = None;
 
     // Check if arg not given but present in wrangler.toml
     if let Some(d) = &manifest.dev {
@@ -23,6 +149,16 @@ pub fn dev(
         port = port.or(d.port);
         local_protocol = local_protocol.or(d.local_protocol);
         upstream_protocol = upstream_protocol.or(d.upstream_protocol);
+        persist_to = persist_to.or_else(|| d.persist_to.clone());
+        cf = d.cf.clone();
+    }
+
+    // flags override the properties in wrangler.toml, one by one
+    if !args.cf.is_empty() {
+        let cf = cf.get_or_insert_with(Map::new);
+        for (key, value) in args.cf {
+            cf.insert(key, local::parse_cf_value(&value));
+        }
     }
 
     let ip = ip.unwrap_or_else(|| Ipv4Addr::new(127, 0, 0, 1).into());
@@ -30,19 +166,242 @@ pub fn dev(
     let local_protocol = local_protocol.unwrap_or(Protocol::Http);
     let upstream_protocol = upstream_protocol.unwrap_or(Protocol::Https);
 
-    let deployments = manifest.get_deployments(cli_params.environment.as_deref())?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), true)?;
-    let user = GlobalUser::new().ok();
-
-    let server_config = commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
-
-    commands::dev::dev(
-        target,
-        deployments,
-        user,
-        server_config,
-        local_protocol,
-        upstream_protocol,
-        cli_params.verbose,
-    )
+    let mut target = manifest.get_target(cli_params.environment(), true)?;
+    commands::dev::dev_vars::apply(&mut target, &cli_params.config, output)?;
+
+    let mut server_config =
+        commands::dev::ServerConfig::new(args.host, ip, port, upstream_protocol, output)?;
+    if args.live_reload {
+        server_config.live_reload = Some(LiveReload::new());
+    }
+    if args.inspect {
+        server_config.inspector_port = Some(args.inspector_port);
+    }
+    server_config.cf = cf;
+    server_config.persist_to = persist_to;
+    if args.repl {
+        server_config.repl = Some(Repl::start(output));
+    }
+    server_config.log_subrequests = args.log_subrequests;
+    if args.local {
+        server_config.upstream_address = Some(local::miniflare_address()?);
+    }
+
+    // the session runs until it is sent an action, which is run once it has stopped
+    let (sender, actions) = mpsc::unbounded_channel();
+    if let Some(path) = &args.test {
+        harness::run(path, &server_config, local_protocol, sender.clone())?;
+    }
+    // the recording to replay is read first, in case it is recorded over
+    if let Some(path) = args.replay {
+        record::replay(path, &server_config, local_protocol)?;
+    }
+    if let Some(path) = &args.record {
+        server_config.recorder = Some(Recorder::new(path, output)?);
+    }
+
+    if ip.is_unspecified() {
+        output.warn(&format!(
+            "Listening on all network interfaces, so the dev server can be reached by anyone on your network. Use {} to only listen on this machine.",
+            styles::highlight("--ip 127.0.0.1")
+        ));
+        if let Some(address) = network_address() {
+            output.info(&format!(
+                "Other devices on your network can visit {}:{}",
+                address,
+                server_config.listening_address.port()
+            ));
+        }
+    }
+
+    let url = format!(
+        "{}://{}",
+        if local_protocol.is_https() {
+            "https"
+        } else {
+            "http"
+        },
+        if server_config.listening_address.ip().is_unspecified() {
+            format!("localhost:{}", server_config.listening_address.port())
+        } else {
+            server_config.listening_address.to_string()
+        }
+    );
+    let tail = match args.tail {
+        Some(script_name) => Some(BackgroundTail::start(
+            script_name,
+            target.account_id.load()?.to_string(),
+            output,
+        )?),
+        None => None,
+    };
+
+    if output == Output::Json {
+        StdOut::as_json(&DevOutput { url: url.clone() });
+    }
+
+    // the REPL reads lines from the terminal, which the hotkeys would take keys from
+    if !args.repl {
+        hotkeys::listen(url, sender, output);
+    }
+
+    let action = if args.local {
+        if args.cron {
+            server_config.crons = crons(&manifest, cli_params.environment())?;
+            if server_config.crons.is_empty() {
+                output.warn(
+                    "--cron was passed, but there are no crons in the [triggers] of wrangler.toml",
+                );
+            }
+        }
+        let services = Services {
+            mounts: args
+                .mounts
+                .into_iter()
+                .map(|(name, path)| (name, PathBuf::from(path)))
+                .collect(),
+            bindings: args.services,
+        };
+        commands::dev::local::dev(
+            target,
+            server_config,
+            local_protocol,
+            services,
+            actions,
+            cli_params.verbose,
+        )?
+    } else {
+        let deployments = manifest.get_deployments(cli_params.environment())?;
+        let user = GlobalUser::new().ok();
+
+        commands::dev::dev(
+            target,
+            deployments,
+            user,
+            server_config,
+            local_protocol,
+            upstream_protocol,
+            actions,
+            cli_params.verbose,
+        )?
+    };
+    // the tail is deleted before the action is run, as it is when the session fails
+    drop(tail);
+
+    match action {
+        Some(action) => hotkeys::run(action, args.local, output),
+        None => Ok(()),
+    }
+}
+
+/// The cron triggers of the worker, to fire scheduled events on locally.
+fn crons(manifest: &Manifest, environment: Option<&str>) -> Result<Vec<String>> {
+    let deployments = manifest.get_deployments(environment)?;
+    Ok(deployments
+        .into_iter()
+        .find_map(|deployment| match deployment {
+            DeployTarget::Schedule(schedule) => Some(schedule.crons),
+            _ => None,
+        })
+        .unwrap_or_default())
+}
+
+/// A tail of a deployed worker, which runs on a thread of its own alongside the dev session.
+///
+/// It is stopped as it is dropped, which waits until it has been deleted.
+struct BackgroundTail {
+    stop: Option<oneshot::Sender<()>>,
+    stopped: std_mpsc::Receiver<()>,
+}
+
+impl BackgroundTail {
+    fn start(script_name: String, account_id: String, output: Output) -> Result<Self> {
+        let user = GlobalUser::new()?;
+        let options = TailOptions {
+            once: false,
+            format: TailFormat::Pretty,
+            filters: vec![],
+            event_filters: vec![],
+            out_file: None,
+            // each log is tagged with the name of the deployed worker
+            label: true,
+            forward: None,
+            duration: None,
+            stats: None,
+            fields: vec![],
+            heartbeat: Duration::from_secs(60),
+            metrics: None,
+            dedupe: None,
+            stderr: output == Output::Json,
+        };
+
+        let (stop, stop_receiver) = oneshot::channel();
+        let (stopped_sender, stopped) = std_mpsc::channel();
+        thread::spawn(move || {
+            let result = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(anyhow::Error::from)
+                .and_then(|runtime| {
+                    runtime.block_on(tail::run_in_background(
+                        user,
+                        account_id,
+                        vec![(script_name, options)],
+                        async {
+                            let _ = stop_receiver.await;
+                        },
+                    ))
+                });
+            // the dev session keeps running, so the error is shown as soon as the tail fails
+            if let Err(e) = result {
+                StdErr::warn(&format!("Failed to tail the deployed worker: {}", e));
+            }
+            let _ = stopped_sender.send(());
+        });
+        Ok(BackgroundTail {
+            stop: Some(stop),
+            stopped,
+        })
+    }
+}
+
+impl Drop for BackgroundTail {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        let _ = self.stopped.recv();
+    }
+}
+
+/// Finds the address of this machine on the local network, by choosing where a packet to
+/// the internet would be sent from. Connecting a UDP socket does not send anything.
+fn network_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(1, 1, 1, 1), 80)).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+fn parse_key_value(input: &str) -> Result<(String, String)> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+            Ok((key.to_owned(), value.to_owned()))
+        }
+        _ => anyhow::bail!("expected a value like NAME=VALUE: {}", input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_key_values() {
+        assert_eq!(
+            parse_key_value("auth=../auth-worker").unwrap(),
+            ("auth".to_owned(), "../auth-worker".to_owned())
+        );
+        assert!(parse_key_value("auth").is_err());
+        assert!(parse_key_value("=../auth-worker").is_err());
+    }
 }
@@ -0,0 +1,24 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::Output;
+
+use anyhow::Result;
+
+pub fn diff(output: Option<String>, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+    let deployments = manifest.get_deployments(cli_params.environment())?;
+
+    let output = if output.as_deref() == Some("json") {
+        Output::Json
+    } else {
+        Output::PlainText
+    };
+    commands::publish::diff(&user, &target, &deployments)?.print(output);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum DispatchNamespace {
+    /// Create a dispatch namespace to upload user workers into
+    Create {
+        /// The name of the namespace
+        #[structopt(index = 1)]
+        name: String,
+    },
+    /// List the dispatch namespaces of your account (outputs json)
+    List,
+    /// Delete a dispatch namespace and the user workers in it
+    Delete {
+        /// The name of the namespace
+        #[structopt(index = 1)]
+        name: String,
+    },
+}
+
+pub fn dispatch_namespace(namespace: DispatchNamespace, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+
+    match namespace {
+        DispatchNamespace::Create { name } => {
+            commands::dispatch_namespace::create(&user, &target, &name)
+        }
+        DispatchNamespace::List => commands::dispatch_namespace::list(&user, &target),
+        DispatchNamespace::Delete { name } => {
+            commands::dispatch_namespace::delete(&user, &target, &name)
+        }
+    }
+}
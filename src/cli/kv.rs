@@ -1,8 +1,10 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
+use super::tail::parse_duration;
 use super::Cli;
 use crate::commands;
-use crate::commands::kv::key::{parse_metadata, KVMetaData};
+use crate::commands::kv::key::{now, parse_metadata, validate_expiration, KVMetaData};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
 
 use anyhow::{anyhow, Result};
@@ -20,14 +22,53 @@ pub enum KvNamespace {
         /// Applies the command to the preview namespace
         #[structopt(name = "preview", long)]
         preview: bool,
+        /// Adds the namespace to your configuration file instead of printing what to add
+        #[structopt(name = "update-config", long)]
+        update_config: bool,
     },
     /// Delete namespace
     Delete {
         #[structopt(flatten)]
         namespace: Namespace,
     },
-    /// List all namespaces on your Cloudflare account
-    List,
+    /// List all namespaces on your Cloudflare account, with their bindings in your configuration file
+    List {
+        /// Only lists the namespaces whose title matches this glob, like "my-worker-*"
+        #[structopt(long)]
+        title: Option<String>,
+    },
+    /// Change the title of a namespace
+    Rename {
+        /// The ID of the namespace to rename
+        #[structopt(index = 1)]
+        id: String,
+
+        /// The new title of the namespace
+        #[structopt(index = 2)]
+        title: String,
+    },
+    /// Copy the key-value pairs of a namespace to another namespace
+    Copy {
+        /// The ID of the namespace to copy from
+        #[structopt(long)]
+        from: String,
+
+        /// The ID of the namespace to copy to
+        #[structopt(long)]
+        to: String,
+
+        /// The account of the namespace to copy to, when it's on another account you have access to
+        #[structopt(name = "to-account-id", long)]
+        to_account_id: Option<String>,
+
+        /// Only copies the keys starting with this prefix
+        #[structopt(long, short = "p")]
+        prefix: Option<String>,
+
+        /// Deletes the keys of the namespace copied to that aren't in the namespace copied from
+        #[structopt(name = "delete-missing", long)]
+        delete_missing: bool,
+    },
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -80,9 +121,14 @@ pub enum KvKey {
         #[structopt(name = "metadata", long, short = "m")]
         metadata: Option<String>,
 
-        /// The value passed in is a path to a file; open and upload its contents
+        /// The value passed in is a path to a file, whose contents are uploaded as they are.
+        /// This is the only way to put a value that isn't text
         #[structopt(name = "path", long, short = "p")]
         path: bool,
+
+        /// The value is encoded as base64, and is decoded before it's uploaded
+        #[structopt(long)]
+        base64: bool,
     },
     /// Get a key's value from a namespace
     Get {
@@ -92,6 +138,18 @@ pub enum KvKey {
         /// Key whose value to get
         #[structopt(name = "key", index = 1)]
         key: String,
+
+        /// Also prints the metadata of the key, to stderr
+        #[structopt(name = "show-metadata", long)]
+        show_metadata: bool,
+
+        /// Writes the value to a file as it is, instead of printing it
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+
+        /// Prints the value encoded as base64, so a value that isn't text can be printed
+        #[structopt(long, conflicts_with = "out")]
+        base64: bool,
     },
     /// Delete a key and its value from a namespace
     Delete {
@@ -99,8 +157,33 @@ pub enum KvKey {
         namespace: Namespace,
 
         /// Key whose value to get
-        #[structopt(name = "key", index = 1)]
-        key: String,
+        #[structopt(name = "key", index = 1, required_unless = "prefix")]
+        key: Option<String>,
+
+        /// Deletes every key starting with this prefix instead of a single key
+        #[structopt(name = "prefix", long, short = "p", conflicts_with = "key")]
+        prefix: Option<String>,
+
+        /// Deletes without asking for confirmation
+        #[structopt(long, short = "f")]
+        force: bool,
+    },
+    /// Poll a key, or the keys starting with a prefix, and print their changes
+    Watch {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// Key to watch
+        #[structopt(name = "key", index = 1, required_unless = "prefix")]
+        key: Option<String>,
+
+        /// Watches every key starting with this prefix instead of a single key
+        #[structopt(name = "prefix", long, short = "p", conflicts_with = "key")]
+        prefix: Option<String>,
+
+        /// How long to wait between polls, like 30s, 5m or 1h. At least 5s
+        #[structopt(long, default_value = "5s", parse(try_from_str = parse_duration))]
+        interval: Duration,
     },
     /// List all keys in a namespace. Produces JSON output
     List {
@@ -110,6 +193,18 @@ pub enum KvKey {
         /// The prefix for filtering listed keys
         #[structopt(name = "prefix", long, short = "p")]
         prefix: Option<String>,
+
+        /// Lists a single page of at most this many keys, from 10 to 1000, instead of every key
+        #[structopt(name = "limit", long, short = "l")]
+        limit: Option<u32>,
+
+        /// Starts listing at the cursor printed after the last page
+        #[structopt(name = "cursor", long)]
+        cursor: Option<String>,
+
+        /// Includes the metadata of every key
+        #[structopt(name = "show-metadata", long)]
+        show_metadata: bool,
     },
 }
 
@@ -124,6 +219,10 @@ pub enum KvBulk {
         /// The JSON file of key-value pairs to upload, in form [{\"key\":..., \"value\":...}\"...]
         #[structopt(index = 1)]
         path: PathBuf,
+
+        /// How many batches of key-value pairs are uploaded at the same time
+        #[structopt(long, default_value = "4")]
+        concurrency: usize,
     },
     /// Delete multiple keys and their values from a namespace
     Delete {
@@ -134,16 +233,34 @@ pub enum KvBulk {
         #[structopt(index = 1)]
         path: PathBuf,
     },
+    /// Download every key-value pair of a namespace to a JSON file that `kv:bulk put` can upload
+    Export {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// The JSON file to write the key-value pairs to
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+
+        /// Only exports the keys starting with this prefix
+        #[structopt(long, short = "p")]
+        prefix: Option<String>,
+    },
 }
 
 pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
-    let env = cli_params.environment.as_deref();
+    let env = cli_params.environment();
 
     match namespace {
-        KvNamespace::Create { binding, preview } => {
-            commands::kv::namespace::create(&manifest, preview, env, &user, &binding)
+        KvNamespace::Create {
+            binding,
+            preview,
+            update_config,
+        } => {
+            let config_path = Some(cli_params.config.as_path()).filter(|_| update_config);
+            commands::kv::namespace::create(&manifest, preview, env, &user, &binding, config_path)
         }
         KvNamespace::Delete { namespace } => {
             let target = manifest.get_target(env, namespace.preview)?;
@@ -156,9 +273,32 @@ pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
             };
             commands::kv::namespace::delete(&target, &user, &id)
         }
-        KvNamespace::List => {
+        KvNamespace::List { title } => {
+            let target = manifest.get_target(env, false)?;
+            commands::kv::namespace::list(&manifest, env, &target, &user, title.as_deref())
+        }
+        KvNamespace::Rename { id, title } => {
+            commands::kv::namespace::rename(&manifest, env, &user, &id, &title)
+        }
+        KvNamespace::Copy {
+            from,
+            to,
+            to_account_id,
+            prefix,
+            delete_missing,
+        } => {
             let target = manifest.get_target(env, false)?;
-            commands::kv::namespace::list(&target, &user)
+            commands::kv::namespace::copy(
+                &target,
+                &user,
+                commands::kv::namespace::NamespaceCopy {
+                    from: &from,
+                    to: &to,
+                    to_account_id,
+                    prefix: prefix.as_deref(),
+                    delete_missing,
+                },
+            )
         }
     }
 }
@@ -166,7 +306,7 @@ pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
 pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
-    let env = cli_params.environment.as_deref();
+    let env = cli_params.environment();
 
     let target_and_namespace = |namespace: Namespace| -> Result<(_, _)> {
         let target = manifest.get_target(env, namespace.preview)?;
@@ -181,19 +321,38 @@ pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
     };
 
     match key {
-        KvKey::Get { namespace, key } => {
+        KvKey::Get {
+            namespace,
+            key,
+            show_metadata,
+            out,
+            base64,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::get(&target, &user, &namespace_id, &key)
+            commands::kv::key::get(
+                &target,
+                &user,
+                &namespace_id,
+                &key,
+                commands::kv::key::GetOptions {
+                    show_metadata,
+                    out: out.as_deref(),
+                    base64,
+                },
+            )
         }
         KvKey::Put {
             namespace,
             key,
             value,
-            path: is_file,
+            path,
+            base64,
             expiration_ttl,
             expiration,
             metadata,
         } => {
+            validate_expiration(expiration, expiration_ttl, now())
+                .map_err(|e| anyhow!("Can't put {}, {}", key, e))?;
             let (target, namespace_id) = target_and_namespace(namespace)?;
             let expiration = expiration.as_ref().map(ToString::to_string);
             let expiration_ttl = expiration_ttl.as_ref().map(ToString::to_string);
@@ -207,20 +366,64 @@ pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
                     namespace_id,
                     key,
                     value,
-                    is_file,
+                    is_file: path,
+                    base64,
                     expiration,
                     expiration_ttl,
                     metadata,
                 },
             )
         }
-        KvKey::Delete { namespace, key } => {
+        KvKey::Delete {
+            namespace,
+            key,
+            prefix,
+            force,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::delete(&target, &user, &namespace_id, &key)
+            match (key, prefix) {
+                (_, Some(prefix)) => {
+                    commands::kv::key::delete_prefix(&target, &user, &namespace_id, &prefix, force)
+                }
+                (Some(key), None) => {
+                    commands::kv::key::delete(&target, &user, &namespace_id, &key, force)
+                }
+                (None, None) => unreachable!("structopt requires a key or a prefix"),
+            }
         }
-        KvKey::List { namespace, prefix } => {
+        KvKey::Watch {
+            namespace,
+            key,
+            prefix,
+            interval,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::list(&target, &user, &namespace_id, prefix.as_deref())
+            let watched = match (&key, &prefix) {
+                (_, Some(prefix)) => commands::kv::key::Watched::Prefix(prefix),
+                (Some(key), None) => commands::kv::key::Watched::Key(key),
+                (None, None) => unreachable!("structopt requires a key or a prefix"),
+            };
+            commands::kv::key::watch(&target, &user, &namespace_id, watched, interval)
+        }
+        KvKey::List {
+            namespace,
+            prefix,
+            limit,
+            cursor,
+            show_metadata,
+        } => {
+            let (target, namespace_id) = target_and_namespace(namespace)?;
+            commands::kv::key::list(
+                &target,
+                &user,
+                &namespace_id,
+                commands::kv::key::ListOptions {
+                    prefix: prefix.as_deref(),
+                    limit,
+                    cursor: cursor.as_deref(),
+                    show_metadata,
+                },
+            )
         }
     }
 }
@@ -229,7 +432,7 @@ pub fn kv_bulk(bulk: KvBulk, cli_params: &Cli) -> Result<()> {
     // Get environment and bindings
     let manifest = Manifest::new(&cli_params.config)?;
     let user = GlobalUser::new()?;
-    let env = cli_params.environment.as_deref();
+    let env = cli_params.environment();
 
     let target_and_namespace = |namespace: Namespace| -> Result<(_, _)> {
         let target = manifest.get_target(env, namespace.preview)?;
@@ -244,13 +447,57 @@ pub fn kv_bulk(bulk: KvBulk, cli_params: &Cli) -> Result<()> {
     };
 
     match bulk {
-        KvBulk::Put { namespace, path } => {
+        KvBulk::Put {
+            namespace,
+            path,
+            concurrency,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::bulk::put(&target, &user, &namespace_id, &path)
+            commands::kv::bulk::put(&target, &user, &namespace_id, &path, concurrency)
         }
         KvBulk::Delete { namespace, path } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
             commands::kv::bulk::delete(&target, &user, &namespace_id, &path)
         }
+        KvBulk::Export {
+            namespace,
+            out,
+            prefix,
+        } => {
+            let (target, namespace_id) = target_and_namespace(namespace)?;
+            commands::kv::bulk::export(&target, &user, &namespace_id, prefix.as_deref(), &out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Command;
+
+    fn put(args: &[&str]) -> (String, String, bool) {
+        let args = ["wrangler", "kv:key", "put", "--namespace-id", "abc"]
+            .iter()
+            .chain(args);
+        match Cli::from_iter(args).command {
+            Command::KvKey(KvKey::Put {
+                key, value, path, ..
+            }) => (key, value, path),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn it_puts_files_passed_with_path() {
+        let put_file = ("key".to_owned(), "image.png".to_owned(), true);
+        assert_eq!(put(&["key", "--path", "image.png"]), put_file);
+        assert_eq!(put(&["key", "image.png", "--path"]), put_file);
+        // the flag doesn't take the key as its value
+        assert_eq!(put(&["--path", "key", "image.png"]), put_file);
+
+        assert_eq!(
+            put(&["key", "hello"]),
+            ("key".to_owned(), "hello".to_owned(), false)
+        );
     }
 }
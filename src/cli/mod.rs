@@ -1,21 +1,30 @@
 pub mod build;
 pub mod config;
+pub mod delete;
 pub mod dev;
+pub mod diff;
+pub mod dispatch_namespace;
 pub mod generate;
 pub mod init;
 pub mod kv;
 pub mod preview;
 pub mod publish;
+pub mod rollback;
 pub mod route;
 pub mod secret;
 pub mod subdomain;
 pub mod tail;
+pub mod triggers;
+pub mod versions;
 pub mod whoami;
 
 pub mod exec {
     pub use super::build::build;
     pub use super::config::configure;
+    pub use super::delete::delete;
     pub use super::dev::dev;
+    pub use super::diff::diff;
+    pub use super::dispatch_namespace::dispatch_namespace;
     pub use super::generate::generate;
     pub use super::init::init;
     pub use super::kv::kv_bulk;
@@ -23,19 +32,18 @@ pub mod exec {
     pub use super::kv::kv_namespace;
     pub use super::preview::preview;
     pub use super::publish::publish;
+    pub use super::rollback::{deployments, rollback};
     pub use super::route::route;
     pub use super::secret::secret;
     pub use super::subdomain::subdomain;
     pub use super::tail::tail;
+    pub use super::triggers::triggers;
+    pub use super::versions::versions;
     pub use super::whoami::whoami;
 }
 
-use std::net::IpAddr;
 use std::path::PathBuf;
-use std::str::FromStr;
 
-use crate::commands::dev::Protocol;
-use crate::commands::tail::websocket::TailFormat;
 use crate::preview::HttpMethod;
 use crate::settings::toml::migrations::{
     DurableObjectsMigration, Migration, MigrationConfig, Migrations, RenameClass, TransferClass,
@@ -63,14 +71,21 @@ pub struct Cli {
     #[structopt(long, short = "c", default_value = "wrangler.toml", global = true)]
     pub config: PathBuf,
 
-    /// Environment to perform a command on.
-    #[structopt(name = "env", long, short = "e", global = true)]
-    pub environment: Option<String>,
+    /// Environment to perform a command on. Can be given more than once to `wrangler publish`
+    #[structopt(name = "env", long, short = "e", global = true, number_of_values = 1)]
+    pub environments: Vec<String>,
 
     #[structopt(subcommand)]
     pub command: Command,
 }
 
+impl Cli {
+    /// The environment to perform a command on, for the commands that take just one.
+    pub fn environment(&self) -> Option<&str> {
+        self.environments.first().map(String::as_str)
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub enum Command {
     /// Interact with your Workers KV Namespaces
@@ -85,10 +100,14 @@ pub enum Command {
     #[structopt(name = "kv:bulk", setting = AppSettings::SubcommandRequiredElseHelp)]
     KvBulk(kv::KvBulk),
 
-    /// List or delete worker routes.
+    /// List, add or delete worker routes.
     #[structopt(name = "route", setting = AppSettings::SubcommandRequiredElseHelp)]
     Route(route::Route),
 
+    /// Manage the dispatch namespaces user workers are uploaded into
+    #[structopt(name = "dispatch-namespace", setting = AppSettings::SubcommandRequiredElseHelp)]
+    DispatchNamespace(dispatch_namespace::DispatchNamespace),
+
     /// Generate a secret that can be referenced in the worker script
     #[structopt(name = "secret", setting = AppSettings::SubcommandRequiredElseHelp)]
     Secret(secret::Secret),
@@ -154,43 +173,56 @@ pub enum Command {
     },
 
     /// Start a local server for developing your worker
-    Dev {
-        /// Host to forward requests to, defaults to the zone of project or to
-        /// tutorial.cloudflareworkers.com if unauthenticated.
-        #[structopt(long, short = "h")]
-        host: Option<String>,
-
-        /// IP to listen on. Defaults to 127.0.0.1
-        #[structopt(long, short = "i")]
-        ip: Option<IpAddr>,
-
-        /// Port to listen on. Defaults to 8787
-        #[structopt(long, short = "p")]
-        port: Option<u16>,
-
-        /// Sets the protocol on which the wrangler dev listens, by default this is http
-        /// but can be set to https
-        #[structopt(name = "local-protocol")]
-        local_protocol: Option<Protocol>,
-
-        /// Sets the protocol on which requests are sent to the host, by default this is https
-        /// but can be set to http
-        #[structopt(name = "upstream-protocol")]
-        upstream_protocol: Option<Protocol>,
-    },
+    Dev(dev::Dev),
 
     /// Publish your worker to the orange cloud
     #[structopt(name = "publish")]
-    Publish {
-        /// [deprecated] alias of wrangler publish
-        #[structopt(long, hidden = true)]
-        release: bool,
+    Publish(publish::Publish),
 
-        #[structopt(possible_value = "json")]
+    /// Inspect or update the cron triggers of your deployed worker
+    #[structopt(name = "triggers", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Triggers(triggers::Triggers),
+
+    /// Manage the versions of your worker that requests are split between
+    #[structopt(name = "versions", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Versions(versions::Versions),
+
+    /// List the deployments of your worker, newest first
+    #[structopt(name = "deployments")]
+    Deployments {
+        #[structopt(long, possible_value = "json")]
         output: Option<String>,
+    },
 
-        #[structopt(flatten)]
-        migration: AdhocMigration,
+    /// Re-activate a previous deployment of your worker, without rebuilding it
+    #[structopt(name = "rollback")]
+    Rollback {
+        /// The deployment to roll back to, defaults to the one before the live deployment
+        #[structopt(name = "deployment-id", index = 1)]
+        deployment_id: Option<String>,
+    },
+
+    /// Show how your worker differs from the deployed one, such as changes made in the dashboard
+    #[structopt(name = "diff")]
+    Diff {
+        #[structopt(long, possible_value = "json")]
+        output: Option<String>,
+    },
+
+    /// Delete your worker from your account
+    #[structopt(name = "delete")]
+    Delete {
+        /// Also delete the routes in your zone that point to the worker
+        #[structopt(long)]
+        routes: bool,
+
+        /// Also delete the Durable Objects namespaces of the worker, and all of their objects
+        #[structopt(name = "durable-objects", long)]
+        durable_objects: bool,
+
+        /// Delete without asking for confirmation
+        #[structopt(long, short = "y")]
+        yes: bool,
     },
 
     /// Authenticate Wrangler with a Cloudflare API Token or Global API Key
@@ -219,55 +251,7 @@ pub enum Command {
 
     /// View a stream of logs from a published worker
     #[structopt(name = "tail")]
-    Tail {
-        /// Name of the worker to tail
-        #[structopt(index = 1)]
-        name: Option<String>,
-
-        /// Output format for log messages
-        #[structopt(long, short = "f", default_value = "json", possible_values = &["json", "pretty"])]
-        format: TailFormat,
-
-        /// Stops the tail after receiving the first log (useful for testing)
-        #[structopt(long)]
-        once: bool,
-
-        /// Adds a sampling rate (0.01 for 1%)
-        #[structopt(long = "sampling-rate", default_value = "1")]
-        sampling_rate: f64,
-
-        /// Filter by invocation status
-        #[structopt(long, possible_values = &["ok", "error", "canceled"])]
-        status: Vec<String>,
-
-        /// Filter by HTTP method
-        #[structopt(long)]
-        method: Vec<String>,
-
-        /// Filter by HTTP header
-        #[structopt(long)]
-        header: Vec<String>,
-
-        /// Filter by IP address ("self" to filter your own IP address)
-        #[structopt(long = "ip-address", parse(try_from_str = parse_ip_address))]
-        ip_address: Vec<String>,
-
-        /// Filter by a text match in console.log messages
-        #[structopt(long)]
-        search: Option<String>,
-
-        /// Set the URL to forward log messages
-        #[structopt(hidden = true)]
-        url: Option<Url>,
-
-        /// Deprecated, no longer used.
-        #[structopt(hidden = true, long = "port", short = "p")]
-        tunnel_port: Option<u16>,
-
-        /// Deprecated, no longer used.
-        #[structopt(hidden = true, long = "metrics")]
-        metrics_port: Option<u16>,
-    },
+    Tail(tail::Tail),
 
     /// Authenticate wrangler with your Cloudflare username and password
     #[structopt(name = "login")]
@@ -356,16 +340,6 @@ impl AdhocMigration {
     }
 }
 
-fn parse_ip_address(input: &str) -> Result<String, anyhow::Error> {
-    match input {
-        "self" => Ok(String::from("self")),
-        address => match IpAddr::from_str(address) {
-            Ok(_) => Ok(address.to_owned()),
-            Err(err) => anyhow::bail!("{}: {}", err, input),
-        },
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -415,7 +389,7 @@ mod tests {
         ])
         .command;
 
-        if let Command::Publish { migration, .. } = command {
+        if let Command::Publish(publish::Publish { migration, .. }) = command {
             assert_eq!(
                 migration.into_migration_config(),
                 Some(MigrationConfig {
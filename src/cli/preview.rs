@@ -16,7 +16,7 @@ pub fn preview(
 ) -> Result<()> {
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), true)?;
+    let target = manifest.get_target(cli_params.environment(), true)?;
 
     // the preview command can be called with or without a Global User having been config'd
     // so we convert this Result into an Option
@@ -1,22 +1,97 @@
 use super::Cli;
-use super::{AdhocMigration, Migrations};
+use super::{AdhocMigration, MigrationConfig, Migrations};
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
-use crate::terminal::message::{Message, Output, StdOut};
+use crate::deploy::DeploymentSet;
+use crate::settings::{
+    global_user::GlobalUser,
+    toml::{Annotations, Manifest, TargetType},
+};
+use crate::sites;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
 use crate::terminal::styles;
 
+use std::path::PathBuf;
+
 use anyhow::Result;
+use serde_json::Value;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct Publish {
+    /// [deprecated] alias of wrangler publish
+    #[structopt(long, hidden = true)]
+    pub release: bool,
+
+    /// Print the published name, version, URLs and sizes as a JSON document on stdout
+    #[structopt(long, possible_value = "json")]
+    pub output: Option<String>,
+
+    /// [deprecated] positional form of --output
+    #[structopt(name = "legacy-output", possible_value = "json", hidden = true)]
+    pub legacy_output: Option<String>,
+
+    /// Build the worker and show what publishing it would change, without uploading it
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Publish to every environment in wrangler.toml, one after another
+    #[structopt(long = "all-envs")]
+    pub all_envs: bool,
+
+    /// Publish every worker with a wrangler.toml under this directory, publishing the workers
+    /// bound with [[services]] before the workers binding them
+    #[structopt(long, conflicts_with_all = &["dry-run", "all-envs", "percentage", "assets", "dispatch-namespace"])]
+    pub workspace: bool,
+
+    /// Build the worker and check its size against the size limit, without uploading it
+    #[structopt(long = "size-only", conflicts_with = "dry-run")]
+    pub size_only: bool,
+
+    /// Upload a new version and send this percentage of requests to it, leaving the rest
+    /// with the live version until `wrangler versions promote`
+    #[structopt(long, parse(try_from_str = commands::versions::parse_percentage), conflicts_with_all = &["dry-run", "size-only"])]
+    pub percentage: Option<f64>,
+
+    /// A message describing what changed, shown in `wrangler deployments`
+    #[structopt(long, short = "m")]
+    pub message: Option<String>,
+
+    /// A tag for the uploaded version, such as the version of your project
+    #[structopt(long)]
+    pub tag: Option<String>,
+
+    /// Publish the files of a directory, served by a generated worker instead of your own
+    #[structopt(long, parse(from_os_str), conflicts_with = "percentage")]
+    pub assets: Option<PathBuf>,
+
+    /// Keep the vars of the deployed worker that aren't in your configuration file, such as
+    /// the ones set in the dashboard, instead of removing them
+    #[structopt(long = "keep-vars")]
+    pub keep_vars: bool,
+
+    /// Upload the script without changing its routes, custom domains or workers.dev
+    /// subdomain, for when traffic is moved to it separately
+    #[structopt(long = "no-routes", conflicts_with = "dispatch-namespace")]
+    pub no_routes: bool,
 
-pub fn publish(
-    release: bool,
-    output: Option<String>,
-    migration: AdhocMigration,
-    cli_params: &Cli,
-) -> Result<()> {
+    /// Minify the built script before uploading it, to shrink it under the size limit
+    #[structopt(long)]
+    pub minify: bool,
+
+    /// Upload the worker into a dispatch namespace as a user worker, instead of to the
+    /// scripts of the account
+    #[structopt(long = "dispatch-namespace", conflicts_with_all = &["dry-run", "percentage"])]
+    pub dispatch_namespace: Option<String>,
+
+    #[structopt(flatten)]
+    pub migration: AdhocMigration,
+}
+
+pub fn publish(args: Publish, cli_params: &Cli) -> Result<()> {
     log::info!("Getting User settings");
     let user = GlobalUser::new()?;
 
-    if release {
+    if args.release {
         StdOut::warn(&format!(concat!(
             "{} is deprecated and behaves exactly the same as {}.\n",
             "See {} for more information."),
@@ -26,21 +101,209 @@ pub fn publish(
         ));
     }
 
+    let output = if args.output.as_deref().or(args.legacy_output.as_deref()) == Some("json") {
+        Output::Json
+    } else {
+        Output::PlainText
+    };
+
+    if args.workspace {
+        return commands::workspace::publish(
+            &cli_params.config,
+            cli_params.environment(),
+            &workspace_flags(&args),
+            output,
+        );
+    }
+
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let migration = args.migration.clone().into_migration_config();
 
-    if let Some(migration) = migration.into_migration_config() {
+    let environments = environments(&manifest, args.all_envs, cli_params)?;
+    if environments.len() == 1 {
+        let document =
+            publish_environment(&user, &manifest, environments[0], migration, &args, output)?;
+        if let Some(document) = document {
+            StdOut::as_json(&document);
+        }
+        return Ok(());
+    }
+
+    // each environment is built and published to in turn, stopping at the first that fails
+    let mut published = vec![];
+    let mut documents = vec![];
+    for (i, environment) in environments.iter().enumerate() {
+        let environment = environment.expect("only the top level of wrangler.toml is unnamed");
+        StdErr::working(&format!("Publishing to {}", styles::highlight(environment)));
+        let result = publish_environment(
+            &user,
+            &manifest,
+            Some(environment),
+            migration.clone(),
+            &args,
+            output,
+        );
+        let document = match result {
+            Ok(document) => document,
+            Err(e) => {
+                let skipped = environments[i + 1..]
+                    .iter()
+                    .filter_map(|environment| *environment)
+                    .collect::<Vec<_>>();
+                if !published.is_empty() {
+                    StdErr::info(&format!("Published to {}", published.join(", ")));
+                }
+                if !skipped.is_empty() {
+                    StdErr::info(&format!("Skipped {}", skipped.join(", ")));
+                }
+                anyhow::bail!("Failed to publish to {}: {}", environment, e)
+            }
+        };
+        published.push(environment);
+        documents.extend(document);
+    }
+    StdErr::success(&format!("Published to {}", published.join(", ")));
+    // the documents of every environment are printed together, as one document
+    if output == Output::Json {
+        StdOut::as_json(&documents);
+    }
+    Ok(())
+}
+
+/// The flags of `wrangler publish` that are passed on to each worker of a `--workspace`.
+fn workspace_flags(args: &Publish) -> Vec<String> {
+    let mut flags = vec![];
+    if let Some(message) = &args.message {
+        flags.push("--message".to_owned());
+        flags.push(message.clone());
+    }
+    if let Some(tag) = &args.tag {
+        flags.push("--tag".to_owned());
+        flags.push(tag.clone());
+    }
+    if args.keep_vars {
+        flags.push("--keep-vars".to_owned());
+    }
+    if args.no_routes {
+        flags.push("--no-routes".to_owned());
+    }
+    if args.minify {
+        flags.push("--minify".to_owned());
+    }
+    flags
+}
+
+/// The environments to publish to, where `None` is the top level of wrangler.toml.
+fn environments<'a>(
+    manifest: &'a Manifest,
+    all_envs: bool,
+    cli_params: &'a Cli,
+) -> Result<Vec<Option<&'a str>>> {
+    if !all_envs {
+        if cli_params.environments.is_empty() {
+            return Ok(vec![None]);
+        }
+        return Ok(cli_params
+            .environments
+            .iter()
+            .map(|env| Some(env.as_str()))
+            .collect());
+    }
+
+    if !cli_params.environments.is_empty() {
+        anyhow::bail!("--all-envs publishes to every environment, it can't be used with --env");
+    }
+    let mut environments = manifest
+        .env
+        .iter()
+        .flat_map(|env| env.keys())
+        .map(|env| Some(env.as_str()))
+        .collect::<Vec<_>>();
+    if environments.is_empty() {
+        anyhow::bail!("--all-envs was passed, but there are no [env] sections in wrangler.toml");
+    }
+    environments.sort();
+    Ok(environments)
+}
+
+/// Publishes to an environment, returning the JSON document describing it with `--output json`.
+fn publish_environment(
+    user: &GlobalUser,
+    manifest: &Manifest,
+    environment: Option<&str>,
+    migration: Option<MigrationConfig>,
+    args: &Publish,
+    output: Output,
+) -> Result<Option<Value>> {
+    let mut target = manifest.get_target(environment, false)?;
+    if let Some(assets) = &args.assets {
+        if target.site.is_some() {
+            anyhow::bail!("--assets publishes a directory as a site, so it can't be used when wrangler.toml already has a [site]");
+        }
+        if target.target_type != TargetType::JavaScript || target.build.is_some() {
+            StdErr::warn(
+                "--assets publishes a generated worker, so your project isn't built or published",
+            );
+        }
+        target.target_type = TargetType::JavaScript;
+        target.build = None;
+        target.site = Some(sites::assets::site(assets)?);
+    }
+    target.dispatch_namespace = args.dispatch_namespace.clone();
+    if args.keep_vars {
+        target.keep_vars = true;
+    }
+    if args.minify {
+        target.minify = true;
+    }
+    target.annotations = Annotations {
+        message: args.message.clone(),
+        tag: args.tag.clone(),
+    };
+
+    if let Some(migration) = migration {
+        if target.migrations.is_some() {
+            anyhow::bail!("This project has [[migrations]] in wrangler.toml, add the migration there instead of passing it to wrangler publish");
+        }
         target.migrations = Some(Migrations {
             migrations: vec![migration],
+            deployed_tag: None,
         });
     }
 
-    let output = if output.as_deref() == Some("json") {
-        Output::Json
+    if args.size_only {
+        commands::publish::size_only(&target)?;
+        return Ok(None);
+    }
+    // user workers are only reached through the dispatcher, not routes of their own
+    let deploy_config = if target.dispatch_namespace.is_some() {
+        DeploymentSet::new()
+    } else if args.no_routes {
+        manifest.get_unrouted_deployments(environment)?
     } else {
-        Output::PlainText
+        manifest.get_deployments(environment)?
     };
-    let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
-    commands::publish(&user, &mut target, deploy_config, output)
+    let document = if args.dry_run {
+        serde_json::to_value(commands::publish::dry_run(
+            user,
+            &target,
+            &deploy_config,
+            output,
+        )?)?
+    } else if let Some(percentage) = args.percentage {
+        serde_json::to_value(commands::publish::gradual(
+            user,
+            &mut target,
+            deploy_config,
+            percentage,
+            output,
+        )?)?
+    } else {
+        serde_json::to_value(commands::publish(user, &mut target, deploy_config, output)?)?
+    };
+    match output {
+        Output::Json => Ok(Some(document)),
+        Output::PlainText => Ok(None),
+    }
 }
@@ -0,0 +1,33 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::Output;
+
+use anyhow::Result;
+
+pub fn rollback(deployment_id: Option<String>, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+
+    commands::deployments::rollback(&user, &target, deployment_id)
+}
+
+pub fn deployments(output: Option<String>, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+
+    let output = if output.as_deref() == Some("json") {
+        Output::Json
+    } else {
+        Output::PlainText
+    };
+    commands::deployments::list(&user, &target, output)
+}
@@ -10,6 +10,15 @@ use structopt::StructOpt;
 pub enum Route {
     /// List all routes associated with a zone (outputs json)
     List,
+    /// Route requests matching a pattern to the worker in your configuration file
+    Add {
+        /// The pattern of the route, like example.com/api/*
+        #[structopt(index = 1)]
+        pattern: String,
+        /// Take over the route if it is already routed to another worker
+        #[structopt(long, short = "f")]
+        force: bool,
+    },
     /// Delete a route by ID
     Delete {
         /// The ID associated with the route you want to delete (find using `wrangler route list`)
@@ -22,7 +31,7 @@ pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
     let zone_id = manifest
-        .get_environment(cli_params.environment.as_deref())?
+        .get_environment(cli_params.environment())?
         .and_then(|e| e.zone_id.as_ref())
         .or_else(|| manifest.zone_id.as_ref());
 
@@ -34,6 +43,10 @@ pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
 
     match route {
         Route::List => commands::route::list(zone_id, &user),
+        Route::Add { pattern, force } => {
+            let script = manifest.worker_name(cli_params.environment());
+            commands::route::add(zone_id, &user, &pattern, &script, force)
+        }
         Route::Delete { route_id } => commands::route::delete(zone_id, &user, &route_id),
     }
 }
@@ -1,9 +1,23 @@
+use std::path::PathBuf;
+
 use super::Cli;
 use crate::commands;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
 
 use anyhow::Result;
 use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct Environments {
+    /// The environment to compare from, or the top level when it's left out
+    #[structopt(long)]
+    from: Option<String>,
+
+    /// The environment to compare to, or the top level when it's left out
+    #[structopt(long)]
+    to: Option<String>,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 #[structopt(rename_all = "lower")]
 pub enum Secret {
@@ -11,6 +25,15 @@ pub enum Secret {
     Put {
         #[structopt(long, short = "n", index = 1)]
         name: String,
+
+        /// Reads the secret from a file, keeping its newlines as they are
+        #[structopt(name = "from-file", long, parse(from_os_str))]
+        from_file: Option<PathBuf>,
+
+        /// Removes the newline at the end of the secret read from --from-file or piped into
+        /// stdin, such as the one `echo` adds. Both are otherwise uploaded as they are
+        #[structopt(name = "trim-newline", long)]
+        trim_newline: bool,
     },
     /// Delete a secret variable from a script
     Delete {
@@ -19,6 +42,26 @@ pub enum Secret {
     },
     /// List all secrets for a script
     List,
+    /// Create or update many secret variables at once, from a .env file or a JSON file
+    Bulk {
+        /// A .env file of NAME=value lines
+        #[structopt(index = 1, parse(from_os_str), required_unless = "json")]
+        path: Option<PathBuf>,
+
+        /// A JSON file of an object of secret names to their values
+        #[structopt(long, parse(from_os_str), conflicts_with = "path")]
+        json: Option<PathBuf>,
+    },
+    /// Show the names of the secrets one environment has and the other doesn't
+    Diff {
+        #[structopt(flatten)]
+        environments: Environments,
+    },
+    /// Prompt for the secrets one environment has and the other is missing, and upload them
+    Sync {
+        #[structopt(flatten)]
+        environments: Environments,
+    },
 }
 
 pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
@@ -27,10 +70,38 @@ pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
 
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
     match secret {
-        Secret::Put { name } => commands::secret::create_secret(&name, &user, &target),
+        Secret::Put {
+            name,
+            from_file,
+            trim_newline,
+        } => commands::secret::create_secret(
+            &name,
+            from_file.as_deref(),
+            trim_newline,
+            &user,
+            &target,
+        ),
         Secret::Delete { name } => commands::secret::delete_secret(&name, &user, &target),
         Secret::List => commands::secret::list_secrets(&user, &target),
+        Secret::Bulk { path, json } => {
+            let secrets = match (path, json) {
+                (_, Some(json)) => commands::secret::read_secrets(&json, true)?,
+                (Some(path), None) => commands::secret::read_secrets(&path, false)?,
+                (None, None) => unreachable!("structopt requires a .env or JSON file"),
+            };
+            commands::secret::bulk_secrets(secrets, &user, &target)
+        }
+        Secret::Diff { environments } => {
+            let from = manifest.get_target(environments.from.as_deref(), false)?;
+            let to = manifest.get_target(environments.to.as_deref(), false)?;
+            commands::secret::diff_secrets(&user, &from, &to)
+        }
+        Secret::Sync { environments } => {
+            let from = manifest.get_target(environments.from.as_deref(), false)?;
+            let to = manifest.get_target(environments.to.as_deref(), false)?;
+            commands::secret::sync_secrets(&user, &from, &to)
+        }
     }
 }
@@ -7,7 +7,7 @@ use anyhow::Result;
 pub fn subdomain(name: Option<String>, cli_params: &Cli) -> Result<()> {
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
 
     log::info!("Getting User settings");
     let user = GlobalUser::new()?;
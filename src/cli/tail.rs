@@ -1,69 +1,421 @@
+use std::convert::TryFrom;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use super::Cli;
 use crate::commands;
+use crate::commands::tail::dedupe::Dedupe;
+use crate::commands::tail::event::{Field, FIELDS};
 use crate::commands::tail::filter::*;
+use crate::commands::tail::forward::Forwarder;
+use crate::commands::tail::metrics::{self, Metrics};
+use crate::commands::tail::output::OutFile;
+use crate::commands::tail::stats::Stats;
 use crate::commands::tail::websocket::{TailFormat, TailOptions};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
 
 use anyhow::Result;
+use structopt::StructOpt;
 use url::Url;
 
-#[allow(clippy::too_many_arguments)]
-pub fn tail(
-    name: Option<String>,
-    url: Option<Url>,
-    format: TailFormat,
-    once: bool,
-    sampling_rate: f64,
-    outcomes: Vec<String>,
-    methods: Vec<String>,
-    headers: Vec<String>,
-    client_ips: Vec<String>,
-    search: Option<String>,
-    cli_params: &Cli,
-) -> Result<()> {
+#[derive(Debug, Clone, StructOpt)]
+pub struct Tail {
+    /// Name of the worker to tail
+    #[structopt(index = 1)]
+    pub name: Option<String>,
+
+    /// Name of another worker to tail, can be repeated to tail multiple workers at once
+    #[structopt(long = "name", short = "n")]
+    pub names: Vec<String>,
+
+    /// Output format for log messages, pretty in a terminal and json (one event per line) otherwise
+    #[structopt(long, short = "f", possible_values = &["json", "pretty"])]
+    pub format: Option<TailFormat>,
+
+    /// Only show these fields for each log message, separated by commas (e.g. url,status,logs)
+    #[structopt(long, use_delimiter = true, possible_values = FIELDS)]
+    pub fields: Vec<Field>,
+
+    /// Stops the tail after receiving the first log (useful for testing)
+    #[structopt(long)]
+    pub once: bool,
+
+    /// Stops the tail after a duration, in seconds or with a unit (e.g. 30s, 5m, 1h)
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub duration: Option<Duration>,
+
+    /// How often to send a keep-alive for the tail, in seconds
+    #[structopt(long = "heartbeat-interval", default_value = "60")]
+    pub heartbeat_interval: u64,
+
+    /// Adds a sampling rate (0.01 for 1%)
+    #[structopt(long = "sampling-rate", default_value = "1", parse(try_from_str = parse_sampling_rate))]
+    pub sampling_rate: f64,
+
+    /// Filter by invocation status
+    #[structopt(long, possible_values = &["ok", "error", "canceled"])]
+    pub status: Vec<String>,
+
+    /// Only show events that threw an exception
+    #[structopt(long = "exceptions-only")]
+    pub exceptions_only: bool,
+
+    /// Filter by the type of event
+    #[structopt(long = "event-type", default_value = "all", possible_values = &["fetch", "scheduled", "all"])]
+    pub event_type: String,
+
+    /// Filter by HTTP method
+    #[structopt(long)]
+    pub method: Vec<String>,
+
+    /// Filter by URL path, using a glob pattern (e.g. "/api/*")
+    #[structopt(long)]
+    pub path: Vec<String>,
+
+    /// Filter by HTTP header
+    #[structopt(long)]
+    pub header: Vec<String>,
+
+    /// Filter by IP address ("self" to filter your own IP address)
+    #[structopt(long = "ip-address", parse(try_from_str = parse_ip_address))]
+    pub ip_address: Vec<String>,
+
+    /// Filter by a text match in console.log messages
+    #[structopt(long)]
+    pub search: Option<String>,
+
+    /// Filter by a regular expression match in console.log messages and exceptions
+    #[structopt(long)]
+    pub grep: Option<String>,
+
+    /// Collapse identical console.log messages within this window into one line (pretty format only)
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub dedupe: Option<Duration>,
+
+    /// Show a summary of requests, errors and exceptions instead of each log message
+    #[structopt(long)]
+    pub stats: bool,
+
+    /// Also write log messages to a file, as newline-delimited JSON
+    #[structopt(long = "out-file", parse(from_os_str))]
+    pub out_file: Option<PathBuf>,
+
+    /// Rotate the output file once it grows past this size, in megabytes
+    #[structopt(long = "rotate-size", requires = "out-file", parse(try_from_str = parse_rotate_size))]
+    pub rotate_size: Option<u64>,
+
+    /// Also send each log message as JSON in a POST request to this URL
+    #[structopt(long)]
+    pub forward: Option<Url>,
+
+    /// Set the URL to forward log messages
+    #[structopt(hidden = true)]
+    pub url: Option<Url>,
+
+    /// Deprecated, no longer used.
+    #[structopt(hidden = true, long = "port", short = "p")]
+    pub tunnel_port: Option<u16>,
+
+    /// Serve Prometheus metrics about received events at http://localhost:<port>/metrics
+    #[structopt(long = "metrics")]
+    pub metrics_port: Option<u16>,
+}
+
+pub fn tail(args: Tail, cli_params: &Cli) -> Result<()> {
+    // Tail used to run a local log server behind a cloudflared tunnel. Events are now streamed
+    // directly over a WebSocket, so there is no port left to configure.
+    if args.tunnel_port.is_some() {
+        StdErr::warn(&format!(
+            "{} is deprecated and has no effect, since `wrangler tail` no longer runs a local server or tunnel.",
+            styles::highlight("--port"),
+        ));
+    }
+
+    if args.heartbeat_interval == 0 {
+        anyhow::bail!("--heartbeat-interval must be at least 1 second");
+    }
+
     let user = GlobalUser::new()?;
 
     // FIXME: If `name` is defined, allow the command to be run outside a `wrangler.toml` directory.
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
     let account_id = target.account_id.load()?.to_string();
-    let script_name = name.unwrap_or(target.name);
 
+    let mut script_names: Vec<String> = vec![];
+    for name in args.name.iter().chain(&args.names) {
+        if !script_names.contains(name) {
+            script_names.push(name.to_owned());
+        }
+    }
+    if script_names.is_empty() {
+        script_names.push(target.name);
+    }
+
+    let out_file = match &args.out_file {
+        Some(path) => Some(Arc::new(Mutex::new(OutFile::new(
+            path.to_owned(),
+            args.rotate_size,
+        )?))),
+        None => None,
+    };
+
+    // Humans get a readable stream, while pipes and files get one JSON object per line.
+    let format = match &args.format {
+        Some(format) => format.to_owned(),
+        None if console::user_attended() => TailFormat::Pretty,
+        None => TailFormat::Json,
+    };
+
+    let (forward, batcher) = match &args.forward {
+        Some(url) => {
+            let (forwarder, batcher) = Forwarder::new(url.to_owned())?;
+            (Some(forwarder), Some(batcher))
+        }
+        None => (None, None),
+    };
+
+    let stats = match args.stats {
+        true => Some(Arc::new(Mutex::new(Stats::new()))),
+        false => None,
+    };
+
+    let metrics = args
+        .metrics_port
+        .map(|_| Arc::new(Mutex::new(Metrics::default())));
+
+    let label = script_names.len() > 1;
+    let mut scripts = Vec::new();
+    for script_name in script_names {
+        let options = TailOptions {
+            once: args.once,
+            format: format.clone(),
+            filters: filters(&args),
+            event_filters: event_filters(&args)?,
+            out_file: out_file.clone(),
+            label,
+            forward: forward.clone(),
+            duration: args.duration,
+            stats: stats.clone(),
+            fields: args.fields.clone(),
+            heartbeat: Duration::from_secs(args.heartbeat_interval),
+            metrics: metrics.clone(),
+            dedupe: args.dedupe.map(Dedupe::new),
+            stderr: false,
+        };
+        scripts.push((script_name, options));
+    }
+    // The batcher sends its last batch once the tails drop their forwarders.
+    drop(forward);
+
+    let metrics_port = args.metrics_port;
+    let tail = commands::tail::run(user, account_id, scripts, args.url);
+    let tail = async move {
+        // The metrics are served on a task of their own, which can't stop the tails from being
+        // deleted, and the port is bound before any tail is created.
+        if let (Some(metrics), Some(port)) = (metrics, metrics_port) {
+            let server = metrics::bind(metrics, port)?;
+            tokio::spawn(async move {
+                if let Err(err) = server.await {
+                    log::warn!("Stopped serving tail metrics: {}", err);
+                }
+            });
+        }
+        let forwarding = batcher.map(|batcher| tokio::spawn(batcher.run()));
+        let result = tail.await;
+        if let Some(forwarding) = forwarding {
+            if let Err(err) = forwarding.await {
+                log::warn!("Failed to forward the last events: {}", err);
+            }
+        }
+        result
+    };
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(tail)?;
+
+    if let Some(Ok(mut stats)) = stats.as_ref().map(|stats| stats.lock()) {
+        stats.draw();
+    }
+    Ok(())
+}
+
+/// Builds the filters that are sent to the tail.
+fn filters(args: &Tail) -> Vec<Box<dyn TraceFilter>> {
     let mut filters: Vec<Box<dyn TraceFilter>> = vec![];
-    if !outcomes.is_empty() {
-        filters.push(Box::new(OutcomeFilter::from(outcomes)));
+    if !args.status.is_empty() {
+        filters.push(Box::new(OutcomeFilter::from(args.status.clone())));
+    } else if args.exceptions_only {
+        // Exceptions only happen in events with an error outcome.
+        filters.push(Box::new(OutcomeFilter::from(vec!["error".to_owned()])));
     }
-    if !methods.is_empty() {
-        filters.push(Box::new(MethodFilter::from(methods)));
+    if !args.method.is_empty() {
+        filters.push(Box::new(MethodFilter::from(args.method.clone())));
     }
-    if !client_ips.is_empty() {
-        filters.push(Box::new(ClientIpFilter::from(client_ips)));
+    if !args.ip_address.is_empty() {
+        filters.push(Box::new(ClientIpFilter::from(args.ip_address.clone())));
     }
-    for header in headers.into_iter() {
-        filters.push(Box::new(HeaderFilter::from(header)))
+    for header in args.header.iter() {
+        filters.push(Box::new(HeaderFilter::from(header.to_owned())))
     }
-    if let Some(query) = search {
-        filters.push(Box::new(QueryFilter::from(query)));
+    if let Some(query) = &args.search {
+        filters.push(Box::new(QueryFilter::from(query.to_owned())));
     };
-    if sampling_rate < 1.0 && sampling_rate > 0.0 {
-        filters.push(Box::new(SamplingRateFilter::from(sampling_rate))); // Should always be last
+    if args.sampling_rate < 1.0 {
+        filters.push(Box::new(SamplingRateFilter::from(args.sampling_rate))); // Should always be last
     };
+    filters
+}
+
+/// Builds the filters that are handled by wrangler.
+fn event_filters(args: &Tail) -> Result<Vec<Box<dyn EventFilter>>> {
+    let mut event_filters: Vec<Box<dyn EventFilter>> = vec![];
+    if !args.path.is_empty() {
+        event_filters.push(Box::new(PathFilter::try_from(args.path.clone())?));
+    }
+    if args.event_type != "all" {
+        event_filters.push(Box::new(EventTypeFilter::from(args.event_type.clone())));
+    }
+    if args.exceptions_only {
+        event_filters.push(Box::new(ExceptionFilter));
+    }
+    if let Some(pattern) = &args.grep {
+        event_filters.push(Box::new(GrepFilter::try_from(pattern.to_owned())?));
+    }
+    Ok(event_filters)
+}
 
-    let tail = commands::tail::run(
-        user,
-        account_id,
-        script_name,
-        url,
-        TailOptions {
-            once,
-            format,
-            filters,
+fn parse_ip_address(input: &str) -> Result<String, anyhow::Error> {
+    match input {
+        "self" => Ok(String::from("self")),
+        address => match IpAddr::from_str(address) {
+            Ok(_) => Ok(address.to_owned()),
+            Err(err) => anyhow::bail!("{}: {}", err, input),
         },
-    );
+    }
+}
 
-    tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
+fn parse_sampling_rate(input: &str) -> Result<f64, anyhow::Error> {
+    let sampling_rate = f64::from_str(input)?;
+    if sampling_rate > 0.0 && sampling_rate <= 1.0 {
+        Ok(sampling_rate)
+    } else {
+        anyhow::bail!("sampling rate must be between 0 and 1: {}", input)
+    }
+}
+
+/// Parses a size in megabytes into bytes.
+fn parse_rotate_size(input: &str) -> Result<u64, anyhow::Error> {
+    match u64::from_str(input) {
+        Ok(megabytes) if megabytes > 0 => match megabytes.checked_mul(1024 * 1024) {
+            Some(bytes) => Ok(bytes),
+            None => anyhow::bail!("rotate size is too large: {}", input),
+        },
+        _ => anyhow::bail!("rotate size must be at least 1 megabyte: {}", input),
+    }
+}
+
+pub(crate) fn parse_duration(input: &str) -> Result<Duration, anyhow::Error> {
+    let (value, multiplier) = match input.chars().last() {
+        Some('s') => (&input[..input.len() - 1], 1),
+        Some('m') => (&input[..input.len() - 1], 60),
+        Some('h') => (&input[..input.len() - 1], 60 * 60),
+        _ => (input, 1),
+    };
+    match u64::from_str(value) {
+        Ok(value) if value > 0 => Ok(Duration::from_secs(value * multiplier)),
+        _ => anyhow::bail!(
+            "invalid duration, expected a value like 30s, 5m or 1h: {}",
+            input
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Command;
+
+    #[test]
+    fn it_parses_ip_address() {
+        assert_eq!(parse_ip_address("self").unwrap(), "self");
+        assert_eq!(parse_ip_address("192.0.2.1").unwrap(), "192.0.2.1");
+        assert_eq!(parse_ip_address("2001:db8::1").unwrap(), "2001:db8::1");
+    }
+
+    #[test]
+    fn it_rejects_invalid_ip_address() {
+        assert!(parse_ip_address("me").is_err());
+        assert!(parse_ip_address("256.0.0.1").is_err());
+    }
+
+    #[test]
+    fn it_parses_duration() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn it_rejects_invalid_duration() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("-5m").is_err());
+        assert!(parse_duration("1d").is_err());
+    }
+
+    #[test]
+    fn it_parses_rotate_size() {
+        assert_eq!(parse_rotate_size("10").unwrap(), 10 * 1024 * 1024);
+        assert!(parse_rotate_size("0").is_err());
+        assert!(parse_rotate_size("-1").is_err());
+        assert!(parse_rotate_size(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn it_rotates_only_an_output_file() {
+        let command = Cli::from_iter_safe(&[
+            "wrangler",
+            "tail",
+            "--out-file",
+            "x.log",
+            "--rotate-size",
+            "10",
+        ])
         .unwrap()
-        .block_on(tail)
+        .command;
+        match command {
+            Command::Tail(tail) => {
+                assert_eq!(tail.out_file, Some(PathBuf::from("x.log")));
+                assert_eq!(tail.rotate_size, Some(10 * 1024 * 1024));
+            }
+            command => panic!("parsed {:?}", command),
+        }
+
+        assert!(Cli::from_iter_safe(&["wrangler", "tail", "--rotate-size", "10"]).is_err());
+    }
+
+    #[test]
+    fn it_parses_sampling_rate() {
+        assert!(parse_sampling_rate("0.01").is_ok());
+        assert!(parse_sampling_rate("1").is_ok());
+    }
+
+    #[test]
+    fn it_rejects_invalid_sampling_rate() {
+        assert!(parse_sampling_rate("0").is_err());
+        assert!(parse_sampling_rate("1.5").is_err());
+        assert!(parse_sampling_rate("-0.1").is_err());
+        assert!(parse_sampling_rate("NaN").is_err());
+        assert!(parse_sampling_rate("abc").is_err());
+    }
 }
@@ -0,0 +1,57 @@
+use super::Cli;
+use crate::commands;
+use crate::deploy::DeployTarget;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Triggers {
+    /// List the cron triggers of your deployed worker
+    List,
+    /// Replace the cron triggers of your deployed worker with the [triggers] crons of your
+    /// configuration file, without publishing it again
+    Update {
+        /// A cron expression to use instead of the ones in your configuration file
+        #[structopt(long, number_of_values = 1)]
+        cron: Vec<String>,
+
+        /// Remove all of the cron triggers of your worker
+        #[structopt(long, conflicts_with = "cron")]
+        clear: bool,
+    },
+}
+
+pub fn triggers(triggers: Triggers, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+
+    match triggers {
+        Triggers::List => commands::triggers::list(&user, &target),
+        Triggers::Update { cron, clear } => {
+            let crons = if clear {
+                vec![]
+            } else if !cron.is_empty() {
+                cron
+            } else {
+                manifest
+                    .get_deployments(cli_params.environment())?
+                    .into_iter()
+                    .find_map(|deployment| match deployment {
+                        DeployTarget::Schedule(schedule) => Some(schedule.crons),
+                        _ => None,
+                    })
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("There are no [triggers] crons in your configuration file, pass them with --cron or remove the triggers with --clear")
+                    })?
+            };
+            commands::triggers::update(&user, &target, crons)
+        }
+    }
+}
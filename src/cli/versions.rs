@@ -0,0 +1,29 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Versions {
+    /// Send all requests to a version, completing a rollout started with `wrangler publish --percentage`
+    Promote {
+        /// The version to promote, defaults to the one being rolled out
+        #[structopt(name = "version-id", index = 1)]
+        version_id: Option<String>,
+    },
+}
+
+pub fn versions(versions: Versions, cli_params: &Cli) -> Result<()> {
+    log::info!("Getting User settings");
+    let user = GlobalUser::new()?;
+
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment(), false)?;
+    match versions {
+        Versions::Promote { version_id } => commands::versions::promote(&user, &target, version_id),
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use cloudflare::endpoints::workers::DeleteRoute;
+use cloudflare::framework::apiclient::ApiClient;
+use serde::Deserialize;
+
+use crate::deploy;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Route, Target};
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::{interactive, styles};
+
+/// What is deleted along with the script.
+#[derive(Debug, Default)]
+pub struct DeleteOptions {
+    /// the zone whose routes to the script are deleted
+    pub zone_id: Option<String>,
+    pub durable_objects: bool,
+    pub yes: bool,
+}
+
+#[derive(Deserialize)]
+struct NamespacesResponse {
+    result: Vec<Namespace>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Namespace {
+    id: String,
+    name: String,
+    script: Option<String>,
+    class: Option<String>,
+}
+
+/// Deletes a script from the account, which takes its workers.dev subdomain with it.
+pub fn delete(user: &GlobalUser, target: &Target, options: DeleteOptions) -> Result<()> {
+    let routes = match &options.zone_id {
+        Some(zone_id) => deploy::fetch_routes(user, zone_id)?
+            .into_iter()
+            .filter(|route| route.script.as_deref() == Some(target.name.as_str()))
+            .collect(),
+        None => vec![],
+    };
+    let namespaces = if options.durable_objects {
+        fetch_namespaces(user, target)?
+            .into_iter()
+            .filter(|namespace| namespace.script.as_deref() == Some(target.name.as_str()))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if !options.yes {
+        StdOut::info(&describe(&target.name, &routes, &namespaces));
+        if !interactive::confirm("Are you sure you want to permanently delete all of this?")? {
+            StdOut::info(&format!("Not deleting {}", target.name));
+            return Ok(());
+        }
+    }
+
+    if let Some(zone_id) = &options.zone_id {
+        let client = http::cf_v4_client(user)?;
+        for route in routes.iter() {
+            let id = match &route.id {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Err(e) = client.request(&DeleteRoute {
+                zone_identifier: zone_id,
+                identifier: id,
+            }) {
+                anyhow::bail!("{}", http::format_error(e, None))
+            }
+            StdOut::info(&format!("Deleted the route {}", route.pattern));
+        }
+    }
+
+    let client = http::legacy_auth_client(user);
+    for namespace in namespaces.iter() {
+        let response = client
+            .delete(&format!("{}/{}", namespaces_addr(target)?, namespace.id))
+            .send()?;
+        if !response.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(response.text()?))
+        }
+        StdOut::info(&format!(
+            "Deleted the Durable Objects namespace {}",
+            namespace.name
+        ));
+    }
+
+    let script_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        target.account_id.load()?,
+        target.name
+    );
+    let response = client.delete(&script_addr).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    StdOut::success(&format!("Deleted {}", styles::highlight(&target.name)));
+    Ok(())
+}
+
+fn namespaces_addr(target: &Target) -> Result<String> {
+    Ok(format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/durable_objects/namespaces",
+        target.account_id.load()?
+    ))
+}
+
+fn fetch_namespaces(user: &GlobalUser, target: &Target) -> Result<Vec<Namespace>> {
+    let response = http::legacy_auth_client(user)
+        .get(&namespaces_addr(target)?)
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let namespaces: NamespacesResponse = response.json()?;
+    Ok(namespaces.result)
+}
+
+fn describe(name: &str, routes: &[Route], namespaces: &[Namespace]) -> String {
+    let mut lines = vec![format!(
+        "This deletes the script {} and its workers.dev subdomain",
+        name
+    )];
+    for route in routes {
+        lines.push(format!("  the route {}", route.pattern));
+    }
+    for namespace in namespaces {
+        lines.push(format!(
+            "  the Durable Objects namespace {} of the class {}, and all of its objects",
+            namespace.name,
+            namespace.class.as_deref().unwrap_or("unknown")
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_what_is_deleted() {
+        let routes = vec![Route {
+            id: Some("1".to_owned()),
+            script: Some("worker".to_owned()),
+            pattern: "example.com/*".to_owned(),
+        }];
+        let namespaces = vec![Namespace {
+            id: "2".to_owned(),
+            name: "worker_Counter".to_owned(),
+            script: Some("worker".to_owned()),
+            class: Some("Counter".to_owned()),
+        }];
+
+        let description = describe("worker", &routes, &namespaces);
+        assert!(description.contains("the route example.com/*"));
+        assert!(description.contains("worker_Counter of the class Counter"));
+        assert_eq!(describe("worker", &[], &[]).lines().count(), 1);
+    }
+}
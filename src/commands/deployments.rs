@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, Output, StdOut};
+use crate::terminal::{interactive, styles};
+
+/// How many of the most recent deployments are listed before rolling back.
+const RECENT_DEPLOYMENTS: usize = 5;
+
+const MESSAGE_ANNOTATION: &str = "workers/message";
+const TAG_ANNOTATION: &str = "workers/tag";
+
+/// A version of a script that was deployed, and can be rolled back to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub id: String,
+    pub number: u64,
+    pub metadata: DeploymentMetadata,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeploymentMetadata {
+    pub author_email: Option<String>,
+    /// how the deployment was made, e.g. by wrangler or the dashboard
+    pub source: Option<String>,
+    pub created_on: String,
+}
+
+impl Deployment {
+    pub fn message(&self) -> Option<&str> {
+        self.annotations.get(MESSAGE_ANNOTATION).map(String::as_str)
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.annotations.get(TAG_ANNOTATION).map(String::as_str)
+    }
+}
+
+/// The history of a script, as it is printed with `--output json`.
+#[derive(Serialize)]
+struct History<'a> {
+    name: &'a str,
+    live: Option<&'a str>,
+    deployments: &'a [Deployment],
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Service {
+    default_environment: ServiceEnvironment,
+}
+
+#[derive(Deserialize)]
+struct ServiceEnvironment {
+    script: ServiceScript,
+}
+
+#[derive(Deserialize)]
+struct ServiceScript {
+    tag: String,
+}
+
+#[derive(Deserialize)]
+struct Deployments {
+    latest: Option<Deployment>,
+    items: Vec<Deployment>,
+}
+
+/// Fetches the deployments of a script, newest first, along with the id of the one that is live.
+pub fn fetch(user: &GlobalUser, target: &Target) -> Result<(Vec<Deployment>, Option<String>)> {
+    let account_id = target.account_id.load()?;
+    let client = http::legacy_auth_client(user);
+
+    // deployments are listed by the tag of a script, which outlives its name
+    let service_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/services/{}",
+        account_id, target.name
+    );
+    let response = client.get(&service_addr).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let service: ApiResponse<Service> = response.json()?;
+
+    let deployments_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/deployments/by-script/{}",
+        account_id, service.result.default_environment.script.tag
+    );
+    let response = client.get(&deployments_addr).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let deployments: ApiResponse<Deployments> = response.json()?;
+
+    let mut items = deployments.result.items;
+    items.sort_by(|a, b| b.number.cmp(&a.number));
+    Ok((items, deployments.result.latest.map(|latest| latest.id)))
+}
+
+/// Prints the deployment history of a script, newest first.
+pub fn list(user: &GlobalUser, target: &Target, out: Output) -> Result<()> {
+    let (deployments, latest) = fetch(user, target)?;
+    if out == Output::Json {
+        StdOut::as_json(&History {
+            name: &target.name,
+            live: latest.as_deref(),
+            deployments: &deployments,
+        });
+        return Ok(());
+    }
+
+    if deployments.is_empty() {
+        StdOut::info(&format!("{} has not been deployed yet", target.name));
+        return Ok(());
+    }
+    StdOut::info(&format!(
+        "Deployments of {}, the live one is marked with *:",
+        styles::bold(&target.name)
+    ));
+    for deployment in deployments.iter() {
+        let live = latest.as_deref() == Some(deployment.id.as_str());
+        println!("{}", describe(deployment, live));
+    }
+    Ok(())
+}
+
+/// Re-activates a previous deployment of a script, or the one before the live one if none is
+/// given, without building or uploading anything.
+pub fn rollback(user: &GlobalUser, target: &Target, id: Option<String>) -> Result<()> {
+    let (deployments, latest) = fetch(user, target)?;
+    if deployments.is_empty() {
+        anyhow::bail!("{} has not been deployed yet", target.name)
+    }
+
+    StdOut::info("Recent deployments:");
+    for deployment in deployments.iter().take(RECENT_DEPLOYMENTS) {
+        let live = latest.as_deref() == Some(deployment.id.as_str());
+        println!("{}", describe(deployment, live));
+    }
+
+    let deployment = match id {
+        Some(id) => deployments
+            .iter()
+            .find(|deployment| deployment.id == id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "There is no deployment of {} with the id {}",
+                    target.name,
+                    id
+                )
+            })?,
+        None => previous(&deployments, latest.as_deref()).ok_or_else(|| {
+            anyhow::anyhow!(
+                "There is no deployment of {} before the live one to roll back to",
+                target.name
+            )
+        })?,
+    };
+    if latest.as_deref() == Some(deployment.id.as_str()) {
+        StdOut::info(&format!("{} is already live", deployment.id));
+        return Ok(());
+    }
+
+    if console::user_attended()
+        && !interactive::confirm(&format!(
+            "Roll {} back to deployment {}?",
+            target.name, deployment.id
+        ))?
+    {
+        StdOut::info("Not rolling back");
+        return Ok(());
+    }
+
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}?rollback_to={}",
+        target.account_id.load()?,
+        target.name,
+        deployment.id
+    );
+    let response = http::legacy_auth_client(user).put(&addr).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+
+    StdOut::success(&format!(
+        "Rolled {} back to deployment {}",
+        target.name,
+        styles::highlight(&deployment.id)
+    ));
+    Ok(())
+}
+
+/// The newest deployment from before the live one.
+fn previous<'a>(deployments: &'a [Deployment], latest: Option<&str>) -> Option<&'a Deployment> {
+    let live = match latest {
+        Some(latest) => deployments
+            .iter()
+            .find(|deployment| deployment.id == latest)?,
+        None => deployments.first()?,
+    };
+    deployments
+        .iter()
+        .find(|deployment| deployment.number < live.number)
+}
+
+fn describe(deployment: &Deployment, live: bool) -> String {
+    let mut line = format!(
+        "{} {} {}",
+        if live { "*" } else { " " },
+        styles::highlight(&deployment.id),
+        deployment.metadata.created_on
+    );
+    if let Some(author) = &deployment.metadata.author_email {
+        line.push_str(&format!(" by {}", author));
+    }
+    if let Some(source) = &deployment.metadata.source {
+        line.push_str(&format!(" from {}", source));
+    }
+    if let Some(tag) = deployment.tag() {
+        line.push_str(&format!(" [{}]", tag));
+    }
+    if let Some(message) = deployment.message() {
+        line.push_str(&format!(" \"{}\"", message));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(id: &str, number: u64) -> Deployment {
+        Deployment {
+            id: id.to_owned(),
+            number,
+            metadata: DeploymentMetadata {
+                author_email: None,
+                source: None,
+                created_on: "2021-06-01T00:00:00Z".to_owned(),
+            },
+            annotations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn it_rolls_back_to_the_deployment_before_the_live_one() {
+        let deployments = vec![deployment("c", 3), deployment("b", 2), deployment("a", 1)];
+
+        assert_eq!(previous(&deployments, None).unwrap().id, "b");
+        // the live deployment may not be the newest, after a rollback
+        assert_eq!(previous(&deployments, Some("b")).unwrap().id, "a");
+        assert!(previous(&deployments, Some("a")).is_none());
+        assert!(previous(&[], None).is_none());
+    }
+
+    #[test]
+    fn it_describes_deployments() {
+        let mut deployment = deployment("a", 1);
+        assert!(!describe(&deployment, false).contains("by"));
+
+        deployment.metadata.author_email = Some("me@example.com".to_owned());
+        deployment.metadata.source = Some("wrangler".to_owned());
+        deployment
+            .annotations
+            .insert(MESSAGE_ANNOTATION.to_owned(), "fix the login".to_owned());
+        deployment
+            .annotations
+            .insert(TAG_ANNOTATION.to_owned(), "v1.4.2".to_owned());
+        let description = describe(&deployment, true);
+        assert!(description.starts_with('*'));
+        assert!(
+            description.ends_with("by me@example.com from wrangler [v1.4.2] \"fix the login\""),
+            "{}",
+            description
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::settings::toml::Target;
+use crate::terminal::message::Output;
+use crate::terminal::styles;
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The file of local-only variables, which is kept out of version control.
+pub const DEV_VARS_FILE: &str = ".dev.vars";
+
+/// Adds the variables of a `.dev.vars` file next to the config file to the Worker's
+/// `vars`, overriding the ones in wrangler.toml.
+pub fn apply(target: &mut Target, config_path: &Path, output: Output) -> Result<()> {
+    let path = config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(DEV_VARS_FILE);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let dev_vars = parse(&fs::read_to_string(&path)?)?;
+    output.info(&format!(
+        "Using vars from {}",
+        styles::highlight(DEV_VARS_FILE)
+    ));
+    target
+        .vars
+        .get_or_insert_with(HashMap::new)
+        .extend(dev_vars);
+    Ok(())
+}
+
+/// Parses `KEY=value` lines, skipping blank lines and `#` comments.
+/// Values may be wrapped in single or double quotes.
+fn parse(contents: &str) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => (key.trim(), value.trim()),
+            _ => anyhow::bail!(
+                "{} line {} should look like KEY=value",
+                DEV_VARS_FILE,
+                number + 1
+            ),
+        };
+        let value = match (value.chars().next(), value.chars().last()) {
+            (Some('"'), Some('"')) | (Some('\''), Some('\'')) if value.len() >= 2 => {
+                &value[1..value.len() - 1]
+            }
+            _ => value,
+        };
+        vars.insert(key.to_owned(), value.to_owned());
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_dev_vars() {
+        let vars = parse(
+            "# secrets for local development\nAPI_KEY=abc123\n\nexport GREETING=\"hello, world\"\nEMPTY=\nQUOTED='a=b'\n",
+        )
+        .unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert("API_KEY".to_owned(), "abc123".to_owned());
+        expected.insert("GREETING".to_owned(), "hello, world".to_owned());
+        expected.insert("EMPTY".to_owned(), "".to_owned());
+        expected.insert("QUOTED".to_owned(), "a=b".to_owned());
+        assert_eq!(vars, expected);
+
+        assert!(parse("NOT A VAR").is_err());
+    }
+
+    #[test]
+    fn it_overrides_vars_from_wrangler_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(DEV_VARS_FILE), "API_KEY=local").unwrap();
+
+        let mut vars = HashMap::new();
+        vars.insert("API_KEY".to_owned(), "production".to_owned());
+        vars.insert("GREETING".to_owned(), "hello".to_owned());
+        let mut target = Target {
+            vars: Some(vars),
+            ..Default::default()
+        };
+        apply(
+            &mut target,
+            &dir.path().join("wrangler.toml"),
+            Output::PlainText,
+        )
+        .unwrap();
+
+        let vars = target.vars.unwrap();
+        assert_eq!(vars["API_KEY"], "local");
+        assert_eq!(vars["GREETING"], "hello");
+    }
+}
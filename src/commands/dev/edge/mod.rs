@@ -7,14 +7,16 @@ use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 use watch::watch_for_changes;
 
-use crate::commands::dev::{socket, Protocol, ServerConfig};
+use crate::commands::dev::hotkeys::{self, Action};
+use crate::commands::dev::{inspector, socket, utils, Protocol, ServerConfig};
 use crate::deploy::DeployTarget;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
 use anyhow::Result;
 
 use tokio::runtime::Runtime as TokioRuntime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 
 use std::sync::{
     mpsc::{self, Sender},
@@ -22,6 +24,7 @@ use std::sync::{
 };
 use std::thread;
 
+#[allow(clippy::too_many_arguments)]
 pub fn dev(
     target: Target,
     user: GlobalUser,
@@ -29,8 +32,18 @@ pub fn dev(
     deploy_target: DeployTarget,
     local_protocol: Protocol,
     upstream_protocol: Protocol,
+    mut actions: UnboundedReceiver<Action>,
     verbose: bool,
-) -> Result<()> {
+) -> Result<Option<Action>> {
+    let output = server_config.output;
+    if let DeployTarget::Zoneless(_) = deploy_target {
+        // Miniflare emulates the Cache API, while on workers.dev it does nothing
+        output.info(&format!(
+            "The Cache API has no effect on workers.dev, run {} to cache responses locally",
+            styles::highlight("wrangler dev --local")
+        ));
+    }
+
     let runtime = TokioRuntime::new()?;
     loop {
         let target = target.clone();
@@ -54,7 +67,26 @@ pub fn dev(
             (rx_init_shutdown, tx_ack_shutdown),
         )?;
 
-        while receiver.recv()?.is_none() {}
+        // the session is refreshed from other threads, so the refresh is passed
+        // on to the runtime, to wait for it alongside the actions
+        let (refresh, mut refreshed) = unbounded_channel();
+        thread::spawn(move || {
+            while let Ok(message) = receiver.recv() {
+                if message.is_some() && refresh.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+        let action = runtime.block_on(async {
+            tokio::select! {
+                refreshed = refreshed.recv() => match refreshed {
+                    Some(()) => Ok(None),
+                    None => Err(anyhow::anyhow!("The dev session stopped unexpectedly")),
+                },
+                action = hotkeys::next(&mut actions) => Ok(Some(action)),
+            }
+        })?;
+
         tx_init_shutdown
             .send(())
             .expect("Could not initiate listener task shutdown");
@@ -66,7 +98,12 @@ pub fn dev(
         for task in tasks {
             task.abort();
         }
-        StdOut::info("Starting a new session because the existing token has expired");
+        if action.is_some() {
+            // the server stops listening as it is dropped with the runtime
+            drop(runtime);
+            return Ok(action);
+        }
+        output.info("Starting a new session because the existing token has expired");
     }
 }
 
@@ -91,14 +128,21 @@ fn dev_once(
         &user,
         session.preview_token.clone(),
         verbose,
+        server_config.output,
     )?;
 
     let preview_token = Arc::new(Mutex::new(preview_token));
+    let script_path = utils::script_path(&target)
+        .ok()
+        .flatten()
+        .map(|(script_path, _)| script_path);
 
     {
         let preview_token = preview_token.clone();
         let session_token = session.preview_token.clone();
         let refresh_session_sender = refresh_session_sender.clone();
+        let live_reload = server_config.live_reload.clone();
+        let output = server_config.output;
 
         thread::spawn(move || {
             watch_for_changes(
@@ -109,13 +153,28 @@ fn dev_once(
                 session_token,
                 verbose,
                 refresh_session_sender,
+                live_reload,
+                output,
             )
         });
     }
 
+    let mut tasks = vec![];
+    if let Some(port) = server_config.inspector_port {
+        tasks.push(runtime.spawn(inspector::serve(
+            session.websocket_url.clone(),
+            port,
+            server_config.output,
+        )));
+    }
+
     let devtools_listener = runtime.spawn(socket::listen(
         session.websocket_url,
         Some(refresh_session_sender),
+        script_path,
+        server_config.repl.clone(),
+        server_config.log_subrequests,
+        server_config.output,
     ));
     let server = match local_protocol {
         Protocol::Https => runtime.spawn(server::https(
@@ -132,5 +191,7 @@ fn dev_once(
             shutdown_channel,
         )),
     };
-    Ok(vec![devtools_listener, server])
+    tasks.push(devtools_listener);
+    tasks.push(server);
+    Ok(tasks)
 }
@@ -1,5 +1,6 @@
 use super::preview_request;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::live_reload::LIVE_RELOAD_PATH;
+use crate::commands::dev::utils::{get_path_as_str, local_host, rewrite_redirect};
 use crate::commands::dev::{Protocol, ServerConfig};
 use crate::terminal::emoji;
 
@@ -24,6 +25,7 @@ pub async fn http(
     let client = HyperClient::builder().build::<_, Body>(https);
 
     let listening_address = server_config.listening_address;
+    let output = server_config.output;
 
     // create a closure that hyper will use later to handle HTTP requests
     let make_service = make_service_fn(move |_| {
@@ -35,19 +37,28 @@ pub async fn http(
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
                 let client = client.to_owned();
+                let live_reload = server_config.live_reload.to_owned();
+                let recorder = server_config.recorder.to_owned();
                 let preview_token = preview_token.lock().unwrap().to_owned();
                 let host = host.to_owned();
                 let version = req.version();
-                let (parts, body) = req.into_parts();
-                let local_host = format!(
-                    "{}:{}",
-                    server_config.listening_address.ip().to_string(),
-                    server_config.listening_address.port().to_string()
-                );
+                let (mut parts, body) = req.into_parts();
+                let local_host = local_host(&parts.headers, server_config.listening_address);
                 let req_method = parts.method.to_string();
                 let now: DateTime<Local> = Local::now();
                 let path = get_path_as_str(&parts.uri);
                 async move {
+                    if let Some(live_reload) = &live_reload {
+                        if parts.uri.path() == LIVE_RELOAD_PATH {
+                            return Ok(live_reload.events());
+                        }
+                        live_reload.prepare_request(&mut parts.headers);
+                    }
+                    let body = match &recorder {
+                        Some(recorder) => recorder.record(&parts, body).await?,
+                        None => body,
+                    };
+
                     let mut resp = preview_request(
                         Request::from_parts(parts, body),
                         client,
@@ -58,8 +69,12 @@ pub async fn http(
                     .await?;
 
                     rewrite_redirect(&mut resp, &host, &local_host, false);
+                    let resp = match &live_reload {
+                        Some(live_reload) => live_reload.inject(resp).await?,
+                        None => resp,
+                    };
 
-                    println!(
+                    output.message(&format!(
                         "[{}] {} {}{} {:?} {}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
@@ -67,7 +82,7 @@ pub async fn http(
                         path,
                         version,
                         resp.status()
-                    );
+                    ));
                     Ok::<_, anyhow::Error>(resp)
                 }
             }))
@@ -80,7 +95,11 @@ pub async fn http(
         .with_graceful_shutdown(async {
             rx.await.expect("Could not receive shutdown initiation");
         });
-    println!("{} Listening on http://{}", emoji::EAR, listening_address);
+    output.message(&format!(
+        "{} Listening on http://{}",
+        emoji::EAR,
+        listening_address
+    ));
 
     if let Err(e) = server.await {
         eprintln!("{}", e);
@@ -1,8 +1,8 @@
 use super::preview_request;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::live_reload::LIVE_RELOAD_PATH;
+use crate::commands::dev::utils::{get_path_as_str, local_host, rewrite_redirect};
 use crate::commands::dev::{tls, Protocol, ServerConfig};
 use crate::terminal::emoji;
-use crate::terminal::message::{Message, StdOut};
 use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
@@ -21,7 +21,8 @@ pub async fn https(
     host: String,
     shutdown_channel: (Receiver<()>, Sender<()>),
 ) -> Result<()> {
-    tls::generate_cert()?;
+    let output = server_config.output;
+    tls::generate_cert(output)?;
 
     // set up https client to connect to the preview service
     let https = HttpsConnector::with_native_roots();
@@ -39,19 +40,28 @@ pub async fn https(
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
                 let client = client.to_owned();
+                let live_reload = server_config.live_reload.to_owned();
+                let recorder = server_config.recorder.to_owned();
                 let preview_token = preview_token.lock().unwrap().to_owned();
                 let host = host.to_owned();
                 let version = req.version();
-                let (parts, body) = req.into_parts();
-                let local_host = format!(
-                    "{}:{}",
-                    server_config.listening_address.ip().to_string(),
-                    server_config.listening_address.port().to_string()
-                );
+                let (mut parts, body) = req.into_parts();
+                let local_host = local_host(&parts.headers, server_config.listening_address);
                 let req_method = parts.method.to_string();
                 let now: DateTime<Local> = Local::now();
                 let path = get_path_as_str(&parts.uri);
                 async move {
+                    if let Some(live_reload) = &live_reload {
+                        if parts.uri.path() == LIVE_RELOAD_PATH {
+                            return Ok(live_reload.events());
+                        }
+                        live_reload.prepare_request(&mut parts.headers);
+                    }
+                    let body = match &recorder {
+                        Some(recorder) => recorder.record(&parts, body).await?,
+                        None => body,
+                    };
+
                     let mut resp = preview_request(
                         Request::from_parts(parts, body),
                         client,
@@ -62,8 +72,12 @@ pub async fn https(
                     .await?;
 
                     rewrite_redirect(&mut resp, &host, &local_host, true);
+                    let resp = match &live_reload {
+                        Some(live_reload) => live_reload.inject(resp).await?,
+                        None => resp,
+                    };
 
-                    println!(
+                    output.message(&format!(
                         "[{}] {} {}{} {:?} {}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
@@ -71,7 +85,7 @@ pub async fn https(
                         path,
                         version,
                         resp.status()
-                    );
+                    ));
                     Ok::<_, anyhow::Error>(resp)
                 }
             }))
@@ -94,7 +108,7 @@ pub async fn https(
                 Ok(tls_stream) => Ok(tls_stream),
                 Err(e) => {
                     eprintln!("Client connection error {}", e);
-                    StdOut::info("Make sure to use https and `--insecure` with curl");
+                    output.info("Make sure to use https and `--insecure` with curl");
                     Err(e)
                 }
             },
@@ -113,8 +127,12 @@ pub async fn https(
         rx.await.expect("Could not receive shutdown initiation");
     });
 
-    println!("{} Listening on https://{}", emoji::EAR, listening_address);
-    StdOut::info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
+    output.message(&format!(
+        "{} Listening on https://{}",
+        emoji::EAR,
+        listening_address
+    ));
+    output.info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
 
     if let Err(e) = server.await {
         eprintln!("{}", e);
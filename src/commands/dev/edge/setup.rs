@@ -5,7 +5,7 @@ use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::sites::{add_namespace, sync};
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::message::Output;
 use crate::upload;
 
 use anyhow::{anyhow, Result};
@@ -19,6 +19,7 @@ pub(super) fn upload(
     user: &GlobalUser,
     session_token: String,
     verbose: bool,
+    output: Output,
 ) -> Result<String> {
     let client = crate::http::legacy_auth_client(user);
 
@@ -31,7 +32,7 @@ pub(super) fn upload(
 
         // First, upload all existing files in given directory
         if verbose {
-            StdOut::info("Uploading updated files...");
+            output.info("Uploading updated files...");
         }
 
         bulk::put(target, user, &site_namespace.id, to_upload, &None)?;
@@ -54,7 +55,7 @@ pub(super) fn upload(
 
     if !to_delete.is_empty() {
         if verbose {
-            StdOut::info("Deleting stale files...");
+            output.info("Deleting stale files...");
         }
 
         bulk::delete(target, user, &site_namespace_id.unwrap(), to_delete, &None)?;
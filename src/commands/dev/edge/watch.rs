@@ -2,13 +2,16 @@ use std::sync::mpsc::Sender;
 use std::sync::{mpsc, Arc, Mutex};
 
 use crate::commands::dev::edge::setup;
+use crate::commands::dev::LiveReload;
 use crate::deploy::DeployTarget;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::Output;
 use crate::watch::watch_and_build;
 
 use anyhow::Result;
 
+#[allow(clippy::too_many_arguments)]
 pub fn watch_for_changes(
     target: &Target,
     deploy_target: &DeployTarget,
@@ -17,9 +20,16 @@ pub fn watch_for_changes(
     session_token: String,
     verbose: bool,
     refresh_session_channel: Sender<Option<()>>,
+    live_reload: Option<LiveReload>,
+    output: Output,
 ) -> Result<()> {
     let (sender, receiver) = mpsc::channel();
-    watch_and_build(target, Some(sender), Some(refresh_session_channel.clone()))?;
+    watch_and_build(
+        target,
+        Some(sender),
+        Some(refresh_session_channel.clone()),
+        true,
+    )?;
 
     while receiver.recv().is_ok() {
         let user = user.clone();
@@ -36,9 +46,19 @@ pub fn watch_for_changes(
         //
         // this allows the server to route subsequent requests
         // to the proper script
-        let uploaded = setup::upload(&mut target, &deploy_target, &user, session_token, verbose);
+        let uploaded = setup::upload(
+            &mut target,
+            &deploy_target,
+            &user,
+            session_token,
+            verbose,
+            output,
+        );
         if let Ok(token) = uploaded {
             *preview_token = token;
+            if let Some(live_reload) = &live_reload {
+                live_reload.reload();
+            }
         } else {
             refresh_session_channel.send(Some(()))?;
             break;
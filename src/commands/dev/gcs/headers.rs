@@ -13,13 +13,19 @@ const HEADER_PREFIX: &str = "cf-ew-raw-";
 use std::str::FromStr;
 
 use anyhow::Result;
-use hyper::header::{HeaderMap, HeaderName};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, HOST};
 use hyper::http::request::Parts as RequestParts;
 use hyper::http::response::Parts as ResponseParts;
 use hyper::http::status::StatusCode;
 
 /// modify an incoming request before sending it to the preview service
-pub fn structure_request(parts: &mut RequestParts) {
+///
+/// the Host header is replaced with the upstream host, so the Worker
+/// and any `fetch(request)` to the origin see the host they would in production
+pub fn structure_request(parts: &mut RequestParts, host: &str) {
+    if let Ok(host) = HeaderValue::from_str(host) {
+        parts.headers.insert(HOST, host);
+    }
     prepend_request_headers_prefix(parts)
 }
 
@@ -85,7 +91,22 @@ fn set_response_status(parts: &mut ResponseParts) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use hyper::Response;
+    use hyper::{Request, Response};
+
+    #[test]
+    fn host_is_set_to_upstream() {
+        let request = Request::builder()
+            .header("Host", "localhost:8787")
+            .header("Accept", "text/html")
+            .body(())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+        structure_request(&mut parts, "example.com");
+
+        assert_eq!(parts.headers["cf-ew-raw-host"], "example.com");
+        assert_eq!(parts.headers["cf-ew-raw-accept"], "text/html");
+        assert!(parts.headers.get(HOST).is_none());
+    }
 
     #[test]
     fn headers_are_appended() {
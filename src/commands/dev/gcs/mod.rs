@@ -6,13 +6,15 @@ mod watch;
 use setup::{get_preview_id, get_session_id};
 use watch::watch_for_changes;
 
-use crate::commands::dev::{socket, Protocol, ServerConfig};
+use crate::commands::dev::hotkeys::{self, Action};
+use crate::commands::dev::{inspector, socket, utils, Protocol, ServerConfig};
 use crate::settings::toml::Target;
 
 use anyhow::Result;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tokio::runtime::Runtime as TokioRuntime;
+use tokio::sync::mpsc::UnboundedReceiver;
 use url::Url;
 
 /// spin up a local server that routes requests to the preview service
@@ -21,9 +23,10 @@ pub fn dev(
     target: Target,
     server_config: ServerConfig,
     local_protocol: Protocol,
+    mut actions: UnboundedReceiver<Action>,
     verbose: bool,
-) -> Result<()> {
-    println!("unauthenticated");
+) -> Result<Option<Action>> {
+    server_config.output.message("unauthenticated");
 
     // setup the session
     let session_id = get_session_id()?;
@@ -48,6 +51,10 @@ pub fn dev(
     // preview ID into an Arc<Mutex so that the server waits on the
     // file watcher to release the lock before routing a request
     let preview_id = Arc::new(Mutex::new(preview_id));
+    let script_path = utils::script_path(&target)
+        .ok()
+        .flatten()
+        .map(|(script_path, _)| script_path);
     // a new scope is created to satisfy the borrow checker
     {
         // we must clone each of these variables in order to
@@ -72,9 +79,23 @@ pub fn dev(
     let runtime = TokioRuntime::new()?;
 
     // and we must block the main thread on the completion of
-    // said futures
-    runtime.block_on(async {
-        let devtools_listener = tokio::spawn(socket::listen(socket_url.clone(), None));
+    // said futures, or on an action that stops them
+    let action = runtime.block_on(async {
+        if let Some(port) = server_config.inspector_port {
+            tokio::spawn(inspector::serve(
+                socket_url.clone(),
+                port,
+                server_config.output,
+            ));
+        }
+        let devtools_listener = tokio::spawn(socket::listen(
+            socket_url.clone(),
+            None,
+            script_path,
+            server_config.repl.clone(),
+            server_config.log_subrequests,
+            server_config.output,
+        ));
 
         let server = match local_protocol {
             Protocol::Https => tokio::spawn(server::https(
@@ -86,12 +107,17 @@ pub fn dev(
             }
         };
 
-        let res = tokio::try_join!(async { devtools_listener.await? }, async { server.await? });
-        match res {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+        let res =
+            async { tokio::try_join!(async { devtools_listener.await? }, async { server.await? }) };
+        tokio::select! {
+            res = res => res.map(|_| None),
+            action = hotkeys::next(&mut actions) => Ok(Some(action)),
         }
-    })
+    })?;
+    // the server stops listening as it is dropped with the runtime
+    drop(runtime);
+
+    Ok(action)
 }
 
 fn get_socket_url(session_id: &str) -> Result<Url, url::ParseError> {
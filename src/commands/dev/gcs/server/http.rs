@@ -1,7 +1,8 @@
 use super::preview_request;
 use crate::commands::dev::gcs::headers::destructure_response;
+use crate::commands::dev::live_reload::LIVE_RELOAD_PATH;
 use crate::commands::dev::server_config::ServerConfig;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::utils::{get_path_as_str, local_host, rewrite_redirect};
 use crate::terminal::emoji;
 
 use std::sync::{Arc, Mutex};
@@ -20,6 +21,7 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
     let client = HyperClient::builder().build::<_, Body>(https);
 
     let listening_address = server_config.listening_address;
+    let output = server_config.output;
 
     // create a closure that hyper will use later to handle HTTP requests
     // this takes care of sending an incoming request along to
@@ -31,6 +33,8 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
                 let client = client.to_owned();
+                let live_reload = server_config.live_reload.to_owned();
+                let recorder = server_config.recorder.to_owned();
                 let server_config = server_config.to_owned();
                 let preview_id = preview_id.lock().unwrap().to_owned();
                 let version = req.version();
@@ -40,12 +44,8 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
 
                 // split the request into parts so we can read
                 // what it contains and display in logs
-                let (parts, body) = req.into_parts();
-                let local_host = format!(
-                    "{}:{}",
-                    server_config.listening_address.ip().to_string(),
-                    server_config.listening_address.port().to_string()
-                );
+                let (mut parts, body) = req.into_parts();
+                let local_host = local_host(&parts.headers, server_config.listening_address);
 
                 let req_method = parts.method.to_string();
 
@@ -54,11 +54,23 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
                 let path = get_path_as_str(&parts.uri);
 
                 async move {
+                    if let Some(live_reload) = &live_reload {
+                        if parts.uri.path() == LIVE_RELOAD_PATH {
+                            return Ok(live_reload.events());
+                        }
+                        live_reload.prepare_request(&mut parts.headers);
+                    }
+                    let body = match &recorder {
+                        Some(recorder) => recorder.record(&parts, body).await?,
+                        None => body,
+                    };
+
                     // send the request to the preview service
                     let resp = preview_request(
                         Request::from_parts(parts, body),
                         client,
                         preview_id.to_owned(),
+                        &server_config.host.to_string(),
                     )
                     .await?;
                     let (mut parts, body) = resp.into_parts();
@@ -72,10 +84,14 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
                         &local_host,
                         false,
                     );
+                    let resp = match &live_reload {
+                        Some(live_reload) => live_reload.inject(resp).await?,
+                        None => resp,
+                    };
 
                     // print information about the response
                     // [2020-04-20 15:25:54] GET example.com/ HTTP/1.1 200 OK
-                    println!(
+                    output.message(&format!(
                         "[{}] {} {}{} {:?} {}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
@@ -83,7 +99,7 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
                         path,
                         version,
                         resp.status()
-                    );
+                    ));
                     Ok::<_, anyhow::Error>(resp)
                 }
             }))
@@ -91,11 +107,11 @@ pub async fn http(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -
     });
 
     let server = Server::bind(&listening_address).serve(make_service);
-    println!(
+    output.message(&format!(
         "{} Listening on http://{}",
         emoji::EAR,
         listening_address.to_string()
-    );
+    ));
     if let Err(e) = server.await {
         eprintln!("server error: {}", e);
     }
@@ -1,10 +1,10 @@
 use super::preview_request;
 use crate::commands::dev::gcs::headers::destructure_response;
+use crate::commands::dev::live_reload::LIVE_RELOAD_PATH;
 use crate::commands::dev::server_config::ServerConfig;
 use crate::commands::dev::tls;
-use crate::commands::dev::utils::{get_path_as_str, rewrite_redirect};
+use crate::commands::dev::utils::{get_path_as_str, local_host, rewrite_redirect};
 use crate::terminal::emoji;
-use crate::terminal::message::{Message, StdOut};
 use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
@@ -18,7 +18,8 @@ use tokio::net::TcpListener;
 /// performs all logic that takes an incoming request
 /// and routes it to the Workers runtime preview service
 pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>) -> Result<()> {
-    tls::generate_cert()?;
+    let output = server_config.output;
+    tls::generate_cert(output)?;
 
     // set up https client to connect to the preview service
     let https = HttpsConnector::with_native_roots();
@@ -36,6 +37,8 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
                 let client = client.to_owned();
+                let live_reload = server_config.live_reload.to_owned();
+                let recorder = server_config.recorder.to_owned();
                 let server_config = server_config.to_owned();
                 let preview_id = preview_id.lock().unwrap().to_owned();
                 let version = req.version();
@@ -45,12 +48,8 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
 
                 // split the request into parts so we can read
                 // what it contains and display in logs
-                let (parts, body) = req.into_parts();
-                let local_host = format!(
-                    "{}:{}",
-                    server_config.listening_address.ip().to_string(),
-                    server_config.listening_address.port().to_string()
-                );
+                let (mut parts, body) = req.into_parts();
+                let local_host = local_host(&parts.headers, server_config.listening_address);
 
                 let req_method = parts.method.to_string();
 
@@ -59,11 +58,23 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
                 let path = get_path_as_str(&parts.uri);
 
                 async move {
+                    if let Some(live_reload) = &live_reload {
+                        if parts.uri.path() == LIVE_RELOAD_PATH {
+                            return Ok(live_reload.events());
+                        }
+                        live_reload.prepare_request(&mut parts.headers);
+                    }
+                    let body = match &recorder {
+                        Some(recorder) => recorder.record(&parts, body).await?,
+                        None => body,
+                    };
+
                     // send the request to the preview service
                     let resp = preview_request(
                         Request::from_parts(parts, body),
                         client,
                         preview_id.to_owned(),
+                        &server_config.host.to_string(),
                     )
                     .await?;
                     let (mut parts, body) = resp.into_parts();
@@ -77,10 +88,14 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
                         &local_host,
                         true,
                     );
+                    let resp = match &live_reload {
+                        Some(live_reload) => live_reload.inject(resp).await?,
+                        None => resp,
+                    };
 
                     // print information about the response
                     // [2020-04-20 15:25:54] GET example.com/ HTTP/1.1 200 OK
-                    println!(
+                    output.message(&format!(
                         "[{}] {} {}{} {:?} {}",
                         now.format("%Y-%m-%d %H:%M:%S"),
                         req_method,
@@ -88,7 +103,7 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
                         path,
                         version,
                         resp.status()
-                    );
+                    ));
                     Ok::<_, anyhow::Error>(resp)
                 }
             }))
@@ -112,7 +127,7 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
                 Ok(tls_stream) => Ok(tls_stream),
                 Err(e) => {
                     eprintln!("Client connection error {}", e);
-                    StdOut::info("Make sure to use https and `--insecure` with curl");
+                    output.info("Make sure to use https and `--insecure` with curl");
                     Err(e)
                 }
             },
@@ -126,13 +141,13 @@ pub async fn https(server_config: ServerConfig, preview_id: Arc<Mutex<String>>)
         acceptor: incoming_tls_stream,
     })
     .serve(service);
-    println!(
+    output.message(&format!(
         "{} Listening on https://{}",
         emoji::EAR,
         listening_address.to_string()
-    );
+    ));
 
-    StdOut::info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
+    output.info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
 
     if let Err(e) = server.await {
         eprintln!("{}", e);
@@ -23,13 +23,14 @@ pub fn preview_request(
     req: Request<Body>,
     client: HyperClient<HttpsConnector<HttpConnector>>,
     preview_id: String,
+    host: &str,
 ) -> ResponseFuture {
     let (mut parts, body) = req.into_parts();
 
     let path = get_path_as_str(&parts.uri);
     let preview_id = &preview_id;
 
-    structure_request(&mut parts);
+    structure_request(&mut parts, host);
 
     parts.headers.insert(
         HeaderName::from_static("host"),
@@ -16,7 +16,7 @@ pub fn watch_for_changes(
     verbose: bool,
 ) -> Result<()> {
     let (sender, receiver) = mpsc::channel();
-    watch_and_build(&target, Some(sender), None)?;
+    watch_and_build(&target, Some(sender), None, true)?;
 
     while receiver.recv().is_ok() {
         let target = target.clone();
@@ -30,6 +30,9 @@ pub fn watch_for_changes(
         // this allows the server to route subsequent requests
         // to the proper script
         *preview_id = get_preview_id(target, None, server_config, session_id, verbose)?;
+        if let Some(live_reload) = &server_config.live_reload {
+            live_reload.reload();
+        }
     }
 
     Ok(())
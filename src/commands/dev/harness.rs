@@ -0,0 +1,220 @@
+use crate::commands::dev::hotkeys::Action;
+use crate::commands::dev::utils::wait_for_server;
+use crate::commands::dev::{Protocol, ServerConfig};
+use crate::terminal::message::{Message, Output, StdErr};
+use crate::terminal::styles;
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::thread;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A list of requests to send to the Worker, with what their responses should be.
+///
+/// ```toml
+/// [[request]]
+/// path = "/api/users"
+/// status = 200
+/// body_contains = "\"name\""
+/// ```
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(rename = "request", default)]
+    requests: Vec<TestRequest>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct TestRequest {
+    name: Option<String>,
+    #[serde(default = "default_method")]
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    /// the expected status of the response
+    status: Option<u16>,
+    body_contains: Option<String>,
+    body_equals: Option<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_owned()
+}
+
+impl TestRequest {
+    fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.method, self.path))
+    }
+
+    /// Describes how a response differs from the expected one.
+    fn check(&self, status: u16, body: &str) -> Vec<String> {
+        let mut failures = vec![];
+        if let Some(expected) = self.status {
+            if status != expected {
+                failures.push(format!("expected status {}, got {}", expected, status));
+            }
+        }
+        if let Some(expected) = &self.body_contains {
+            if !body.contains(expected.as_str()) {
+                failures.push(format!("expected the body to contain {:?}", expected));
+            }
+        }
+        if let Some(expected) = &self.body_equals {
+            if body != expected {
+                failures.push(format!(
+                    "expected the body to be {:?}, got {:?}",
+                    expected, body
+                ));
+            }
+        }
+        failures
+    }
+}
+
+/// Sends the requests of a spec to the dev server once it is listening, prints
+/// which of them passed, and then asks the dev session to exit, with an error if
+/// any failed.
+pub fn run(
+    path: &Path,
+    server_config: &ServerConfig,
+    local_protocol: Protocol,
+    actions: UnboundedSender<Action>,
+) -> Result<()> {
+    let spec = parse(path, &fs::read_to_string(path)?)?;
+    if spec.requests.is_empty() {
+        anyhow::bail!("{} has no [[request]] to test", path.display())
+    }
+
+    let listening_address = server_config.listening_address;
+    let upstream_address = server_config.upstream_address;
+    let output = server_config.output;
+    thread::spawn(move || {
+        let code = match test(
+            spec,
+            listening_address,
+            upstream_address,
+            local_protocol,
+            output,
+        ) {
+            Ok(0) => 0,
+            Ok(_) => 1,
+            Err(e) => {
+                StdErr::user_error(&format!("Failed to run the tests: {}", e));
+                1
+            }
+        };
+        let _ = actions.send(Action::Exit(code));
+    });
+    Ok(())
+}
+
+/// Runs the tests, returning how many failed.
+fn test(
+    spec: Spec,
+    listening_address: SocketAddr,
+    upstream_address: Option<SocketAddr>,
+    local_protocol: Protocol,
+    output: Output,
+) -> Result<usize> {
+    let (client, base_url) = wait_for_server(listening_address, upstream_address, local_protocol)?;
+
+    let mut failed = 0;
+    for request in spec.requests.iter() {
+        let mut builder = client.request(
+            request.method.parse()?,
+            &format!("{}{}", base_url, request.path),
+        );
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.to_owned());
+        }
+
+        let failures = match builder.send() {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                request.check(status, &response.text().unwrap_or_default())
+            }
+            Err(e) => vec![e.to_string()],
+        };
+        if failures.is_empty() {
+            output.message(&format!("{} {}", styles::cyan("PASS"), request.name()));
+        } else {
+            failed += 1;
+            output.message(&format!("{} {}", styles::warning("FAIL"), request.name()));
+            for failure in failures {
+                output.message(&format!("    {}", failure));
+            }
+        }
+    }
+
+    let passed = spec.requests.len() - failed;
+    if failed == 0 {
+        output.success(&format!("{} passed", passed));
+    } else {
+        StdErr::user_error(&format!("{} passed, {} failed", passed, failed));
+    }
+    Ok(failed)
+}
+
+/// Parses a spec as JSON when the file ends in `.json`, and as TOML otherwise.
+fn parse(path: &Path, contents: &str) -> Result<Spec> {
+    if path
+        .extension()
+        .map_or(false, |extension| extension == "json")
+    {
+        Ok(serde_json::from_str(contents)?)
+    } else {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_toml_and_json_specs() {
+        let toml = parse(
+            Path::new("tests.toml"),
+            "[[request]]\npath = \"/\"\nstatus = 200\n\n[[request]]\nmethod = \"POST\"\npath = \"/users\"\nbody = \"{}\"\nstatus = 201\n",
+        )
+        .unwrap();
+        let json = parse(
+            Path::new("tests.json"),
+            r#"{"request": [{"path": "/", "status": 200}, {"method": "POST", "path": "/users", "body": "{}", "status": 201}]}"#,
+        )
+        .unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(toml.requests[0].method, "GET");
+
+        assert!(parse(Path::new("tests.toml"), "[[request]]\nstatuss = 200\n").is_err());
+    }
+
+    #[test]
+    fn it_checks_responses() {
+        let request = TestRequest {
+            name: None,
+            method: "GET".to_owned(),
+            path: "/".to_owned(),
+            headers: HashMap::new(),
+            body: None,
+            status: Some(200),
+            body_contains: Some("hello".to_owned()),
+            body_equals: None,
+        };
+        assert!(request.check(200, "hello world").is_empty());
+        assert_eq!(request.check(404, "not found").len(), 2);
+        assert_eq!(request.name(), "GET /");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::commands::tail;
+use crate::terminal::message::Output;
+use crate::terminal::{open_browser, styles};
+
+use anyhow::Result;
+use console::{Key, Term};
+use std::ffi::OsString;
+use std::process::Command;
+use std::thread;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// What a hotkey or the test harness asks of the running dev session, as it has to
+/// be stopped first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Exit with this code
+    Exit(i32),
+    ToggleLocal,
+}
+
+/// Listens for hotkeys on the terminal, when there is someone there to press them.
+///
+/// Clearing the console and opening the browser are handled right away,
+/// while exiting and toggling local mode are sent to the dev session.
+pub fn listen(url: String, actions: UnboundedSender<Action>, output: Output) {
+    if !console::user_attended() {
+        return;
+    }
+
+    output.info(&format!(
+        "Press {} to open a browser, {} to clear the console, {} to toggle local mode, {} to exit",
+        styles::highlight("[b]"),
+        styles::highlight("[c]"),
+        styles::highlight("[l]"),
+        styles::highlight("[x]"),
+    ));
+
+    thread::spawn(move || {
+        let term = Term::stdout();
+        while let Ok(key) = term.read_key() {
+            let action = match key {
+                Key::Char('b') => {
+                    if let Err(e) = open_browser(&url) {
+                        log::debug!("Failed to open a browser: {}", e);
+                    }
+                    continue;
+                }
+                Key::Char('c') => {
+                    let _ = term.clear_screen();
+                    continue;
+                }
+                Key::Char('l') => Action::ToggleLocal,
+                // the terminal is raw while waiting for a key, so ctrl-c arrives as a key too
+                Key::Char('x') | Key::Char('\u{3}') => Action::Exit(0),
+                _ => continue,
+            };
+            // stop listening, so the terminal is not left raw as the session ends
+            let _ = actions.send(action);
+            break;
+        }
+    });
+}
+
+/// Waits for the next action for the dev session, which is also asked to exit
+/// when wrangler is asked to shut down.
+pub async fn next(actions: &mut UnboundedReceiver<Action>) -> Action {
+    tokio::select! {
+        Some(action) = actions.recv() => action,
+        _ = tail::shutdown() => Action::Exit(0),
+    }
+}
+
+/// Runs an action once the dev session has stopped.
+pub fn run(action: Action, local: bool, output: Output) -> Result<()> {
+    match action {
+        Action::Exit(0) => Ok(()),
+        // everything the session started has been stopped, so it's safe to exit right away
+        Action::Exit(code) => std::process::exit(code),
+        Action::ToggleLocal => restart(!local, output),
+    }
+}
+
+/// Restarts `wrangler dev`, with or without `--local`.
+fn restart(local: bool, output: Output) -> Result<()> {
+    let mut args = toggle_local(std::env::args_os().skip(1), local);
+    let mut command = Command::new(std::env::current_exe()?);
+    command.args(args.drain(..));
+    output.info(&format!(
+        "Restarting {}",
+        styles::highlight(if local {
+            "wrangler dev --local"
+        } else {
+            "wrangler dev"
+        })
+    ));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // exec only returns if it failed
+        Err(command.exec().into())
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status()?;
+        if !status.success() {
+            anyhow::bail!("wrangler dev exited with {}", status);
+        }
+        Ok(())
+    }
+}
+
+fn toggle_local(args: impl Iterator<Item = OsString>, local: bool) -> Vec<OsString> {
+    let mut args = args.filter(|arg| arg != "--local").collect::<Vec<_>>();
+    if local {
+        args.push("--local".into());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_toggles_local_mode() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            toggle_local(args(&["dev", "--port", "8000"]).into_iter(), true),
+            args(&["dev", "--port", "8000", "--local"])
+        );
+        assert_eq!(
+            toggle_local(
+                args(&["dev", "--local", "--port", "8000"]).into_iter(),
+                false
+            ),
+            args(&["dev", "--port", "8000"])
+        );
+    }
+}
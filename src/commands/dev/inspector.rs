@@ -0,0 +1,105 @@
+use crate::commands::dev::utils::proxy_websocket;
+use crate::terminal::message::Output;
+
+use anyhow::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::net::SocketAddr;
+use url::Url;
+
+/// The path DevTools connects to, which is proxied to the inspector of the preview session.
+const INSPECTOR_PATH: &str = "/ws";
+
+/// Serves an inspector that Chrome DevTools can find from chrome://inspect.
+///
+/// The HTTP endpoints DevTools uses to discover the Worker are answered locally,
+/// while its WebSocket is proxied to the inspector of the preview session.
+pub async fn serve(websocket_url: Url, port: u16, output: Output) -> Result<()> {
+    let listening_address = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let make_service = make_service_fn(move |_| {
+        let websocket_url = websocket_url.to_owned();
+        async move {
+            Ok::<_, anyhow::Error>(service_fn(move |req| {
+                let websocket_url = websocket_url.to_owned();
+                async move { handle(req, websocket_url, listening_address) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&listening_address) {
+        Ok(server) => server.serve(make_service),
+        Err(_) => {
+            output.warn(&format!(
+                "{} is unavailable, so the inspector will not be started. Try another port with --inspector-port",
+                listening_address
+            ));
+            return Ok(());
+        }
+    };
+    output.info(&format!(
+        "Open chrome://inspect to debug the Worker, with DevTools listening on {}",
+        listening_address
+    ));
+    server.await?;
+    Ok(())
+}
+
+fn handle(
+    req: Request<Body>,
+    websocket_url: Url,
+    listening_address: SocketAddr,
+) -> Result<Response<Body>> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/json") | (&Method::GET, "/json/list") => {
+            json(&targets(listening_address))?
+        }
+        (&Method::GET, "/json/version") => json(&serde_json::json!({
+            "Browser": format!("wrangler/v{}", env!("CARGO_PKG_VERSION")),
+            "Protocol-Version": "1.3",
+        }))?,
+        (&Method::GET, INSPECTOR_PATH) => proxy_websocket(req, websocket_url)?,
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())?,
+    };
+    Ok(response)
+}
+
+/// The targets DevTools lists, which is only ever the Worker.
+fn targets(listening_address: SocketAddr) -> serde_json::Value {
+    let socket = format!("{}{}", listening_address, INSPECTOR_PATH);
+    serde_json::json!([{
+        "id": "wrangler",
+        "type": "node",
+        "title": "wrangler dev",
+        "description": "Cloudflare Worker",
+        "url": "worker.js",
+        "webSocketDebuggerUrl": format!("ws://{}", socket),
+        "devtoolsFrontendUrl": format!(
+            "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws={}",
+            socket
+        ),
+    }])
+}
+
+fn json(value: &serde_json::Value) -> Result<Response<Body>> {
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(value)?))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_lists_the_worker_as_a_target() {
+        let targets = targets(SocketAddr::from(([127, 0, 0, 1], 9229)));
+        assert_eq!(targets[0]["webSocketDebuggerUrl"], "ws://127.0.0.1:9229/ws");
+        assert_eq!(
+            targets[0]["devtoolsFrontendUrl"],
+            "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws=127.0.0.1:9229/ws"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use hyper::header::{HeaderMap, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Response};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// The path on the local server that pages listen on for reloads.
+pub const LIVE_RELOAD_PATH: &str = "/__wrangler/live-reload";
+
+const LIVE_RELOAD_SCRIPT: &str = "<script>new EventSource(\"/__wrangler/live-reload\").onmessage = function () { location.reload(); };</script>";
+
+/// Reloads the pages that are open in a browser each time the Worker is re-uploaded.
+///
+/// A small script is injected into HTML responses, which listens for reloads
+/// as server-sent events from `LIVE_RELOAD_PATH`.
+#[derive(Clone, Debug)]
+pub struct LiveReload {
+    sender: broadcast::Sender<()>,
+}
+
+impl LiveReload {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(16);
+        Self { sender }
+    }
+
+    /// Tells every open page to reload.
+    pub fn reload(&self) {
+        // this only fails when there are no pages open, which is fine
+        let _ = self.sender.send(());
+    }
+
+    /// Responds with a stream of reload events, which is open until the page goes away.
+    pub fn events(&self) -> Response<Body> {
+        let mut receiver = self.sender.subscribe();
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(()) | Err(RecvError::Lagged(_)) => {
+                        if sender.send_data("data: reload\n\n".into()).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        Response::builder()
+            .header(CONTENT_TYPE, "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .body(body)
+            .expect("Could not create live reload response")
+    }
+
+    /// Asks for an uncompressed response, so the script can be injected into it.
+    pub fn prepare_request(&self, headers: &mut HeaderMap) {
+        headers.remove(ACCEPT_ENCODING);
+    }
+
+    /// Injects the live reload script into HTML responses.
+    pub async fn inject(&self, resp: Response<Body>) -> Result<Response<Body>> {
+        if !is_html(resp.headers()) {
+            return Ok(resp);
+        }
+
+        let (mut parts, body) = resp.into_parts();
+        let mut html = hyper::body::to_bytes(body).await?.to_vec();
+        html.extend_from_slice(LIVE_RELOAD_SCRIPT.as_bytes());
+        parts.headers.remove(CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::from(html)))
+    }
+}
+
+impl Default for LiveReload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_html(headers: &HeaderMap) -> bool {
+    let html = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("text/html"));
+    html && !headers.contains_key(CONTENT_ENCODING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn it_only_injects_into_uncompressed_html() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        assert!(is_html(&headers));
+
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        assert!(!is_html(&headers));
+
+        headers.remove(CONTENT_ENCODING);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert!(!is_html(&headers));
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// The properties of `request.cf` that Miniflare sets by default, for a request to DFW from Austin.
+const DEFAULT_CF: &str = r#"{
+    "asn": 395747,
+    "colo": "DFW",
+    "city": "Austin",
+    "region": "Texas",
+    "regionCode": "TX",
+    "metroCode": "635",
+    "postalCode": "78701",
+    "country": "US",
+    "continent": "NA",
+    "timezone": "America/Chicago",
+    "latitude": "30.27130",
+    "longitude": "-97.74260",
+    "clientTcpRtt": 0,
+    "httpProtocol": "HTTP/1.1",
+    "requestPriority": "weight=192;exclusive=0",
+    "tlsCipher": "AEAD-AES128-GCM-SHA256",
+    "tlsVersion": "TLSv1.3",
+    "tlsClientAuth": {
+        "certPresented": "0",
+        "certVerified": "NONE",
+        "certRevoked": "0",
+        "certIssuerDN": "",
+        "certSubjectDN": "",
+        "certIssuerDNRFC2253": "",
+        "certSubjectDNRFC2253": "",
+        "certIssuerDNLegacy": "",
+        "certSubjectDNLegacy": "",
+        "certSerial": "",
+        "certIssuerSerial": "",
+        "certSKI": "",
+        "certIssuerSKI": "",
+        "certFingerprintSHA1": "",
+        "certFingerprintSHA256": "",
+        "certNotBefore": "",
+        "certNotAfter": ""
+    }
+}"#;
+
+/// Writes the `request.cf` of every request to the Worker, with the given properties
+/// in place of the defaults.
+///
+/// Miniflare reads the file instead of fetching the properties from Cloudflare,
+/// as long as it was written in the last 30 days.
+pub fn write(properties: &Map<String, Value>, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&merge(properties)?)?)?;
+    Ok(())
+}
+
+fn merge(properties: &Map<String, Value>) -> Result<Map<String, Value>> {
+    let mut cf: Map<String, Value> = serde_json::from_str(DEFAULT_CF)?;
+    cf.extend(properties.clone());
+    Ok(cf)
+}
+
+/// Parses the value of a `--cf` flag, which is JSON when it can be, e.g. `asn=13335`,
+/// and a string otherwise, e.g. `country=GB`.
+pub fn parse_value(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_overrides_default_properties() {
+        let mut properties = Map::new();
+        properties.insert("country".to_owned(), parse_value("GB"));
+        properties.insert("asn".to_owned(), parse_value("13335"));
+        properties.insert("tlsVersion".to_owned(), parse_value("TLSv1.2"));
+
+        let cf = merge(&properties).unwrap();
+        assert_eq!(cf["country"], "GB");
+        assert_eq!(cf["asn"], 13335);
+        assert_eq!(cf["tlsVersion"], "TLSv1.2");
+        assert_eq!(cf["colo"], "DFW");
+    }
+}
@@ -0,0 +1,666 @@
+mod cf;
+mod proxy;
+
+pub use cf::parse_value as parse_cf_value;
+
+use crate::build::build_dev_target;
+use crate::commands::dev::hotkeys::{self, Action};
+use crate::commands::dev::utils::script_path;
+use crate::commands::dev::{Protocol, ServerConfig};
+use crate::install;
+use crate::settings::toml::Target;
+use crate::terminal::message::Output;
+use crate::terminal::{emoji, styles};
+use crate::watch::watch_and_build;
+
+use anyhow::Result;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tokio::runtime::Runtime as TokioRuntime;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// The version of Miniflare that runs Workers locally. It's pinned to an exact version,
+/// so once npm has cached it, it can be run again without a network connection.
+const MINIFLARE_PACKAGE: &str = "miniflare@2.13.0";
+
+/// Where Miniflare keeps the state of emulated bindings by default, so it survives restarts.
+const STATE_DIR: &str = ".wrangler/state";
+
+/// The file in the state directory with the simulated `request.cf`.
+const CF_FILE: &str = "cf.json";
+
+/// Other Workers to run alongside this one, and the service bindings to them.
+#[derive(Debug, Clone, Default)]
+pub struct Services {
+    /// The Workers to mount by name, from the directories of their projects
+    pub mounts: Vec<(String, PathBuf)>,
+    /// The service bindings, from binding name to the name of a mounted Worker
+    pub bindings: Vec<(String, String)>,
+}
+
+/// `wrangler dev --local` runs the Worker on this machine with Miniflare, a simulator
+/// for the Workers runtime, instead of uploading it to the Cloudflare preview service.
+///
+/// Bindings are emulated by Miniflare, so this works without logging in. Miniflare is
+/// downloaded from npm the first time, after which this also works offline.
+/// `caches.default` is Miniflare's Cache API, which respects `Cache-Control` and `Expires`,
+/// and its entries are kept in the state directory like those of the other bindings.
+pub fn dev(
+    target: Target,
+    server_config: ServerConfig,
+    local_protocol: Protocol,
+    services: Services,
+    mut actions: UnboundedReceiver<Action>,
+    verbose: bool,
+) -> Result<Option<Action>> {
+    if which::which("npx").is_err() {
+        anyhow::bail!(
+            "{} `wrangler dev --local` requires Node.js, which can be installed from https://nodejs.org",
+            emoji::WARN
+        )
+    }
+
+    let output = server_config.output;
+    if server_config.inspector_port.is_some() {
+        output.warn("`wrangler dev --local` does not support the inspector yet.");
+    }
+    if server_config.repl.is_some() {
+        output.warn("`wrangler dev --local` does not support the REPL yet.");
+    }
+    if server_config.log_subrequests {
+        output.warn("`wrangler dev --local` does not support logging subrequests yet.");
+    }
+
+    let miniflare_address = match server_config.upstream_address {
+        Some(address) => address,
+        None => miniflare_address()?,
+    };
+    let args = miniflare_args(
+        &target,
+        &server_config,
+        miniflare_address,
+        &services,
+        verbose,
+    )?;
+
+    if let Some(properties) = &server_config.cf {
+        cf::write(properties, Path::new(&state_dir(&server_config, CF_FILE)))?;
+    }
+
+    // before serving requests we must first build the Worker
+    build_dev_target(&target)?;
+
+    // then rebuild it on changes, which Miniflare watches for to reload the Worker
+    watch_and_build(&target, None, None, true)?;
+
+    let mut command = build_npx_command();
+    command
+        .arg("--yes")
+        .arg("--prefer-offline")
+        .arg(MINIFLARE_PACKAGE)
+        .args(&args);
+    command.kill_on_drop(true);
+    // what the Worker logs is copied to stderr, as stdout is kept for the URL
+    if output == Output::Json {
+        command.stdout(Stdio::piped());
+    }
+
+    output.working(&format!(
+        "Running {} locally with {}...",
+        styles::bold(&target.name),
+        styles::highlight("miniflare")
+    ));
+    output.info(&format!(
+        "Dispatch a scheduled event to the Worker with {}",
+        styles::highlight("/__scheduled?cron=*+*+*+*+*")
+    ));
+
+    let runtime = TokioRuntime::new()?;
+    let action = runtime.block_on(async {
+        let mut miniflare = command.spawn()?;
+        if let Some(mut stdout) = miniflare.stdout.take() {
+            tokio::spawn(async move {
+                let _ = tokio::io::copy(&mut stdout, &mut tokio::io::stderr()).await;
+            });
+        }
+        let proxy = proxy::serve(
+            server_config.listening_address,
+            miniflare_address,
+            local_protocol,
+            server_config.recorder.to_owned(),
+            output,
+        );
+        let action = tokio::select! {
+            status = miniflare.wait() => {
+                let status = status?;
+                if !status.success() {
+                    anyhow::bail!("npx miniflare exited with {}", status);
+                }
+                return Ok(None);
+            }
+            result = proxy => result.map(|_| None),
+            action = hotkeys::next(&mut actions) => Ok(Some(action)),
+        };
+        // wait for Miniflare to exit, so its port is free before the session is restarted
+        let _ = miniflare.kill().await;
+        action
+    })?;
+    // the proxy stops listening as it is dropped with the runtime
+    drop(runtime);
+
+    Ok(action)
+}
+
+/// Picks an address for Miniflare, which listens on a port of its own behind a proxy
+/// on the listening address.
+pub fn miniflare_address() -> Result<SocketAddr> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()?)
+}
+
+/// Builds the arguments for the Miniflare CLI, from the Worker's configuration.
+fn miniflare_args(
+    target: &Target,
+    server_config: &ServerConfig,
+    miniflare_address: SocketAddr,
+    services: &Services,
+    verbose: bool,
+) -> Result<Vec<String>> {
+    let (script_path, modules) = match script_path(target)? {
+        Some(script) => script,
+        None => anyhow::bail!(
+            "{} `wrangler dev --local` does not support Rust type projects yet.",
+            emoji::WARN
+        ),
+    };
+    let mut args = vec![
+        script_path.to_string_lossy().to_string(),
+        "--host".to_owned(),
+        miniflare_address.ip().to_string(),
+        "--port".to_owned(),
+        miniflare_address.port().to_string(),
+        "--watch".to_owned(),
+    ];
+    if server_config.live_reload.is_some() {
+        args.push("--live-reload".to_owned());
+    }
+    if modules {
+        args.push("--modules".to_owned());
+    }
+    if verbose {
+        args.push("--verbose".to_owned());
+    }
+    if server_config.cf.is_some() {
+        args.push("--cf-fetch".to_owned());
+        args.push(state_dir(server_config, CF_FILE));
+    }
+    // Miniflare dispatches scheduled events on these schedules, in UTC like the edge
+    for cron in server_config.crons.iter() {
+        args.push("--cron".to_owned());
+        args.push(cron.to_owned());
+    }
+    if let Some(date) = &target.compatibility_date {
+        args.push("--compat-date".to_owned());
+        args.push(date.to_owned());
+    }
+    for flag in target.compatibility_flags.iter() {
+        args.push("--compat-flag".to_owned());
+        args.push(flag.to_owned());
+    }
+    if let Some(vars) = &target.vars {
+        let mut vars = vars.iter().collect::<Vec<_>>();
+        vars.sort();
+        for (key, value) in vars {
+            args.push("--binding".to_owned());
+            args.push(format!("{}={}", key, value));
+        }
+    }
+    // the Cache API is always available, so its state is always persisted
+    args.push("--cache-persist".to_owned());
+    args.push(state_dir(server_config, "cache"));
+    for namespace in target.kv_namespaces.iter() {
+        args.push("--kv".to_owned());
+        args.push(namespace.binding.to_owned());
+    }
+    if !target.kv_namespaces.is_empty() {
+        // each namespace is stored in its own directory, named after its binding
+        args.push("--kv-persist".to_owned());
+        args.push(state_dir(server_config, "kv"));
+    }
+    // Miniflare serves the bucket from __STATIC_CONTENT, along with its manifest,
+    // so sites are not uploaded to a preview namespace
+    if let Some(site) = &target.site {
+        args.push("--site".to_owned());
+        args.push(site.bucket.to_string_lossy().to_string());
+        for pattern in site.include.iter().flatten() {
+            args.push("--site-include".to_owned());
+            args.push(pattern.to_owned());
+        }
+        for pattern in site.exclude.iter().flatten() {
+            args.push("--site-exclude".to_owned());
+            args.push(pattern.to_owned());
+        }
+    }
+    let classes = target
+        .durable_objects
+        .as_ref()
+        .and_then(|durable_objects| durable_objects.classes.as_ref());
+    if let Some(classes) = classes {
+        for class in classes.iter() {
+            // classes in other Workers are run from the Workers mounted by that name
+            let script_name = class
+                .script_name
+                .as_ref()
+                .filter(|script_name| **script_name != target.name);
+            args.push("--do".to_owned());
+            args.push(match script_name {
+                Some(script_name) => {
+                    if !services.mounts.iter().any(|(mount, _)| mount == script_name) {
+                        anyhow::bail!(
+                            "The Durable Object {} is in the worker {}, add it with `--mount {}=<path>`",
+                            class.binding,
+                            script_name,
+                            script_name
+                        )
+                    }
+                    format!("{}={}@{}", class.binding, class.class_name, script_name)
+                }
+                None => format!("{}={}", class.binding, class.class_name),
+            });
+        }
+        if !classes.is_empty() {
+            // objects are stored in their own directories, and are only ever run one at a time
+            args.push("--do-persist".to_owned());
+            args.push(state_dir(server_config, "do"));
+        }
+    }
+    if let Some(blobs) = &target.text_blobs {
+        for (key, path) in blobs.iter() {
+            args.push("--text-blob".to_owned());
+            args.push(format!("{}={}", key, path.display()));
+        }
+    }
+    if let Some(modules) = &target.wasm_modules {
+        for (key, path) in modules.iter() {
+            args.push("--wasm".to_owned());
+            args.push(format!("{}={}", key, path.display()));
+        }
+    }
+    // mounted Workers are built and configured from their own wrangler.toml
+    for (name, path) in services.mounts.iter() {
+        args.push("--mount".to_owned());
+        args.push(format!("{}={}", name, path.display()));
+    }
+    for (binding, name) in services.bindings.iter() {
+        if !services.mounts.iter().any(|(mount, _)| mount == name) {
+            anyhow::bail!(
+                "No worker named {} is mounted for the service binding {}, add it with `--mount {}=<path>`",
+                name,
+                binding,
+                name
+            )
+        }
+        args.push("--service".to_owned());
+        args.push(format!("{}={}", binding, name));
+    }
+    Ok(args)
+}
+
+fn state_dir(server_config: &ServerConfig, binding_type: &str) -> String {
+    server_config
+        .persist_to
+        .as_deref()
+        .unwrap_or_else(|| Path::new(STATE_DIR))
+        .join(binding_type)
+        .to_string_lossy()
+        .to_string()
+}
+
+// On Windows, `npx` is a shell script rather than a binary, so it needs to be run with `cmd /C`.
+fn build_npx_command() -> Command {
+    if install::target::WINDOWS {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command.arg("npx");
+        command
+    } else {
+        Command::new("npx")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::{
+        Builder, DurableObjects, DurableObjectsClass, KvNamespace, Site, TargetType, UploadFormat,
+    };
+    use std::collections::HashMap;
+
+    fn server_config() -> ServerConfig {
+        ServerConfig::new(
+            None,
+            Ipv4Addr::LOCALHOST.into(),
+            0,
+            Protocol::Https,
+            Output::PlainText,
+        )
+        .unwrap()
+    }
+
+    fn miniflare_address() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 8788))
+    }
+
+    #[test]
+    fn it_builds_miniflare_args_for_modules() {
+        let mut vars = HashMap::new();
+        vars.insert("GREETING".to_owned(), "hello".to_owned());
+        let target = Target {
+            target_type: TargetType::JavaScript,
+            build: Some(Builder {
+                command: None,
+                cwd: PathBuf::from("."),
+                watch_dir: PathBuf::from("src"),
+                upload: UploadFormat::Modules {
+                    main: "index.mjs".to_owned(),
+                    dir: PathBuf::from("dist"),
+                    rules: None,
+                },
+            }),
+            vars: Some(vars),
+            compatibility_date: Some("2021-11-01".to_owned()),
+            ..Default::default()
+        };
+        let server_config = server_config();
+        let miniflare_address = miniflare_address();
+
+        let args = miniflare_args(
+            &target,
+            &server_config,
+            miniflare_address,
+            &Services::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                PathBuf::from("dist")
+                    .join("index.mjs")
+                    .to_string_lossy()
+                    .to_string(),
+                "--host".to_owned(),
+                "127.0.0.1".to_owned(),
+                "--port".to_owned(),
+                "8788".to_owned(),
+                "--watch".to_owned(),
+                "--modules".to_owned(),
+                "--compat-date".to_owned(),
+                "2021-11-01".to_owned(),
+                "--binding".to_owned(),
+                "GREETING=hello".to_owned(),
+                "--cache-persist".to_owned(),
+                PathBuf::from(".wrangler")
+                    .join("state")
+                    .join("cache")
+                    .to_string_lossy()
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_persists_kv_namespaces() {
+        let target = Target {
+            target_type: TargetType::Webpack,
+            kv_namespaces: vec![
+                KvNamespace {
+                    id: "0f2ac74b498b48028cb68387c421e279".to_owned(),
+                    binding: "CACHE".to_owned(),
+                },
+                KvNamespace {
+                    id: "b0b45e6e8a4d4d4fb4e96b1e5c5e9d8a".to_owned(),
+                    binding: "USERS".to_owned(),
+                },
+            ],
+            ..Default::default()
+        };
+        let server_config = server_config();
+        let miniflare_address = miniflare_address();
+
+        let args = miniflare_args(
+            &target,
+            &server_config,
+            miniflare_address,
+            &Services::default(),
+            false,
+        )
+        .unwrap();
+        let kv_args = args
+            .iter()
+            .skip_while(|arg| *arg != "--kv")
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kv_args,
+            vec![
+                "--kv".to_owned(),
+                "CACHE".to_owned(),
+                "--kv".to_owned(),
+                "USERS".to_owned(),
+                "--kv-persist".to_owned(),
+                PathBuf::from(".wrangler")
+                    .join("state")
+                    .join("kv")
+                    .to_string_lossy()
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_persists_state_to_a_given_directory() {
+        let target = Target {
+            target_type: TargetType::Webpack,
+            kv_namespaces: vec![KvNamespace {
+                id: "0f2ac74b498b48028cb68387c421e279".to_owned(),
+                binding: "CACHE".to_owned(),
+            }],
+            ..Default::default()
+        };
+        let mut server_config = server_config();
+        server_config.persist_to = Some(PathBuf::from("fixtures"));
+        let miniflare_address = miniflare_address();
+
+        let args = miniflare_args(
+            &target,
+            &server_config,
+            miniflare_address,
+            &Services::default(),
+            false,
+        )
+        .unwrap();
+        let fixture = |name: &str| {
+            PathBuf::from("fixtures")
+                .join(name)
+                .to_string_lossy()
+                .to_string()
+        };
+        assert!(args.contains(&fixture("cache")));
+        assert!(args.contains(&fixture("kv")));
+    }
+
+    #[test]
+    fn it_fires_cron_triggers() {
+        let target = Target {
+            target_type: TargetType::Webpack,
+            ..Default::default()
+        };
+        let mut server_config = server_config();
+        server_config.crons = vec!["*/5 * * * *".to_owned(), "0 0 * * MON".to_owned()];
+        let miniflare_address = miniflare_address();
+
+        let args = miniflare_args(
+            &target,
+            &server_config,
+            miniflare_address,
+            &Services::default(),
+            false,
+        )
+        .unwrap();
+        let cron_args = args
+            .iter()
+            .skip_while(|arg| *arg != "--cron")
+            .take(4)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cron_args,
+            vec![
+                "--cron".to_owned(),
+                "*/5 * * * *".to_owned(),
+                "--cron".to_owned(),
+                "0 0 * * MON".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_serves_site_buckets() {
+        let target = Target {
+            target_type: TargetType::Webpack,
+            site: Some(Site {
+                bucket: PathBuf::from("public"),
+                entry_point: None,
+                include: None,
+                exclude: Some(vec!["*.map".to_owned()]),
+            }),
+            ..Default::default()
+        };
+        let server_config = server_config();
+        let miniflare_address = miniflare_address();
+
+        let args = miniflare_args(
+            &target,
+            &server_config,
+            miniflare_address,
+            &Services::default(),
+            false,
+        )
+        .unwrap();
+        let site_args = args
+            .iter()
+            .skip_while(|arg| *arg != "--site")
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            site_args,
+            vec![
+                "--site".to_owned(),
+                "public".to_owned(),
+                "--site-exclude".to_owned(),
+                "*.map".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_binds_services_to_mounted_workers() {
+        let target = Target {
+            target_type: TargetType::Webpack,
+            ..Default::default()
+        };
+        let server_config = server_config();
+        let miniflare_address = miniflare_address();
+        let mut services = Services {
+            mounts: vec![("auth".to_owned(), PathBuf::from("../auth-worker"))],
+            bindings: vec![("AUTH".to_owned(), "auth".to_owned())],
+        };
+
+        let args =
+            miniflare_args(&target, &server_config, miniflare_address, &services, false).unwrap();
+        let service_args = args
+            .iter()
+            .skip_while(|arg| *arg != "--mount")
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            service_args,
+            vec![
+                "--mount".to_owned(),
+                format!("auth={}", PathBuf::from("../auth-worker").display()),
+                "--service".to_owned(),
+                "AUTH=auth".to_owned(),
+            ]
+        );
+
+        services
+            .bindings
+            .push(("USERS".to_owned(), "users".to_owned()));
+        assert!(
+            miniflare_args(&target, &server_config, miniflare_address, &services, false).is_err()
+        );
+    }
+
+    #[test]
+    fn it_runs_durable_objects_locally() {
+        let mut target = Target {
+            name: "chat".to_owned(),
+            target_type: TargetType::Webpack,
+            durable_objects: Some(DurableObjects {
+                classes: Some(vec![
+                    DurableObjectsClass {
+                        binding: "ROOMS".to_owned(),
+                        class_name: "ChatRoom".to_owned(),
+                        script_name: None,
+                    },
+                    DurableObjectsClass {
+                        binding: "LIMITERS".to_owned(),
+                        class_name: "RateLimiter".to_owned(),
+                        script_name: Some("limiter".to_owned()),
+                    },
+                ]),
+            }),
+            ..Default::default()
+        };
+        let server_config = server_config();
+        let miniflare_address = miniflare_address();
+        let services = Services {
+            mounts: vec![("limiter".to_owned(), PathBuf::from("../limiter"))],
+            bindings: vec![],
+        };
+
+        let args =
+            miniflare_args(&target, &server_config, miniflare_address, &services, false).unwrap();
+        let do_args = args
+            .iter()
+            .skip_while(|arg| *arg != "--do")
+            .take(6)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            do_args,
+            vec![
+                "--do".to_owned(),
+                "ROOMS=ChatRoom".to_owned(),
+                "--do".to_owned(),
+                "LIMITERS=RateLimiter@limiter".to_owned(),
+                "--do-persist".to_owned(),
+                state_dir(&server_config, "do"),
+            ]
+        );
+
+        target
+            .durable_objects
+            .as_mut()
+            .unwrap()
+            .classes
+            .as_mut()
+            .unwrap()[1]
+            .script_name = Some("missing".to_owned());
+        assert!(
+            miniflare_args(&target, &server_config, miniflare_address, &services, false).is_err()
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::commands::dev::utils::{get_path_as_str, is_websocket, proxy_websocket};
+use crate::commands::dev::{tls, Protocol, Recorder};
+use crate::terminal::emoji;
+use crate::terminal::message::Output;
+
+use anyhow::Result;
+use futures_util::stream::{self, StreamExt};
+use hyper::client::HttpConnector;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client as HyperClient, Request, Response, Server, Uri};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use url::Url;
+
+/// The route that dispatches a scheduled event to the Worker, e.g. `/__scheduled?cron=*+*+*+*+*`
+const SCHEDULED_PATH: &str = "/__scheduled";
+/// Where Miniflare dispatches scheduled events from.
+const MINIFLARE_SCHEDULED_PATH: &str = "/cdn-cgi/mf/scheduled";
+
+/// Serves the Worker on the listening address, by proxying requests to Miniflare.
+pub async fn serve(
+    listening_address: SocketAddr,
+    miniflare_address: SocketAddr,
+    local_protocol: Protocol,
+    recorder: Option<Recorder>,
+    output: Output,
+) -> Result<()> {
+    let client = HyperClient::new();
+
+    let make_service = move || {
+        let client = client.to_owned();
+        let recorder = recorder.to_owned();
+        async move {
+            Ok::<_, anyhow::Error>(service_fn(move |req| {
+                proxy(
+                    req,
+                    client.to_owned(),
+                    miniflare_address,
+                    recorder.to_owned(),
+                )
+            }))
+        }
+    };
+
+    match local_protocol {
+        Protocol::Http => {
+            let server =
+                Server::bind(&listening_address).serve(make_service_fn(move |_| make_service()));
+            output.message(&format!(
+                "{} Listening on http://{}",
+                emoji::EAR,
+                listening_address
+            ));
+            server.await?;
+        }
+        Protocol::Https => {
+            tls::generate_cert(output)?;
+            let tcp = TcpListener::bind(&listening_address).await?;
+            let tls_acceptor = tls::get_tls_acceptor()?;
+
+            let incoming_tls_stream = stream::unfold(tcp, move |tcp| {
+                let tls_acceptor = tls_acceptor.clone();
+                async move {
+                    let result = match tcp.accept().await {
+                        Ok((tcp_stream, _addr)) => tls_acceptor.accept(tcp_stream).await,
+                        Err(e) => Err(e),
+                    };
+                    Some((result, tcp))
+                }
+            })
+            // a client that fails the handshake should not stop the server
+            .filter_map(move |result| async move {
+                match result {
+                    Ok(tls_stream) => Some(Ok(tls_stream)),
+                    Err(e) => {
+                        eprintln!("Client connection error {}", e);
+                        output.info("Make sure to use https and `--insecure` with curl");
+                        None
+                    }
+                }
+            })
+            .boxed();
+
+            let server = Server::builder(tls::HyperAcceptor {
+                acceptor: incoming_tls_stream,
+            })
+            .serve(make_service_fn(move |_| make_service()));
+            output.message(&format!(
+                "{} Listening on https://{}",
+                emoji::EAR,
+                listening_address
+            ));
+            output.info("Generated certificate is not verified, browsers will give a warning and curl will require `--insecure`");
+            server.await?;
+        }
+    }
+    Ok(())
+}
+
+async fn proxy(
+    req: Request<Body>,
+    client: HyperClient<HttpConnector>,
+    miniflare_address: SocketAddr,
+    recorder: Option<Recorder>,
+) -> Result<Response<Body>> {
+    let path = upstream_path(req.uri());
+    if is_websocket(&req) {
+        let url = Url::parse(&format!("ws://{}{}", miniflare_address, path))?;
+        return proxy_websocket(req, url);
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let body = match &recorder {
+        Some(recorder) => recorder.record(&parts, body).await?,
+        None => body,
+    };
+    parts.uri = format!("http://{}{}", miniflare_address, path).parse()?;
+    Ok(client.request(Request::from_parts(parts, body)).await?)
+}
+
+fn upstream_path(uri: &Uri) -> String {
+    let path = get_path_as_str(uri);
+    match path.strip_prefix(SCHEDULED_PATH) {
+        Some(query) if query.is_empty() || query.starts_with('?') => {
+            format!("{}{}", MINIFLARE_SCHEDULED_PATH, query)
+        }
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_routes_scheduled_events_to_miniflare() {
+        let path = |uri: &str| upstream_path(&uri.parse().unwrap());
+        assert_eq!(
+            path("/__scheduled?cron=*+*+*+*+*"),
+            "/cdn-cgi/mf/scheduled?cron=*+*+*+*+*"
+        );
+        assert_eq!(path("/__scheduled"), "/cdn-cgi/mf/scheduled");
+        assert_eq!(path("/__scheduled-reports"), "/__scheduled-reports");
+        assert_eq!(path("/?q=1"), "/?q=1");
+    }
+}
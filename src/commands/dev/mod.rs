@@ -1,24 +1,41 @@
+pub mod dev_vars;
 mod edge;
 mod gcs;
+pub mod harness;
+pub mod hotkeys;
+mod inspector;
+mod live_reload;
+pub mod local;
+pub mod record;
+mod repl;
 mod server_config;
 mod socket;
+mod source_map;
+mod subrequests;
 mod tls;
 mod utils;
 
+pub use live_reload::LiveReload;
+pub use record::Recorder;
+pub use repl::Repl;
 pub use server_config::Protocol;
 pub use server_config::ServerConfig;
+pub(crate) use utils::script_path;
 
-use crate::build::build_target;
+use crate::build::build_dev_target;
 use crate::deploy::{DeployTarget, DeploymentSet};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
-use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
 
 use anyhow::Result;
+use hotkeys::Action;
+use tokio::sync::mpsc::UnboundedReceiver;
 
 /// `wrangler dev` starts a server on a dev machine that routes incoming HTTP requests
-/// to a Cloudflare Workers runtime and returns HTTP responses
+/// to a Cloudflare Workers runtime and returns HTTP responses, until an action
+/// stops it
+#[allow(clippy::too_many_arguments)]
 pub fn dev(
     target: Target,
     deployments: DeploymentSet,
@@ -26,10 +43,11 @@ pub fn dev(
     server_config: ServerConfig,
     local_protocol: Protocol,
     upstream_protocol: Protocol,
+    actions: UnboundedReceiver<Action>,
     verbose: bool,
-) -> Result<()> {
+) -> Result<Option<Action>> {
     // before serving requests we must first build the Worker
-    build_target(&target)?;
+    build_dev_target(&target)?;
 
     let deploy_target = {
         let valid_targets = deployments
@@ -67,6 +85,10 @@ pub fn dev(
         anyhow::bail!("{} cannot be https if {} is http", local_str, upstream_str)
     }
 
+    if server_config.cf.is_some() {
+        server_config.output.warn("request.cf can only be simulated with `wrangler dev --local`, the properties in [dev.cf] will be ignored.");
+    }
+
     if let Some(user) = user {
         if server_config.host.is_default() {
             // Authenticated and no host provided, run on edge with user's zone
@@ -77,12 +99,13 @@ pub fn dev(
                 deploy_target,
                 local_protocol,
                 upstream_protocol,
+                actions,
                 verbose,
             );
         }
 
         // If user is authenticated but host is provided, use gcs with given host
-        StdOut::warn(
+        server_config.output.warn(
             format!(
                 "{} provided, will run unauthenticated and upstream to provided host",
                 host_str
@@ -95,5 +118,5 @@ pub fn dev(
         anyhow::bail!("wrangler dev does not yet support unauthenticated sessions when using Durable Objects. Please run wrangler login or wrangler config first.")
     }
 
-    gcs::dev(target, server_config, local_protocol, verbose)
+    gcs::dev(target, server_config, local_protocol, actions, verbose)
 }
@@ -0,0 +1,158 @@
+use crate::commands::dev::utils::wait_for_server;
+use crate::commands::dev::{Protocol, ServerConfig};
+use crate::terminal::message::{Message, Output, StdErr};
+use crate::terminal::styles;
+
+use anyhow::Result;
+use hyper::body;
+use hyper::header::{CONTENT_LENGTH, HOST};
+use hyper::http::request::Parts;
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A request to the dev server, as it is written to a recording.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    /// the path and query of the request
+    url: String,
+    headers: Vec<(String, String)>,
+    /// the body of the request, encoded as base64 as it may not be text
+    body: String,
+}
+
+/// Records the requests to the dev server to a file, one JSON object per line,
+/// so they can be replayed with `wrangler dev --replay`.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path, output: Output) -> Result<Self> {
+        let file = File::create(path)?;
+        output.info(&format!(
+            "Recording requests to {}",
+            styles::highlight(path.display())
+        ));
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Writes a request to the recording, returning its body so it can still be sent on.
+    pub async fn record(&self, parts: &Parts, body: Body) -> Result<Body> {
+        let bytes = body::to_bytes(body).await?;
+        let request = RecordedRequest {
+            method: parts.method.to_string(),
+            url: parts
+                .uri
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/")
+                .to_owned(),
+            headers: parts
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_owned()))
+                })
+                .collect(),
+            body: base64::encode(&bytes),
+        };
+
+        let line = serde_json::to_string(&request)?;
+        writeln!(self.file.lock().unwrap(), "{}", line)?;
+        Ok(Body::from(bytes))
+    }
+}
+
+/// Re-sends the requests of a recording to the dev server, in order, once it is listening.
+pub fn replay(path: PathBuf, server_config: &ServerConfig, local_protocol: Protocol) -> Result<()> {
+    let requests = parse(&fs::read_to_string(&path)?)?;
+
+    let listening_address = server_config.listening_address;
+    let upstream_address = server_config.upstream_address;
+    let output = server_config.output;
+    thread::spawn(move || {
+        if let Err(e) = send(
+            requests,
+            listening_address,
+            upstream_address,
+            local_protocol,
+            output,
+        ) {
+            StdErr::user_error(&format!("Failed to replay {}: {}", path.display(), e));
+        }
+    });
+    Ok(())
+}
+
+fn send(
+    requests: Vec<RecordedRequest>,
+    listening_address: SocketAddr,
+    upstream_address: Option<SocketAddr>,
+    local_protocol: Protocol,
+    output: Output,
+) -> Result<()> {
+    let (client, base_url) = wait_for_server(listening_address, upstream_address, local_protocol)?;
+    let count = requests.len();
+    for request in requests {
+        let mut builder = client.request(
+            request.method.parse()?,
+            &format!("{}{}", base_url, request.url),
+        );
+        for (name, value) in request.headers.iter() {
+            // these are set for the dev server and the body it is sent
+            if name != HOST.as_str() && name != CONTENT_LENGTH.as_str() {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+        }
+        let response = builder.body(base64::decode(&request.body)?).send()?;
+        output.info(&format!(
+            "Replayed {} {} {}",
+            request.method,
+            request.url,
+            response.status()
+        ));
+    }
+    output.success(&format!("Replayed {} requests", count));
+    Ok(())
+}
+
+fn parse(recording: &str) -> Result<Vec<RecordedRequest>> {
+    recording
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_recordings() {
+        let requests = parse(
+            "{\"method\":\"POST\",\"url\":\"/api?q=1\",\"headers\":[[\"content-type\",\"text/plain\"]],\"body\":\"aGVsbG8=\"}\n\n",
+        )
+        .unwrap();
+        assert_eq!(
+            requests,
+            vec![RecordedRequest {
+                method: "POST".to_owned(),
+                url: "/api?q=1".to_owned(),
+                headers: vec![("content-type".to_owned(), "text/plain".to_owned())],
+                body: base64::encode("hello"),
+            }]
+        );
+        assert!(parse("not json").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::terminal::message::Output;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::Mutex;
+
+/// The ids of the messages the REPL sends start here, well above the ones of
+/// the other messages on the devtools socket, so the responses can be told apart.
+const FIRST_ID: u64 = 1 << 32;
+
+/// Evaluates the JavaScript typed at a prompt in the global scope of the Worker,
+/// with `Runtime.evaluate` over the devtools socket.
+#[derive(Clone, Debug)]
+pub struct Repl {
+    expressions: Arc<Mutex<UnboundedReceiver<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Repl {
+    /// Starts reading expressions from stdin, one per line.
+    pub fn start(output: Output) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        thread::spawn(move || {
+            prompt(output);
+            for line in io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if line.trim().is_empty() {
+                    prompt(output);
+                } else if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            expressions: Arc::new(Mutex::new(receiver)),
+            next_id: Arc::new(AtomicU64::new(FIRST_ID)),
+        }
+    }
+
+    /// Waits for the next expression, and returns the message that evaluates it.
+    pub async fn next_message(&self) -> Option<String> {
+        let expression = self.expressions.lock().await.recv().await?;
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        Some(evaluate_message(id, &expression))
+    }
+}
+
+fn evaluate_message(id: u64, expression: &str) -> String {
+    json!({
+        "id": id,
+        "method": "Runtime.evaluate",
+        "params": {
+            "expression": expression,
+            // like the console of the devtools, so `let` and `const` can be redeclared
+            "replMode": true,
+            "awaitPromise": true,
+        }
+    })
+    .to_string()
+}
+
+/// Prints the result of an expression, if the message is a response to the REPL.
+/// Returns whether it was.
+pub fn print_result(message_text: &str, output: Output) -> bool {
+    let message: Value = match serde_json::from_str(message_text) {
+        Ok(message) => message,
+        Err(_) => return false,
+    };
+    match message["id"].as_u64() {
+        Some(id) if id >= FIRST_ID => {}
+        _ => return false,
+    }
+
+    match describe_result(&message) {
+        Ok(result) => output.message(&result),
+        Err(e) => eprintln!("{}", e),
+    }
+    prompt(output);
+    true
+}
+
+fn describe_result(message: &Value) -> Result<String> {
+    if let Some(error) = message["error"]["message"].as_str() {
+        anyhow::bail!("{}", error)
+    }
+    let result = &message["result"];
+    if let Some(exception) = result["exceptionDetails"]["exception"]["description"].as_str() {
+        anyhow::bail!("Uncaught {}", exception)
+    }
+
+    let value = &result["result"];
+    Ok(match (value["description"].as_str(), value.get("value")) {
+        (Some(description), _) => description.to_owned(),
+        (None, Some(Value::String(value))) => format!("{:?}", value),
+        (None, Some(value)) => value.to_string(),
+        (None, None) => value["type"].as_str().unwrap_or("undefined").to_owned(),
+    })
+}
+
+fn prompt(output: Output) {
+    match output {
+        Output::Json => eprint!("> "),
+        Output::PlainText => {
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_describes_results() {
+        let describe = |message: Value| describe_result(&message).map_err(|e| e.to_string());
+
+        assert_eq!(
+            describe(
+                json!({"id": FIRST_ID, "result": {"result": {"type": "number", "value": 2, "description": "2"}}})
+            ),
+            Ok("2".to_owned())
+        );
+        assert_eq!(
+            describe(
+                json!({"id": FIRST_ID, "result": {"result": {"type": "string", "value": "hi"}}})
+            ),
+            Ok("\"hi\"".to_owned())
+        );
+        assert_eq!(
+            describe(json!({"id": FIRST_ID, "result": {"result": {"type": "undefined"}}})),
+            Ok("undefined".to_owned())
+        );
+        assert_eq!(
+            describe(json!({"id": FIRST_ID, "result": {
+                "result": {"type": "object"},
+                "exceptionDetails": {"exception": {"description": "ReferenceError: KV is not defined"}}
+            }})),
+            Err("Uncaught ReferenceError: KV is not defined".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_ignores_other_responses() {
+        assert!(!print_result(r#"{"id":2,"result":{}}"#, Output::PlainText));
+        assert!(!print_result(
+            r#"{"method":"Runtime.consoleAPICalled"}"#,
+            Output::PlainText
+        ));
+    }
+}
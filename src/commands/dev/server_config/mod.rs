@@ -5,13 +5,38 @@ pub use protocol::Protocol;
 
 use host::Host;
 
+use crate::commands::dev::{LiveReload, Recorder, Repl};
+use crate::terminal::message::Output;
+
 use anyhow::Result;
+use serde_json::{Map, Value};
 use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+/// How many of the ports after the given one are tried, when it is taken.
+const PORT_ATTEMPTS: u16 = 10;
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub host: Host,
     pub listening_address: SocketAddr,
+    pub live_reload: Option<LiveReload>,
+    pub inspector_port: Option<u16>,
+    /// The properties of `request.cf` to simulate, in place of the defaults
+    pub cf: Option<Map<String, Value>>,
+    pub recorder: Option<Recorder>,
+    pub repl: Option<Repl>,
+    pub log_subrequests: bool,
+    /// Where the state of emulated bindings is kept with `--local`
+    pub persist_to: Option<PathBuf>,
+    /// The cron triggers to fire scheduled events on with `--local`
+    pub crons: Vec<String>,
+    /// Where Miniflare listens behind the dev server with `--local`, which can
+    /// still be starting once the dev server is listening
+    pub upstream_address: Option<SocketAddr>,
+    /// With `Output::Json`, stdout is kept for the URL of the dev server, so everything
+    /// else is printed to stderr
+    pub output: Output,
 }
 
 impl ServerConfig {
@@ -20,12 +45,23 @@ impl ServerConfig {
         ip: IpAddr,
         port: u16,
         upstream_protocol: Protocol,
+        output: Output,
     ) -> Result<Self> {
         let addr = SocketAddr::new(ip, port);
-        let listening_address = match TcpListener::bind(&addr) {
-            Ok(socket) => socket.local_addr(),
-            Err(_) => anyhow::bail!("{} is unavailable, try binding to another address with the --port and --ip flags, or stop other `wrangler dev` processes.", &addr)
-        }?;
+        let listening_address = match available_address(addr) {
+            Some(listening_address) => {
+                // the OS picks a port when asked for port 0, which is not a fallback
+                if port != 0 && listening_address.port() != port {
+                    output.warn(&format!(
+                        "{} is unavailable, listening on port {} instead",
+                        &addr,
+                        listening_address.port()
+                    ));
+                }
+                listening_address
+            }
+            None => anyhow::bail!("{} is unavailable, try binding to another address with the --port and --ip flags, or stop other `wrangler dev` processes.", &addr)
+        };
 
         let host = if let Some(host) = host {
             Host::new(&host, false)?
@@ -42,6 +78,45 @@ impl ServerConfig {
         Ok(ServerConfig {
             host,
             listening_address,
+            live_reload: None,
+            inspector_port: None,
+            cf: None,
+            recorder: None,
+            repl: None,
+            log_subrequests: false,
+            persist_to: None,
+            crons: vec![],
+            upstream_address: None,
+            output,
         })
     }
 }
+
+/// Finds the first port from the given address that can be listened on.
+fn available_address(addr: SocketAddr) -> Option<SocketAddr> {
+    // a port of 0 is chosen by the OS, so it is always available
+    let attempts = if addr.port() == 0 { 1 } else { PORT_ATTEMPTS };
+    (0..attempts)
+        .filter_map(|offset| addr.port().checked_add(offset))
+        .find_map(|port| {
+            let socket = TcpListener::bind(SocketAddr::new(addr.ip(), port)).ok()?;
+            socket.local_addr().ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn it_falls_back_to_the_next_free_port() {
+        let taken = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = taken.local_addr().unwrap();
+
+        let available = available_address(addr).unwrap();
+        assert_eq!(available.ip(), addr.ip());
+        assert!(available.port() > addr.port());
+        assert!(available.port() < addr.port() + PORT_ATTEMPTS);
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 use std::time::Duration;
 
@@ -8,8 +9,11 @@ use futures_util::sink::SinkExt;
 use futures_util::stream::{SplitStream, StreamExt};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::commands::dev::repl::{self, Repl};
+use crate::commands::dev::source_map::SourceMap;
+use crate::commands::dev::subrequests::{Subrequests, ENABLE_NETWORK_ID};
 use crate::terminal::colored_json_string;
-use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::message::{Message, Output, StdErr};
 use protocol::domain::runtime::event::Event::ExceptionThrown;
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
@@ -23,10 +27,16 @@ use url::Url;
 const KEEP_ALIVE_INTERVAL: u64 = 10;
 
 /// connect to a Workers runtime WebSocket emitting the Chrome Devtools Protocol
-/// parse all console messages, and print them to stdout
+/// parse all console messages, and print them to stdout, or to stderr with `Output::Json`
+///
+/// the stack traces of exceptions are resolved with the source map of the script, if it has one
 pub async fn listen(
     socket_url: Url,
     refresh_session_sender: Option<Sender<Option<()>>>,
+    script_path: Option<PathBuf>,
+    repl: Option<Repl>,
+    log_subrequests: bool,
+    output: Output,
 ) -> Result<()> {
     // we loop here so we can issue a reconnect when something
     // goes wrong with the websocket connection
@@ -44,11 +54,21 @@ pub async fn listen(
         let enable_runtime = tungstenite::protocol::Message::Text(enable_runtime);
         write.send(enable_runtime).await?;
 
+        // the subrequests of the Worker are in the Network domain, which is enabled the same way
+        if log_subrequests {
+            let enable_network = Subrequests::enable_message();
+            let enable_network = tungstenite::protocol::Message::Text(enable_network);
+            write.send(enable_network).await?;
+        }
+
         // if left unattended, the preview service will kill the socket
         // that emits console messages
         // send a keep alive message every so often in the background
         let (keep_alive_tx, keep_alive_rx) = mpsc::unbounded_channel();
 
+        // expressions typed into the REPL are written to the websocket the same way
+        let repl_to_channel = forward_repl(repl.clone(), keep_alive_tx.clone());
+
         // every 10 seconds, send a keep alive message on the channel
         let heartbeat = keep_alive(keep_alive_tx);
 
@@ -60,10 +80,10 @@ pub async fn listen(
             .map_err(Into::into);
 
         // parse all incoming messages and print them to stdout
-        let printer = print_ws_messages(read);
+        let printer = print_ws_messages(read, script_path.as_deref(), log_subrequests, output);
 
         // run the heartbeat and message printer in parallel
-        if tokio::try_join!(heartbeat, keep_alive_to_ws, printer).is_ok() {
+        if tokio::try_join!(heartbeat, repl_to_channel, keep_alive_to_ws, printer).is_ok() {
             break Ok(());
         } else {
         }
@@ -112,26 +132,45 @@ async fn connect_retry(
     }
 }
 
-fn print_json(value: Result<serde_json::Value, serde_json::Error>, fallback: String) {
+fn print_json(
+    value: Result<serde_json::Value, serde_json::Error>,
+    fallback: String,
+    output: Output,
+) {
     if let Ok(json) = value {
         if let Ok(json_str) = colored_json_string(&json) {
-            println!("{}", json_str);
+            output.message(&json_str);
         } else {
-            StdOut::message(fallback.as_str());
+            output.message(fallback.as_str());
         }
     } else {
-        println!("{}", fallback);
+        output.message(&fallback);
     }
 }
 
 async fn print_ws_messages(
     mut read: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    script_path: Option<&Path>,
+    log_subrequests: bool,
+    output: Output,
 ) -> Result<()> {
+    let mut subrequests = Subrequests::new(output);
     while let Some(message) = read.next().await {
         let message = message?;
         let message_text = message.into_text().unwrap();
         log::info!("{}", &message_text);
 
+        if repl::print_result(&message_text, output) {
+            continue;
+        }
+        if log_subrequests {
+            if let Ok(message) = serde_json::from_str(&message_text) {
+                if subrequests.handle(&message) {
+                    continue;
+                }
+            }
+        }
+
         let parsed_message: Result<protocol::Runtime> = serde_json::from_str(&message_text)
             .map_err(|e| anyhow!("Failed to parse event:\n{}", e));
 
@@ -145,21 +184,24 @@ async fn print_ws_messages(
                     .as_ref()
                     .unwrap_or(&default_description);
 
-                StdOut::message(&format!(
+                output.message(&format!(
                     "{} at line {:?}, col {:?}",
                     description,
                     params.exception_details.line_number,
                     params.exception_details.column_number,
                 ));
+                if let Some(script_path) = script_path {
+                    print_original_stack(&message_text, script_path, output);
+                }
 
                 let json_parse = serde_json::to_value(params.clone());
-                print_json(json_parse, format!("{:?}", params));
+                print_json(json_parse, format!("{:?}", params), output);
             }
             Ok(protocol::Runtime::Event(event)) => {
                 // Try to parse json to pretty print, otherwise just print string
                 let json_parse: Result<serde_json::Value, serde_json::Error> =
                     serde_json::from_str(&*event.to_string());
-                print_json(json_parse, event.to_string());
+                print_json(json_parse, event.to_string(), output);
             }
             Ok(protocol::Runtime::Method(_)) => {}
             Err(err) => log::debug!("{}", err),
@@ -168,13 +210,66 @@ async fn print_ws_messages(
     Ok(())
 }
 
+/// Prints the stack frames of an exception at their locations in the original source.
+fn print_original_stack(message_text: &str, script_path: &Path, output: Output) {
+    // the source map is loaded each time, since the script is rebuilt on changes
+    let source_map = match SourceMap::for_script(script_path) {
+        Some(source_map) => source_map,
+        None => return,
+    };
+    let message: serde_json::Value = match serde_json::from_str(message_text) {
+        Ok(message) => message,
+        Err(_) => return,
+    };
+    let frames = match message["params"]["exceptionDetails"]["stackTrace"]["callFrames"].as_array()
+    {
+        Some(frames) => frames,
+        None => return,
+    };
+
+    for frame in frames {
+        let location = match (frame["lineNumber"].as_u64(), frame["columnNumber"].as_u64()) {
+            (Some(line), Some(column)) => source_map.lookup(line, column),
+            _ => None,
+        };
+        if let Some(location) = location {
+            let function = frame["functionName"]
+                .as_str()
+                .filter(|name| !name.is_empty())
+                .unwrap_or("<anonymous>");
+            output.message(&format!(
+                "    at {} ({}:{}:{})",
+                function,
+                location.source,
+                location.line + 1,
+                location.column + 1
+            ));
+        }
+    }
+}
+
+/// Sends the expressions typed into the REPL on the channel to the websocket, as they come.
+async fn forward_repl(
+    repl: Option<Repl>,
+    tx: mpsc::UnboundedSender<tungstenite::protocol::Message>,
+) -> Result<()> {
+    if let Some(repl) = repl {
+        while let Some(message) = repl.next_message().await {
+            tx.send(tungstenite::protocol::Message::Text(message))?;
+        }
+    }
+    // without a REPL, or once stdin is closed, there is nothing left to send
+    futures_util::future::pending().await
+}
+
 async fn keep_alive(tx: mpsc::UnboundedSender<tungstenite::protocol::Message>) -> Result<()> {
     let duration = Duration::from_millis(1000 * KEEP_ALIVE_INTERVAL);
     let mut delay = sleep(duration);
 
-    // this is set to 2 because we have already sent an id of 1 to enable the runtime
+    // this is set to 3 because we have already sent an id of 1 to enable the runtime,
+    // and an id of 2 to enable the network
     // eventually this logic should be moved to the chrome-devtools-rs library
-    let mut id = 2;
+    let mut id = ENABLE_NETWORK_ID + 1;
 
     loop {
         delay.await;
@@ -0,0 +1,184 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const BASE64_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const SOURCE_MAPPING_URL: &str = "//# sourceMappingURL=";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    sources: Vec<String>,
+    source_root: Option<String>,
+    mappings: String,
+}
+
+/// Where a position in the built script came from.
+#[derive(Debug, PartialEq)]
+pub struct Location {
+    pub source: String,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// A version 3 source map of the built script, to find the original
+/// locations of the stack frames in exceptions.
+#[derive(Debug)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    // for each line of the built script, the segments of
+    // (column, source, original line, original column)
+    lines: Vec<Vec<(u64, usize, u64, u64)>>,
+}
+
+impl SourceMap {
+    /// Loads the source map of a script, from its `sourceMappingURL` comment,
+    /// or from a `.map` file next to it.
+    pub fn for_script(script_path: &Path) -> Option<SourceMap> {
+        let script = fs::read_to_string(script_path).ok()?;
+        let url = script
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix(SOURCE_MAPPING_URL));
+
+        let json = match url {
+            Some(url) if url.starts_with("data:") => {
+                let (_, data) = url.split_once(";base64,")?;
+                String::from_utf8(base64::decode(data).ok()?).ok()?
+            }
+            Some(url) => fs::read_to_string(script_path.parent()?.join(url)).ok()?,
+            None => fs::read_to_string(format!("{}.map", script_path.display())).ok()?,
+        };
+
+        match SourceMap::parse(&json) {
+            Ok(source_map) => Some(source_map),
+            Err(e) => {
+                log::debug!("Failed to parse source map: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn parse(json: &str) -> Result<SourceMap> {
+        let raw: RawSourceMap = serde_json::from_str(json)?;
+        let source_root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .iter()
+            .map(|source| {
+                format!("{}{}", source_root, source)
+                    .trim_start_matches("webpack:///")
+                    .to_owned()
+            })
+            .collect();
+
+        // every field but the column is relative to the previous segment, even across lines
+        let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+        let mut lines = vec![];
+        for mappings in raw.mappings.split(';') {
+            let mut segments = vec![];
+            let mut generated_column = 0i64;
+            for segment in mappings.split(',').filter(|segment| !segment.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                generated_column += fields[0];
+                if fields.len() < 4 {
+                    continue;
+                }
+                source += fields[1];
+                line += fields[2];
+                column += fields[3];
+                if source < 0 || line < 0 || column < 0 || generated_column < 0 {
+                    anyhow::bail!("invalid mapping: {}", segment);
+                }
+                segments.push((
+                    generated_column as u64,
+                    source as usize,
+                    line as u64,
+                    column as u64,
+                ));
+            }
+            lines.push(segments);
+        }
+
+        Ok(SourceMap { sources, lines })
+    }
+
+    /// Finds the original location of a zero-based line and column of the built script.
+    pub fn lookup(&self, line: u64, column: u64) -> Option<Location> {
+        let segments = self.lines.get(line as usize)?;
+        let (_, source, line, column) = segments
+            .iter()
+            .rev()
+            .find(|(generated_column, ..)| *generated_column <= column)?;
+        Some(Location {
+            source: self.sources.get(*source)?.to_owned(),
+            line: *line,
+            column: *column,
+        })
+    }
+}
+
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut fields = vec![];
+    let (mut value, mut shift) = (0i64, 0);
+    for c in segment.bytes() {
+        let digit = BASE64_DIGITS
+            .iter()
+            .position(|&digit| digit == c)
+            .ok_or_else(|| anyhow!("invalid mapping: {}", segment))? as i64;
+        value += (digit & 31) << shift;
+        if digit & 32 == 0 {
+            fields.push(if value & 1 == 1 {
+                -(value >> 1)
+            } else {
+                value >> 1
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_vlq() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("IACD").unwrap(), vec![4, 0, 1, -1]);
+        assert_eq!(decode_vlq("gB").unwrap(), vec![16]);
+        assert!(decode_vlq("A!").is_err());
+    }
+
+    #[test]
+    fn it_looks_up_original_locations() {
+        let source_map = SourceMap::parse(
+            r#"{"version":3,"sources":["webpack:///./src/index.ts"],"mappings":"AAAA,IAAI;AACA"}"#,
+        )
+        .unwrap();
+
+        let location = |source: &str, line, column| Location {
+            source: source.to_owned(),
+            line,
+            column,
+        };
+        assert_eq!(
+            source_map.lookup(0, 2),
+            Some(location("./src/index.ts", 0, 0))
+        );
+        assert_eq!(
+            source_map.lookup(0, 6),
+            Some(location("./src/index.ts", 0, 4))
+        );
+        assert_eq!(
+            source_map.lookup(1, 0),
+            Some(location("./src/index.ts", 1, 4))
+        );
+        assert_eq!(source_map.lookup(2, 0), None);
+    }
+}
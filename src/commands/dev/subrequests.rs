@@ -0,0 +1,125 @@
+use crate::terminal::message::Output;
+use crate::terminal::styles;
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// The id of the message that enables the Network domain, see `socket::keep_alive`.
+pub const ENABLE_NETWORK_ID: u64 = 2;
+
+/// Logs the subrequests the Worker makes with `fetch()`, from the events of the
+/// Network domain of the devtools protocol.
+#[derive(Debug)]
+pub struct Subrequests {
+    // the method, URL and start time of the subrequests that are waiting for a response
+    pending: HashMap<String, (String, String, f64)>,
+    output: Output,
+}
+
+impl Subrequests {
+    pub fn new(output: Output) -> Self {
+        Subrequests {
+            pending: HashMap::new(),
+            output,
+        }
+    }
+
+    /// The message that asks for the events of the Network domain.
+    pub fn enable_message() -> String {
+        json!({ "id": ENABLE_NETWORK_ID, "method": "Network.enable" }).to_string()
+    }
+
+    /// Logs a subrequest once its response arrives. Returns whether the message
+    /// was an event of the Network domain.
+    pub fn handle(&mut self, message: &Value) -> bool {
+        let params = &message["params"];
+        match message["method"].as_str() {
+            Some("Network.requestWillBeSent") => {
+                if let Some(id) = params["requestId"].as_str() {
+                    self.pending.insert(
+                        id.to_owned(),
+                        (
+                            params["request"]["method"]
+                                .as_str()
+                                .unwrap_or("GET")
+                                .to_owned(),
+                            params["request"]["url"].as_str().unwrap_or("").to_owned(),
+                            params["timestamp"].as_f64().unwrap_or(0.0),
+                        ),
+                    );
+                }
+                true
+            }
+            Some("Network.responseReceived") => {
+                let id = params["requestId"].as_str().unwrap_or("");
+                if let Some((method, url, started)) = self.pending.remove(id) {
+                    let status = params["response"]["status"].as_u64().unwrap_or(0);
+                    let elapsed = params["timestamp"].as_f64().unwrap_or(started) - started;
+                    self.output
+                        .message(&describe(&method, &url, status, elapsed));
+                }
+                true
+            }
+            Some("Network.loadingFailed") => {
+                let id = params["requestId"].as_str().unwrap_or("");
+                if let Some((method, url, _)) = self.pending.remove(id) {
+                    let error = params["errorText"].as_str().unwrap_or("failed");
+                    self.output.message(&format!(
+                        "{} {} {} {}",
+                        styles::cyan("[subrequest]"),
+                        method,
+                        url,
+                        error
+                    ));
+                }
+                true
+            }
+            Some(method) => method.starts_with("Network."),
+            None => false,
+        }
+    }
+}
+
+fn describe(method: &str, url: &str, status: u64, elapsed_seconds: f64) -> String {
+    format!(
+        "{} {} {} {} ({}ms)",
+        styles::cyan("[subrequest]"),
+        method,
+        url,
+        status,
+        (elapsed_seconds * 1000.0).round() as u64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_pairs_requests_with_their_responses() {
+        let mut subrequests = Subrequests::new(Output::PlainText);
+        assert!(subrequests.handle(&json!({
+            "method": "Network.requestWillBeSent",
+            "params": {
+                "requestId": "1",
+                "timestamp": 10.5,
+                "request": { "method": "POST", "url": "https://api.example.com/users" }
+            }
+        })));
+        assert_eq!(subrequests.pending.len(), 1);
+
+        assert!(subrequests.handle(&json!({
+            "method": "Network.responseReceived",
+            "params": { "requestId": "1", "timestamp": 10.625, "response": { "status": 201 } }
+        })));
+        assert!(subrequests.pending.is_empty());
+
+        assert!(!subrequests.handle(&json!({ "method": "Runtime.consoleAPICalled" })));
+    }
+
+    #[test]
+    fn it_describes_subrequests() {
+        let description = describe("GET", "https://example.com/", 200, 0.1234);
+        assert!(description.ends_with("GET https://example.com/ 200 (123ms)"));
+    }
+}
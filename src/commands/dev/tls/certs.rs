@@ -10,27 +10,42 @@ use openssl::x509::extension::{
 };
 use openssl::x509::{X509NameBuilder, X509Req, X509ReqBuilder, X509};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::settings::get_wrangler_home_dir;
-use crate::terminal::message::{Message, StdOut};
+use super::cert_paths;
+use crate::terminal::message::Output;
 /// Create files for cert and private key
-fn create_output_files() -> Result<Option<(PathBuf, PathBuf)>> {
-    let home = get_wrangler_home_dir().join("config");
-    let cert = home.join("dev-cert.pem");
-    let privkey = home.join("dev-privkey.rsa");
+fn create_output_files(output: Output) -> Result<Option<(PathBuf, PathBuf)>> {
+    let (cert, privkey) = cert_paths();
+    let home = cert.parent().expect("Could not find certificate directory");
 
-    if cert.exists() && privkey.exists() {
+    if cert.exists() && privkey.exists() && !is_expired(&cert, output)? {
         Ok(None)
     } else {
-        fs::create_dir_all(&home)?;
+        fs::create_dir_all(home)?;
 
-        StdOut::info(format!("Generating certificate and private key for https server, if you would like to use your own you can replace `dev-cert.pem` and `dev-privkey.rsa` at {}", home.to_str().unwrap()).as_str());
+        output.info(format!("Generating certificate and private key for https server, if you would like to use your own you can replace `dev-cert.pem` and `dev-privkey.rsa` at {}", home.to_str().unwrap()).as_str());
 
         Ok(Some((cert, privkey)))
     }
 }
 
+/// Check if a previously generated cert is no longer valid. Certs that can't be
+/// read are left alone, since they may have been replaced by the user.
+fn is_expired(cert: &Path, output: Output) -> Result<bool> {
+    let cert = match X509::from_pem(&fs::read(cert)?) {
+        Ok(cert) => cert,
+        Err(_) => return Ok(false),
+    };
+
+    if cert.not_after() < Asn1Time::days_from_now(0)? {
+        output.info("The certificate for the https server has expired");
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
 /// Generate certificate authority to sign cert
 fn create_ca() -> Result<(X509, PKey<Private>)> {
     let rsa = Rsa::generate(2048)?;
@@ -85,8 +100,8 @@ fn create_req(privkey: &PKey<Private>) -> Result<X509Req> {
     let mut x509_name = X509NameBuilder::new()?;
     x509_name.append_entry_by_text("C", "US")?;
     x509_name.append_entry_by_text("ST", "TX")?;
-    x509_name.append_entry_by_text("O", "Some organization")?;
-    x509_name.append_entry_by_text("CN", "www.example.com")?;
+    x509_name.append_entry_by_text("O", "Wrangler")?;
+    x509_name.append_entry_by_text("CN", "localhost")?;
     let x509_name = x509_name.build();
     req_builder.set_subject_name(&x509_name)?;
 
@@ -96,8 +111,8 @@ fn create_req(privkey: &PKey<Private>) -> Result<X509Req> {
 }
 
 /// Generate cert and private key
-pub fn generate_cert() -> Result<()> {
-    let files = create_output_files()?;
+pub fn generate_cert(output: Output) -> Result<()> {
+    let files = create_output_files(output)?;
     if files.is_none() {
         return Ok(());
     }
@@ -153,8 +168,9 @@ pub fn generate_cert() -> Result<()> {
     cert_builder.append_extension(auth_key_identifier)?;
 
     let subject_alt_name = SubjectAlternativeName::new()
-        .dns("*.example.com")
-        .dns("hello.com")
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .ip("::1")
         .build(&cert_builder.x509v3_context(Some(&ca), None))?;
     cert_builder.append_extension(subject_alt_name)?;
 
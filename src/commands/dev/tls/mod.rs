@@ -17,11 +17,15 @@ use tokio_rustls::{server::TlsStream, TlsAcceptor};
 
 use crate::settings::get_wrangler_home_dir;
 
+/// The paths of the cert and private key that are generated for the https server.
+pub(super) fn cert_paths() -> (PathBuf, PathBuf) {
+    let home = get_wrangler_home_dir().join("config");
+    (home.join("dev-cert.pem"), home.join("dev-privkey.rsa"))
+}
+
 // Build TLS configuration
 pub(super) fn get_tls_acceptor() -> Result<TlsAcceptor> {
-    let home = get_wrangler_home_dir().join("config");
-    let cert = home.join("dev-cert.pem");
-    let privkey = home.join("dev-privkey.rsa");
+    let (cert, privkey) = cert_paths();
 
     // Load public certificate
     let certs = load_certs(cert)?;
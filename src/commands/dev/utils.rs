@@ -1,5 +1,20 @@
-use http::{HeaderValue, Response};
-use hyper::{Body, Uri};
+use crate::commands::dev::Protocol;
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::upload::Package;
+use crate::wranglerjs;
+
+use anyhow::Result;
+use futures_util::stream::StreamExt;
+use http::header::HOST;
+use http::{HeaderMap, HeaderValue, Response};
+use hyper::{Body, Request, Uri};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::create_response;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::{connect_async, WebSocketStream};
 use url::Url;
 
 pub(super) fn get_path_as_str(uri: &Uri) -> String {
@@ -9,6 +24,59 @@ pub(super) fn get_path_as_str(uri: &Uri) -> String {
         .to_string()
 }
 
+/// How long to wait for the dev server to start listening.
+const SERVER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Waits until the dev server is listening, returning a client for it and its URL.
+pub(super) fn wait_for_server(
+    listening_address: SocketAddr,
+    upstream_address: Option<SocketAddr>,
+    local_protocol: Protocol,
+) -> Result<(reqwest::blocking::Client, String)> {
+    // the server may be listening on all interfaces, but it can always be reached locally
+    let mut address = listening_address;
+    if address.ip().is_unspecified() {
+        address.set_ip(Ipv4Addr::LOCALHOST.into());
+    }
+
+    // requests are only answered once the server behind the dev server is listening too
+    let started = Instant::now();
+    for address in upstream_address.iter().chain(Some(&address)) {
+        while TcpStream::connect(address).is_err() {
+            if started.elapsed() > SERVER_TIMEOUT {
+                anyhow::bail!("the dev server did not start listening on {}", address)
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    // the certificate of the https server is generated locally, so it can't be verified
+    let client = reqwest::blocking::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let base_url = format!(
+        "{}://{}",
+        if local_protocol.is_https() {
+            "https"
+        } else {
+            "http"
+        },
+        address
+    );
+    Ok((client, base_url))
+}
+
+/// The host that a request was sent to, so redirects lead back to the same
+/// address, even when listening on all interfaces for other devices.
+pub(super) fn local_host(headers: &HeaderMap, listening_address: SocketAddr) -> String {
+    headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(|host| host.to_owned())
+        .unwrap_or_else(|| listening_address.to_string())
+}
+
 /// Rewrites redirects to host to be localhost
 pub(super) fn rewrite_redirect(
     resp: &mut Response<Body>,
@@ -45,3 +113,66 @@ pub(super) fn rewrite_redirect(
         }
     }
 }
+
+/// Finds the built script of the Worker, and whether it uses the modules format.
+///
+/// Rust Workers are built to Wasm, so there is no script to find.
+pub(crate) fn script_path(target: &Target) -> Result<Option<(PathBuf, bool)>> {
+    match &target.target_type {
+        TargetType::Rust => Ok(None),
+        TargetType::JavaScript => match target.build.as_ref().map(|build| &build.upload) {
+            Some(UploadFormat::Modules { main, dir, .. }) => Ok(Some((dir.join(main), true))),
+            _ => {
+                let package_dir = target.package_dir()?;
+                let package = Package::new(&package_dir)?;
+                Ok(Some((package_dir.join(package.main(&package_dir)?), false)))
+            }
+        },
+        TargetType::Webpack => {
+            let package_dir = target.package_dir()?;
+            let bundle = wranglerjs::Bundle::new(&package_dir);
+            Ok(Some((bundle.script_path(), false)))
+        }
+    }
+}
+
+/// Checks if a request is asking to upgrade to a WebSocket
+pub(super) fn is_websocket(req: &Request<Body>) -> bool {
+    req.headers()
+        .get("Upgrade")
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// Accepts a WebSocket, then proxies its messages to and from the upstream WebSocket
+pub(super) fn proxy_websocket(req: Request<Body>, upstream_url: Url) -> Result<Response<Body>> {
+    // only the head of the request is needed to check the handshake
+    let mut handshake = Request::new(());
+    *handshake.method_mut() = req.method().to_owned();
+    *handshake.uri_mut() = req.uri().to_owned();
+    *handshake.version_mut() = req.version();
+    *handshake.headers_mut() = req.headers().to_owned();
+    let (parts, _) = create_response(&handshake)?.into_parts();
+
+    tokio::spawn(async move {
+        if let Err(e) = pipe_websocket(req, upstream_url).await {
+            log::info!("WebSocket disconnected: {}", e);
+        }
+    });
+
+    Ok(Response::from_parts(parts, Body::empty()))
+}
+
+async fn pipe_websocket(req: Request<Body>, upstream_url: Url) -> Result<()> {
+    let upgraded = hyper::upgrade::on(req).await?;
+    let downstream = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+    let (upstream, _) = connect_async(&upstream_url).await?;
+
+    let (downstream_write, downstream_read) = downstream.split();
+    let (upstream_write, upstream_read) = upstream.split();
+    tokio::try_join!(
+        downstream_read.forward(upstream_write),
+        upstream_read.forward(downstream_write)
+    )?;
+    Ok(())
+}
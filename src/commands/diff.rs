@@ -0,0 +1,637 @@
+use std::fmt;
+use std::fs;
+
+use anyhow::Result;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::dev::script_path;
+use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Target, TargetType};
+use crate::terminal::message::{Message, Output, StdOut};
+use crate::terminal::styles;
+
+/// How a Worker would change if it were published, compared to the deployed Worker.
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    pub name: String,
+    /// whether the Worker has been published before
+    pub deployed: bool,
+    pub script: ScriptDiff,
+    pub bindings: Vec<Change<Binding>>,
+    pub routes: Vec<Change<String>>,
+    pub schedules: Vec<Change<String>>,
+    pub compatibility_date: ValueDiff,
+    pub compatibility_flags: Vec<Change<String>>,
+    pub workers_dev: bool,
+    /// the hostnames that would be attached as custom domains
+    pub custom_domains: Vec<String>,
+}
+
+/// The SHA-256 hashes of the local and deployed scripts, when they can be known.
+#[derive(Debug, Default, Serialize)]
+pub struct ScriptDiff {
+    pub local: Option<String>,
+    pub deployed: Option<String>,
+    pub changed: Option<bool>,
+}
+
+/// A setting of the local and deployed Workers, when they have one.
+#[derive(Debug, Default, Serialize)]
+pub struct ValueDiff {
+    pub local: Option<String>,
+    pub deployed: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Binding {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub binding_type: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "change", content = "value", rename_all = "lowercase")]
+pub enum Change<T> {
+    Added(T),
+    Removed(T),
+    Unchanged(T),
+    /// a route that is assigned to another script, and would not be claimed
+    Conflict(T, String),
+    /// a route to the script that isn't in wrangler.toml, such as one added in the
+    /// dashboard, which publishing leaves in place
+    Undeclared(T),
+}
+
+#[derive(Deserialize)]
+struct BindingsResponse {
+    result: Vec<Binding>,
+}
+
+#[derive(Deserialize)]
+struct SchedulesResponse {
+    result: Schedules,
+}
+
+#[derive(Deserialize)]
+struct Schedules {
+    #[serde(default)]
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Deserialize)]
+struct Schedule {
+    cron: String,
+}
+
+#[derive(Deserialize)]
+struct SettingsResponse {
+    result: Settings,
+}
+
+#[derive(Default, Deserialize)]
+struct Settings {
+    compatibility_date: Option<String>,
+    #[serde(default)]
+    compatibility_flags: Vec<String>,
+}
+
+impl Diff {
+    /// Compares a built Worker with the one deployed under its name.
+    pub fn new(user: &GlobalUser, target: &Target, deployments: &DeploymentSet) -> Result<Diff> {
+        let account_id = target.account_id.load()?;
+        let client = http::legacy_auth_client(user);
+        let script_url = format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+            account_id, target.name
+        );
+
+        let local_path = script_path(target)?.map(|(path, _)| path);
+        let local_script = match &local_path {
+            Some(path) => Some(fs::read(path)?),
+            None => None,
+        };
+        let main_module = local_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .and_then(|name| name.to_str());
+
+        let response = client.get(&script_url).send()?;
+        let deployed_script = match response.status() {
+            StatusCode::NOT_FOUND => None,
+            status if status.is_success() => {
+                let content_type = response
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned);
+                Some(script_content(
+                    content_type.as_deref(),
+                    &response.bytes()?,
+                    main_module,
+                ))
+            }
+            _ => anyhow::bail!(crate::format_api_errors(response.text()?)),
+        };
+
+        let script = ScriptDiff {
+            local: local_script.as_deref().map(hash),
+            deployed: deployed_script.as_deref().map(hash),
+            changed: match (&local_script, &deployed_script) {
+                (Some(local), Some(deployed)) => Some(local != deployed),
+                (Some(_), None) => Some(true),
+                (None, _) => None,
+            },
+        };
+
+        let deployed_bindings = if deployed_script.is_some() {
+            let response = client.get(&format!("{}/bindings", script_url)).send()?;
+            if !response.status().is_success() {
+                anyhow::bail!(crate::format_api_errors(response.text()?))
+            }
+            response.json::<BindingsResponse>()?.result
+        } else {
+            vec![]
+        };
+
+        let (deployed_crons, deployed_settings) = if deployed_script.is_some() {
+            let response = client.get(&format!("{}/schedules", script_url)).send()?;
+            if !response.status().is_success() {
+                anyhow::bail!(crate::format_api_errors(response.text()?))
+            }
+            let crons = response
+                .json::<SchedulesResponse>()?
+                .result
+                .schedules
+                .into_iter()
+                .map(|schedule| schedule.cron)
+                .collect();
+
+            let response = client.get(&format!("{}/settings", script_url)).send()?;
+            if !response.status().is_success() {
+                anyhow::bail!(crate::format_api_errors(response.text()?))
+            }
+            (crons, response.json::<SettingsResponse>()?.result)
+        } else {
+            (vec![], Settings::default())
+        };
+
+        let mut routes = vec![];
+        let mut workers_dev = false;
+        let mut custom_domains = vec![];
+        let mut local_crons = vec![];
+        for deployment in deployments {
+            match deployment {
+                DeployTarget::Zoned(zoned) => {
+                    let existing_routes = deploy::fetch_routes(user, &zoned.zone_id)?;
+                    for route in zoned.routes.iter() {
+                        let existing = existing_routes
+                            .iter()
+                            .find(|existing| existing.pattern == route.pattern);
+                        routes.push(match existing {
+                            Some(existing) if existing.script == route.script => {
+                                Change::Unchanged(route.pattern.clone())
+                            }
+                            Some(existing) => Change::Conflict(
+                                route.pattern.clone(),
+                                existing
+                                    .script
+                                    .clone()
+                                    .unwrap_or_else(|| "null worker".to_owned()),
+                            ),
+                            None => Change::Added(route.pattern.clone()),
+                        });
+                    }
+                    for existing in existing_routes.iter() {
+                        if existing.script.as_deref() == Some(target.name.as_str())
+                            && !zoned
+                                .routes
+                                .iter()
+                                .any(|route| route.pattern == existing.pattern)
+                        {
+                            routes.push(Change::Undeclared(existing.pattern.clone()));
+                        }
+                    }
+                }
+                DeployTarget::Zoneless(_) => workers_dev = true,
+                DeployTarget::CustomDomains(domains) => {
+                    custom_domains.extend(domains.hostnames.iter().cloned())
+                }
+                DeployTarget::Schedule(schedule) => local_crons = schedule.crons.clone(),
+                DeployTarget::QueueConsumers(_) => {}
+            }
+        }
+
+        Ok(Diff {
+            name: target.name.clone(),
+            deployed: deployed_script.is_some(),
+            script,
+            bindings: compare(local_bindings(target), deployed_bindings),
+            routes,
+            schedules: compare_values(local_crons, deployed_crons),
+            compatibility_date: ValueDiff {
+                local: target.compatibility_date.clone(),
+                deployed: deployed_settings.compatibility_date,
+            },
+            compatibility_flags: compare_values(
+                target.compatibility_flags.clone(),
+                deployed_settings.compatibility_flags,
+            ),
+            workers_dev,
+            custom_domains,
+        })
+    }
+
+    pub fn print(&self, out: Output) {
+        if out == Output::Json {
+            StdOut::as_json(self);
+            return;
+        }
+
+        if self.deployed {
+            StdOut::info(&format!(
+                "Changes to {} if it were published:",
+                styles::bold(&self.name)
+            ));
+        } else {
+            StdOut::info(&format!(
+                "{} has not been published yet, it would be created with:",
+                styles::bold(&self.name)
+            ));
+        }
+
+        println!("script");
+        match (&self.script.changed, &self.script.local) {
+            (Some(true), Some(local)) => println!(
+                "  {} {} (deployed {})",
+                styles::warning("~"),
+                short(local),
+                self.script.deployed.as_deref().map_or("none", short)
+            ),
+            (Some(false), Some(local)) => println!("    {} (unchanged)", short(local)),
+            _ => println!("    unknown until the script is uploaded"),
+        }
+
+        println!("bindings");
+        for change in self.bindings.iter() {
+            println!("  {}", change);
+        }
+        if self.bindings.is_empty() {
+            println!("    none");
+        }
+
+        println!("routes");
+        for change in self.routes.iter() {
+            println!("  {}", change);
+        }
+        if self.routes.is_empty() {
+            println!("    none");
+        }
+
+        println!("crons");
+        for change in self.schedules.iter() {
+            println!("  {}", change);
+        }
+        if self.schedules.is_empty() {
+            println!("    none");
+        }
+
+        println!("compatibility date");
+        match (
+            &self.compatibility_date.local,
+            &self.compatibility_date.deployed,
+        ) {
+            (local, deployed) if local == deployed => {
+                println!("    {}", local.as_deref().unwrap_or("none"))
+            }
+            (local, deployed) => println!(
+                "  {} {} (deployed {})",
+                styles::warning("~"),
+                local.as_deref().unwrap_or("none"),
+                deployed.as_deref().unwrap_or("none")
+            ),
+        }
+
+        if !self.compatibility_flags.is_empty() {
+            println!("compatibility flags");
+            for change in self.compatibility_flags.iter() {
+                println!("  {}", change);
+            }
+        }
+        if self.workers_dev {
+            println!("workers.dev\n    enabled");
+        }
+        if !self.custom_domains.is_empty() {
+            println!("custom domains");
+            for hostname in self.custom_domains.iter() {
+                println!("    {}", hostname);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.binding_type)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Change<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(value) => write!(f, "{} {}", styles::cyan("+"), value),
+            Change::Removed(value) => write!(f, "{} {}", styles::warning("-"), value),
+            Change::Unchanged(value) => write!(f, "  {}", value),
+            Change::Conflict(value, script) => write!(
+                f,
+                "{} {} is already pointing to {}",
+                styles::warning("!"),
+                value,
+                script
+            ),
+            Change::Undeclared(value) => write!(
+                f,
+                "{} {} points to the worker, but isn't in your configuration file",
+                styles::warning("?"),
+                value
+            ),
+        }
+    }
+}
+
+/// The bindings that would be uploaded with the Worker.
+///
+/// Values aren't compared, since the API doesn't return the ones of secrets or text.
+pub fn local_bindings(target: &Target) -> Vec<Binding> {
+    let binding = |name: &str, binding_type: &str| Binding {
+        name: name.to_owned(),
+        binding_type: binding_type.to_owned(),
+    };
+
+    let mut bindings = vec![];
+    for namespace in target.kv_namespaces.iter() {
+        bindings.push(binding(&namespace.binding, "kv_namespace"));
+    }
+    for class in target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+    {
+        bindings.push(binding(&class.binding, "durable_object_namespace"));
+    }
+    for name in target.vars.iter().flat_map(|vars| vars.keys()) {
+        bindings.push(binding(name, "plain_text"));
+    }
+    for name in target.text_blobs.iter().flat_map(|blobs| blobs.keys()) {
+        bindings.push(binding(name, "text_blob"));
+    }
+    for name in target
+        .wasm_modules
+        .iter()
+        .flat_map(|modules| modules.keys())
+    {
+        bindings.push(binding(name, "wasm_module"));
+    }
+    if let TargetType::Rust = target.target_type {
+        bindings.push(binding("wasm", "wasm_module"));
+    }
+    for service in target.services.iter() {
+        bindings.push(binding(&service.binding, "service"));
+    }
+    for dataset in target.analytics_engine_datasets.iter() {
+        bindings.push(binding(&dataset.binding, "analytics_engine"));
+    }
+    for producer in target.queue_producers.iter() {
+        bindings.push(binding(&producer.binding, "queue"));
+    }
+    for unsafe_binding in target.unsafe_bindings.iter() {
+        if let (Some(name), Some(binding_type)) = (
+            unsafe_binding["name"].as_str(),
+            unsafe_binding["type"].as_str(),
+        ) {
+            bindings.push(binding(name, binding_type));
+        }
+    }
+    if target.site.is_some() {
+        bindings.push(binding("__STATIC_CONTENT", "kv_namespace"));
+        bindings.push(binding("__STATIC_CONTENT_MANIFEST", "text_blob"));
+    }
+    bindings
+}
+
+/// Compares bindings by name and type, in order of name.
+fn compare(local: Vec<Binding>, deployed: Vec<Binding>) -> Vec<Change<Binding>> {
+    let mut changes = vec![];
+    for binding in local.iter() {
+        if deployed.contains(binding) {
+            changes.push(Change::Unchanged(binding.clone()));
+        } else {
+            changes.push(Change::Added(binding.clone()));
+        }
+    }
+    for binding in deployed {
+        // secrets are set separately, so publishing doesn't remove them
+        if !local.contains(&binding) && binding.binding_type != "secret_text" {
+            changes.push(Change::Removed(binding));
+        }
+    }
+    changes.sort_by(|a, b| name(a).cmp(name(b)));
+    changes
+}
+
+/// Compares values such as the crons of the Worker, in the order they are configured.
+fn compare_values(local: Vec<String>, deployed: Vec<String>) -> Vec<Change<String>> {
+    let mut changes = vec![];
+    for value in local.iter() {
+        if deployed.contains(value) {
+            changes.push(Change::Unchanged(value.clone()));
+        } else {
+            changes.push(Change::Added(value.clone()));
+        }
+    }
+    for value in deployed {
+        if !local.contains(&value) {
+            changes.push(Change::Removed(value));
+        }
+    }
+    changes
+}
+
+fn name(change: &Change<Binding>) -> &str {
+    match change {
+        Change::Added(binding)
+        | Change::Removed(binding)
+        | Change::Unchanged(binding)
+        | Change::Conflict(binding, _)
+        | Change::Undeclared(binding) => &binding.name,
+    }
+}
+
+fn hash(content: &[u8]) -> String {
+    openssl::sha::sha256(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..12.min(hash.len())]
+}
+
+/// The content of the deployed script. Modules are returned in a multipart body with a
+/// part for each of them, so the main module is the part with the name of the local one.
+fn script_content(content_type: Option<&str>, body: &[u8], main_module: Option<&str>) -> Vec<u8> {
+    let boundary = content_type
+        .filter(|content_type| content_type.starts_with("multipart/"))
+        .and_then(|content_type| {
+            content_type
+                .split(';')
+                .find_map(|param| param.trim().strip_prefix("boundary="))
+        })
+        .map(|boundary| boundary.trim_matches('"'));
+    match boundary {
+        Some(boundary) => {
+            let parts = multipart_parts(body, boundary);
+            parts
+                .iter()
+                .find(|(name, _)| Some(name.as_str()) == main_module)
+                .or_else(|| parts.first())
+                .map(|(_, content)| content.to_vec())
+                .unwrap_or_default()
+        }
+        None => body.to_vec(),
+    }
+}
+
+/// The named parts of a multipart body.
+fn multipart_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<(String, &'a [u8])> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut parts = vec![];
+    let mut rest = body;
+    while let Some(start) = find(rest, delimiter) {
+        rest = &rest[start + delimiter.len()..];
+        // the last delimiter is followed by "--"
+        if rest.starts_with(b"--") {
+            break;
+        }
+        let end = find(rest, delimiter).unwrap_or(rest.len());
+        let part = &rest[..end];
+        if let Some(headers_end) = find(part, b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&part[..headers_end]);
+            let content = &part[headers_end + 4..];
+            let content = content.strip_suffix(b"\r\n").unwrap_or(content);
+            parts.push((part_name(&headers).unwrap_or_default(), content));
+        }
+        rest = &rest[end..];
+    }
+    parts
+}
+
+/// The name in the `Content-Disposition` header of a part.
+fn part_name(headers: &str) -> Option<String> {
+    let disposition = headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-disposition") {
+            Some(value)
+        } else {
+            None
+        }
+    })?;
+    disposition.split(';').find_map(|param| {
+        let name = param.trim().strip_prefix("name=")?;
+        Some(name.trim_matches('"').to_owned())
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(name: &str, binding_type: &str) -> Binding {
+        Binding {
+            name: name.to_owned(),
+            binding_type: binding_type.to_owned(),
+        }
+    }
+
+    #[test]
+    fn it_compares_bindings() {
+        let local = vec![
+            binding("CACHE", "kv_namespace"),
+            binding("GREETING", "plain_text"),
+        ];
+        let deployed = vec![
+            binding("API_KEY", "secret_text"),
+            binding("GREETING", "plain_text"),
+            binding("ROOMS", "durable_object_namespace"),
+        ];
+        assert_eq!(
+            compare(local, deployed),
+            vec![
+                Change::Added(binding("CACHE", "kv_namespace")),
+                Change::Unchanged(binding("GREETING", "plain_text")),
+                Change::Removed(binding("ROOMS", "durable_object_namespace")),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_compares_values() {
+        let local = vec!["0 * * * *".to_owned(), "*/5 * * * *".to_owned()];
+        let deployed = vec!["0 * * * *".to_owned(), "0 0 * * *".to_owned()];
+        assert_eq!(
+            compare_values(local, deployed),
+            vec![
+                Change::Unchanged("0 * * * *".to_owned()),
+                Change::Added("*/5 * * * *".to_owned()),
+                Change::Removed("0 0 * * *".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_scripts_in_multipart_bodies() {
+        let script = b"export default { fetch() {} }";
+        let multipart = b"--boundary\r\nContent-Disposition: form-data; name=\"util.mjs\"\r\n\r\nexport const a = 1;\r\n--boundary\r\nContent-Disposition: form-data; name=\"index.mjs\"\r\n\r\nexport default { fetch() {} }\r\n--boundary--\r\n";
+        assert_eq!(script_content(None, script, None), script);
+        assert_eq!(
+            script_content(
+                Some("multipart/form-data; boundary=boundary"),
+                multipart,
+                Some("index.mjs")
+            ),
+            script
+        );
+        assert_eq!(
+            script_content(
+                Some("multipart/form-data; boundary=\"boundary\""),
+                multipart,
+                None
+            ),
+            b"export const a = 1;"
+        );
+    }
+
+    #[test]
+    fn it_changes_scripts_that_only_contain_the_local_one() {
+        let local = b"export default { fetch() {} }";
+        let deployed = b"--boundary\r\nContent-Disposition: form-data; name=\"index.mjs\"\r\n\r\nexport default { fetch() {} }\nconsole.log(1);\r\n--boundary--\r\n";
+        let deployed = script_content(
+            Some("multipart/form-data; boundary=boundary"),
+            deployed,
+            Some("index.mjs"),
+        );
+        assert_ne!(deployed, local);
+        assert_ne!(hash(&deployed), hash(local));
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+/// A namespace user workers are uploaded into, to be run by a dispatch worker.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DispatchNamespace {
+    pub namespace_id: String,
+    pub namespace_name: String,
+    #[serde(default)]
+    pub script_count: u64,
+    pub created_on: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NamespaceResponse {
+    result: DispatchNamespace,
+}
+
+#[derive(Deserialize)]
+struct NamespacesResponse {
+    result: Vec<DispatchNamespace>,
+}
+
+fn namespaces_addr(target: &Target) -> Result<String> {
+    Ok(format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/dispatch/namespaces",
+        target.account_id.load()?
+    ))
+}
+
+pub fn create(user: &GlobalUser, target: &Target, name: &str) -> Result<()> {
+    let response = http::legacy_auth_client(user)
+        .post(&namespaces_addr(target)?)
+        .json(&serde_json::json!({ "name": name }))
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let namespace: NamespaceResponse = response.json()?;
+    StdOut::success(&format!(
+        "Created the dispatch namespace {} with id {}",
+        styles::highlight(&namespace.result.namespace_name),
+        namespace.result.namespace_id
+    ));
+    StdOut::info(&format!(
+        "Upload user workers into it with {}",
+        styles::highlight(format!("wrangler publish --dispatch-namespace {}", name))
+    ));
+    Ok(())
+}
+
+/// Lists the dispatch namespaces of the account (outputs json)
+pub fn list(user: &GlobalUser, target: &Target) -> Result<()> {
+    let response = http::legacy_auth_client(user)
+        .get(&namespaces_addr(target)?)
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let namespaces: NamespacesResponse = response.json()?;
+    println!("{}", serde_json::to_string(&namespaces.result)?);
+    Ok(())
+}
+
+pub fn delete(user: &GlobalUser, target: &Target, name: &str) -> Result<()> {
+    if !interactive::confirm(&format!(
+        "Are you sure you want to delete the dispatch namespace {}, and all of the user workers in it?",
+        name
+    ))? {
+        StdOut::info(&format!("Not deleting the dispatch namespace {}", name));
+        return Ok(());
+    }
+
+    let response = http::legacy_auth_client(user)
+        .delete(&format!("{}/{}", namespaces_addr(target)?, name))
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    StdOut::success(&format!("Deleted the dispatch namespace {}", name));
+    Ok(())
+}
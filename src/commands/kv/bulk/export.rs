@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::http;
+use crate::kv::bulk::BulkPair;
+use crate::kv::key::{encode_value, get_value, KeyList, ListedKey};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+/// Turns a key into the pair `kv:bulk put` reads, so an export can be uploaded again.
+fn exported_pair(key: ListedKey, value: Vec<u8>) -> BulkPair {
+    let (value, base64) = encode_value(value);
+    BulkPair {
+        key: key.name,
+        value,
+        expiration: key.expiration,
+        metadata: key.metadata,
+        base64,
+        ..Default::default()
+    }
+}
+
+pub fn run(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    prefix: Option<&str>,
+    out: &Path,
+) -> Result<()> {
+    StdErr::working("Listing the keys of the namespace");
+    let keys = KeyList::new(target, user, namespace_id, prefix)?.collect::<Result<Vec<_>>>()?;
+
+    let progress_bar = ProgressBar::new(keys.len() as u64);
+    progress_bar.set_style(
+        ProgressStyle::default_bar().template("{wide_bar} {pos}/{len} keys ({eta} left)"),
+    );
+
+    let client = http::legacy_auth_client(user);
+    let mut pairs = Vec::with_capacity(keys.len());
+    for key in keys {
+        // keys deleted since they were listed are left out
+        if let Some(value) = get_value(target, &client, namespace_id, &key.name)? {
+            pairs.push(exported_pair(key, value));
+        }
+        progress_bar.inc(1);
+    }
+    progress_bar.finish_and_clear();
+
+    fs::write(out, serde_json::to_string_pretty(&pairs)?)?;
+    StdErr::success(&format!(
+        "Exported {} key value pairs to {}",
+        pairs.len(),
+        out.display()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed_key(name: &str) -> ListedKey {
+        ListedKey {
+            name: name.to_owned(),
+            expiration: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn it_exports_text_values_as_they_are() {
+        let mut key = listed_key("greeting");
+        key.expiration = Some(1_700_000_000);
+        key.metadata = Some(serde_json::json!({ "lang": "en" }));
+
+        let pair = exported_pair(key, b"hello".to_vec());
+        assert_eq!(
+            serde_json::to_value(&pair).unwrap(),
+            serde_json::json!({
+                "key": "greeting",
+                "value": "hello",
+                "expiration": 1_700_000_000,
+                "metadata": { "lang": "en" },
+            })
+        );
+    }
+
+    #[test]
+    fn it_exports_binary_values_as_base64() {
+        let pair = exported_pair(listed_key("image"), vec![0xff, 0xd8, 0xff]);
+        assert_eq!(pair.value, "/9j/");
+        assert_eq!(pair.base64, Some(true));
+    }
+}
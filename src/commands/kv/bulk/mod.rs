@@ -1,5 +1,7 @@
 pub mod delete;
+pub mod export;
 pub mod put;
 
 pub use delete::run as delete;
+pub use export::run as export;
 pub use put::run as put;
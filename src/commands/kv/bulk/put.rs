@@ -2,25 +2,82 @@ extern crate base64;
 
 use std::fs;
 use std::fs::metadata;
-use std::path::Path;
-
-use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
 
-use crate::kv::bulk::put;
+use crate::commands::kv::key::{now, validate_expiration, validate_metadata};
 use crate::kv::bulk::BATCH_KEY_MAX;
+use crate::kv::bulk::{batch_keys_values, put_batches, BulkPair};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdErr};
-pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Path) -> Result<()> {
-    let pairs: Vec<KeyValuePair> = match &metadata(filename) {
+
+/// The batches of a bulk upload that were uploaded, so running it again resumes after them.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct Checkpoint {
+    namespace_id: String,
+    /// the hash of the uploaded file, so a changed file is uploaded from the start
+    digest: String,
+    uploaded: Vec<usize>,
+}
+
+impl Checkpoint {
+    fn path(filename: &Path) -> PathBuf {
+        let mut path = filename.as_os_str().to_owned();
+        path.push(".progress");
+        PathBuf::from(path)
+    }
+
+    /// Reads the checkpoint of an earlier upload of the same file to the same namespace,
+    /// starting over when there isn't one or the file has changed since.
+    fn load(path: &Path, namespace_id: &str, digest: &str) -> Checkpoint {
+        let checkpoint = fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<Checkpoint>(&data).ok());
+        match checkpoint {
+            Some(checkpoint)
+                if checkpoint.namespace_id == namespace_id && checkpoint.digest == digest =>
+            {
+                checkpoint
+            }
+            _ => Checkpoint {
+                namespace_id: namespace_id.to_owned(),
+                digest: digest.to_owned(),
+                uploaded: vec![],
+            },
+        }
+    }
+
+    fn digest(data: &str) -> String {
+        let mut hasher = XxHash64::default();
+        hasher.write(data.as_bytes());
+        format!("{:x}", hasher.finish())
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub fn run(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    filename: &Path,
+    concurrency: usize,
+) -> Result<()> {
+    let (pairs, digest): (Vec<BulkPair>, String) = match &metadata(filename) {
         Ok(file_type) if file_type.is_file() => {
             let data = fs::read_to_string(filename)?;
             let data_vec = serde_json::from_str(&data);
             match data_vec {
-                Ok(data_vec) => Ok(data_vec),
+                Ok(data_vec) => Ok((data_vec, Checkpoint::digest(&data))),
                 Err(_) => Err(anyhow!("Failed to decode JSON. Please make sure to follow the format, [{{\"key\": \"test_key\", \"value\": \"test_value\"}}, ...]"))
             }
         }
@@ -30,24 +87,124 @@ pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Pa
         )),
         Err(e) => Err(anyhow!("{}", e)),
     }?;
+    let now = now();
+    for pair in pairs.iter() {
+        validate_expiration(pair.expiration, pair.expiration_ttl, now)
+            .map_err(|e| anyhow!("Can't put {}, {}", pair.key, e))?;
+        if let Some(metadata) = &pair.metadata {
+            validate_metadata(metadata)
+                .map_err(|e| anyhow!("The metadata of {} is too large, {}", pair.key, e))?;
+        }
+    }
 
     let len = pairs.len();
+    let checkpoint_path = Checkpoint::path(filename);
+    let mut checkpoint = Checkpoint::load(&checkpoint_path, namespace_id, &digest);
+
+    let batches = batch_keys_values(pairs);
+    // an upload of a single batch has nothing to resume from
+    let mut resumable = batches.len() > 1;
+    let batches = batches
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !checkpoint.uploaded.contains(index))
+        .collect::<Vec<_>>();
+    let remaining = batches.iter().map(|(_, batch)| batch.len()).sum::<usize>();
+    if remaining < len {
+        StdErr::info(&format!(
+            "Resuming an earlier upload, {} of the key value pairs were already uploaded",
+            len - remaining
+        ));
+    }
 
-    StdErr::working(&format!("uploading {} key value pairs", len));
+    StdErr::working(&format!("uploading {} key value pairs", remaining));
     let progress_bar = if len > BATCH_KEY_MAX {
         let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{wide_bar} {pos}/{len} ({per_sec}, {eta} left)\n{msg}"),
+        );
+        pb.set_position((len - remaining) as u64);
         Some(pb)
     } else {
         None
     };
 
-    put(target, user, namespace_id, pairs, &progress_bar)?;
+    let result = put_batches(
+        target,
+        user,
+        namespace_id,
+        batches,
+        concurrency,
+        &progress_bar,
+        |index| {
+            checkpoint.uploaded.push(index);
+            // the upload goes on without a checkpoint, it just can't be resumed
+            if resumable {
+                if let Err(e) = checkpoint.save(&checkpoint_path) {
+                    StdErr::warn(&format!(
+                        "Failed to record the progress of the upload in {}, so it can't be resumed: {}",
+                        checkpoint_path.display(),
+                        e
+                    ));
+                    resumable = false;
+                }
+            }
+            Ok(())
+        },
+    );
+    if let Err(e) = result {
+        if let Some(pb) = &progress_bar {
+            pb.abandon();
+        }
+        if resumable && !checkpoint.uploaded.is_empty() {
+            StdErr::info(&format!(
+                "The uploaded batches are recorded in {}, run the same command again to upload the rest",
+                checkpoint_path.display()
+            ));
+        }
+        return Err(e);
+    }
 
     if let Some(pb) = &progress_bar {
         pb.finish_with_message(&format!("uploaded {} key value pairs", len));
     }
+    if checkpoint_path.exists() {
+        fs::remove_file(&checkpoint_path)?;
+    }
 
     StdErr::success("Success");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_resumes_uploads_of_the_same_file_to_the_same_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Checkpoint::path(&dir.path().join("pairs.json"));
+        assert!(path.ends_with("pairs.json.progress"));
+
+        let digest = Checkpoint::digest(r#"[{"key": "a", "value": "1"}]"#);
+        let checkpoint = Checkpoint {
+            namespace_id: "namespace".to_owned(),
+            digest: digest.clone(),
+            uploaded: vec![0, 2],
+        };
+        checkpoint.save(&path).unwrap();
+
+        assert_eq!(Checkpoint::load(&path, "namespace", &digest), checkpoint);
+        assert!(Checkpoint::load(&path, "other", &digest)
+            .uploaded
+            .is_empty());
+
+        // a file with the same number of pairs, but a changed value
+        let changed = Checkpoint::digest(r#"[{"key": "a", "value": "2"}]"#);
+        assert_ne!(changed, digest);
+        assert!(Checkpoint::load(&path, "namespace", &changed)
+            .uploaded
+            .is_empty());
+    }
+}
@@ -2,23 +2,29 @@ use cloudflare::endpoints::workerskv::delete_key::DeleteKey;
 use cloudflare::framework::apiclient::ApiClient;
 
 use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::commands::kv::format_error;
 use crate::http;
+use crate::kv::bulk;
+use crate::kv::key::KeyList;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
-pub fn delete(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()> {
+
+pub fn delete(target: &Target, user: &GlobalUser, id: &str, key: &str, force: bool) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
-    match interactive::confirm(&format!("Are you sure you want to delete key \"{}\"?", key)) {
-        Ok(true) => (),
-        Ok(false) => {
-            StdOut::info(&format!("Not deleting key \"{}\"", key));
-            return Ok(());
+    if !force {
+        match interactive::confirm(&format!("Are you sure you want to delete key \"{}\"?", key)) {
+            Ok(true) => (),
+            Ok(false) => {
+                StdOut::info(&format!("Not deleting key \"{}\"", key));
+                return Ok(());
+            }
+            Err(e) => anyhow::bail!(e),
         }
-        Err(e) => anyhow::bail!(e),
     }
 
     let msg = format!("Deleting key \"{}\"", key);
@@ -37,3 +43,53 @@ pub fn delete(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result
 
     Ok(())
 }
+
+/// Deletes every key starting with a prefix, in batches as large as the bulk API takes.
+pub fn delete_prefix(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    prefix: &str,
+    force: bool,
+) -> Result<()> {
+    if prefix.is_empty() {
+        anyhow::bail!("--prefix can't be empty, as that would delete every key of the namespace")
+    }
+
+    let keys = KeyList::new(target, user, id, Some(prefix))?
+        .map(|key| key.map(|key| key.name))
+        .collect::<Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        StdOut::info(&format!("There are no keys starting with \"{}\"", prefix));
+        return Ok(());
+    }
+
+    if !force {
+        match interactive::confirm(&format!(
+            "Are you sure you want to delete the {} keys starting with \"{}\"?",
+            keys.len(),
+            prefix
+        )) {
+            Ok(true) => (),
+            Ok(false) => {
+                StdOut::info(&format!("Not deleting keys starting with \"{}\"", prefix));
+                return Ok(());
+            }
+            Err(e) => anyhow::bail!(e),
+        }
+    }
+
+    let len = keys.len();
+    StdOut::working(&format!(
+        "Deleting {} keys starting with \"{}\"",
+        len, prefix
+    ));
+    let progress_bar = ProgressBar::new(len as u64);
+    progress_bar.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
+
+    bulk::delete(target, user, id, keys, &Some(progress_bar.clone()))?;
+
+    progress_bar.finish_with_message(&format!("deleted {} keys", len));
+    StdOut::success("Success");
+    Ok(())
+}
@@ -9,11 +9,32 @@ use anyhow::Result;
 
 use crate::commands::kv;
 use crate::http;
+use crate::kv::key::get_metadata;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 
-pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()> {
+/// How `wrangler kv:key get` hands over the value.
+#[derive(Debug, Default)]
+pub struct GetOptions<'a> {
+    /// Also prints the metadata of the key
+    pub show_metadata: bool,
+    /// Writes the value to this file instead of stdout
+    pub out: Option<&'a Path>,
+    /// Prints the value encoded as base64
+    pub base64: bool,
+}
+
+pub fn get(
+    target: &Target,
+    user: &GlobalUser,
+    id: &str,
+    key: &str,
+    options: GetOptions,
+) -> Result<()> {
     let api_endpoint = format!(
         "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
         target.account_id.load()?,
@@ -32,7 +53,21 @@ pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()
         // in case someone is piping this to stdin.
         // This will probably fail for non-UTF8 on Windows, but should at least work for people
         // getting binary data from KV on Unix-y systems.
-        io::stdout().write_all(&*body)?;
+        if let Some(out) = options.out {
+            fs::write(out, &*body)?;
+            StdErr::success(&format!("Wrote {} bytes to {}", body.len(), out.display()));
+        } else if options.base64 {
+            println!("{}", base64::encode(&*body));
+        } else {
+            io::stdout().write_all(&*body)?;
+        }
+        // the metadata goes to stderr, so piping the value keeps working
+        if options.show_metadata {
+            match get_metadata(target, &client, id, key)? {
+                Some(metadata) => StdErr::info(&format!("metadata: {}", metadata)),
+                None => StdErr::info("metadata: none"),
+            }
+        }
     } else {
         // This is logic pulled from cloudflare-rs for pretty error formatting right now;
         // it will be redundant when we switch to using cloudflare-rs for all API requests.
@@ -1,13 +1,39 @@
 extern crate serde_json;
 
-use crate::commands::kv;
-use crate::http;
 use crate::kv::key::KeyList;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
 
 use anyhow::Result;
 
+// The range of page sizes the API accepts
+const MIN_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 1000;
+
+/// Which keys `wrangler kv:key list` lists, and how.
+#[derive(Debug, Default)]
+pub struct ListOptions<'a> {
+    pub prefix: Option<&'a str>,
+    /// Lists a single page of this many keys instead of every key
+    pub limit: Option<u32>,
+    /// Where to start listing, as returned with the last page
+    pub cursor: Option<&'a str>,
+    pub show_metadata: bool,
+}
+
+fn validate_limit(limit: u32) -> Result<()> {
+    if !(MIN_LIMIT..=MAX_LIMIT).contains(&limit) {
+        anyhow::bail!(
+            "--limit must be between {} and {}, but is {}",
+            MIN_LIMIT,
+            MAX_LIMIT,
+            limit
+        )
+    }
+    Ok(())
+}
+
 // Note: this function only prints keys in json form, given that
 // the number of entries in each json blob is variable (so csv and tsv
 // representation won't make sense)
@@ -15,31 +41,55 @@ pub fn list(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
-    prefix: Option<&str>,
+    options: ListOptions,
 ) -> Result<()> {
-    let client = http::cf_v4_client(user)?;
-    let key_list = KeyList::new(target, client, namespace_id, prefix)?;
+    if let Some(limit) = options.limit {
+        validate_limit(limit)?;
+    }
+
+    let mut key_list = KeyList::new(target, user, namespace_id, options.prefix)?
+        .from_cursor(options.cursor)
+        .limit(options.limit);
 
     print!("["); // Open json list bracket
 
     let mut first_key = true;
 
-    for key_result in key_list {
-        match key_result {
-            Ok(key) => {
-                if first_key {
-                    first_key = false;
-                } else {
-                    print!(",");
-                }
-
-                print!("{}", serde_json::to_string(&key)?);
-            }
-            Err(e) => print!("{}", kv::format_error(e)),
+    for key in key_list.by_ref() {
+        let mut key = key?;
+        if !options.show_metadata {
+            key.metadata = None;
+        }
+        if first_key {
+            first_key = false;
+        } else {
+            print!(",");
         }
+
+        print!("{}", serde_json::to_string(&key)?);
     }
 
     print!("]"); // Close json list bracket
 
+    // without a limit every page is listed, so there is only a cursor left after a limit
+    if let Some(cursor) = key_list.cursor() {
+        StdErr::info(&format!(
+            "There are more keys, pass --cursor {} to list the next ones",
+            cursor
+        ));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_limits_pages_to_what_the_api_accepts() {
+        assert!(validate_limit(10).is_ok());
+        assert!(validate_limit(1000).is_ok());
+        assert!(validate_limit(9).is_err());
+        assert!(validate_limit(1001).is_err());
+    }
+}
@@ -17,11 +17,20 @@ use crate::terminal::message::{Message, StdOut};
 use regex::Regex;
 use reqwest::blocking::multipart;
 
+/// The limit the API puts on the size of the metadata of a key, in bytes.
+const METADATA_MAX_SIZE: usize = 1024;
+/// How soon a key can expire at the earliest, in seconds.
+const MIN_EXPIRATION_TTL: u64 = 60;
+// Timestamps past this are in milliseconds rather than seconds, as it's the year 5138
+const MAX_EXPIRATION: u64 = 100_000_000_000;
+
 pub struct KVMetaData {
     pub namespace_id: String,
     pub key: String,
     pub value: String,
     pub is_file: bool,
+    /// The value, or the contents of the file, is encoded as base64
+    pub base64: bool,
     pub expiration: Option<String>,
     pub expiration_ttl: Option<String>,
     pub metadata: Option<serde_json::Value>,
@@ -48,7 +57,62 @@ pub fn parse_metadata(arg: Option<&str>) -> Result<Option<serde_json::Value>> {
     }
 }
 
+/// Checks metadata against the size limit of the API, so it fails before anything is uploaded.
+pub fn validate_metadata(metadata: &serde_json::Value) -> Result<()> {
+    let size = metadata.to_string().len();
+    if size > METADATA_MAX_SIZE {
+        anyhow::bail!(
+            "metadata is {} bytes once serialized, but can be no more than {} bytes",
+            size,
+            METADATA_MAX_SIZE
+        )
+    }
+    Ok(())
+}
+
+/// Checks that a key expires at least a minute from `now`, which the API requires.
+pub fn validate_expiration(
+    expiration: Option<u64>,
+    expiration_ttl: Option<u64>,
+    now: u64,
+) -> Result<()> {
+    if let Some(ttl) = expiration_ttl {
+        if ttl < MIN_EXPIRATION_TTL {
+            anyhow::bail!(
+                "the expiration TTL must be at least {} seconds, but is {}",
+                MIN_EXPIRATION_TTL,
+                ttl
+            )
+        }
+    }
+    if let Some(expiration) = expiration {
+        if expiration >= MAX_EXPIRATION {
+            anyhow::bail!(
+                "the expiration {} is too far in the future, it should be in seconds since the UNIX epoch rather than milliseconds",
+                expiration
+            )
+        }
+        if expiration < now + MIN_EXPIRATION_TTL {
+            anyhow::bail!(
+                "the expiration {} must be at least {} seconds from now, at {} or later",
+                expiration,
+                MIN_EXPIRATION_TTL,
+                now + MIN_EXPIRATION_TTL
+            )
+        }
+    }
+    Ok(())
+}
+
+/// The current time as the API takes it, in seconds since the UNIX epoch.
+pub fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
 pub fn put(target: &Target, user: &GlobalUser, data: KVMetaData) -> Result<()> {
+    if let Some(metadata) = &data.metadata {
+        validate_metadata(metadata)?;
+    }
     let api_endpoint = format!(
         "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
         target.account_id.load()?,
@@ -108,8 +172,23 @@ fn get_response(
 }
 
 // If is_file is true, overwrite value to be the contents of the given
-// filename in the 'value' arg.
+// filename in the 'value' arg. Values encoded as base64 are uploaded decoded, ignoring
+// whitespace so wrapped output like that of `base64` is accepted.
 fn get_request_body(data: &KVMetaData) -> Result<Vec<u8>> {
+    let body = read_value(data)?;
+    if data.base64 {
+        let encoded: Vec<u8> = body
+            .into_iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        return base64::decode(&encoded).map_err(|e| {
+            anyhow::anyhow!("--base64 was passed, but the value isn't base64: {}", e)
+        });
+    }
+    Ok(body)
+}
+
+fn read_value(data: &KVMetaData) -> Result<Vec<u8>> {
     if data.is_file {
         match &metadata(&data.value) {
             Ok(file_type) if file_type.is_file() => Ok(fs::read(&data.value)?),
@@ -132,6 +211,30 @@ fn get_request_body(data: &KVMetaData) -> Result<Vec<u8>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn it_decodes_base64_values() {
+        let mut data = KVMetaData {
+            namespace_id: "namespace".to_owned(),
+            key: "image".to_owned(),
+            value: "/9j/\n".to_owned(),
+            is_file: false,
+            base64: true,
+            expiration: None,
+            expiration_ttl: None,
+            metadata: None,
+        };
+        assert_eq!(get_request_body(&data).unwrap(), vec![0xff, 0xd8, 0xff]);
+
+        data.value = "/9j/\r\n/9j/\n".to_owned();
+        assert_eq!(
+            get_request_body(&data).unwrap(),
+            vec![0xff, 0xd8, 0xff, 0xff, 0xd8, 0xff]
+        );
+
+        data.value = "not base64!".to_owned();
+        assert!(get_request_body(&data).is_err());
+    }
+
     #[test]
     fn metadata_parser_legal() {
         for input in &[
@@ -153,6 +256,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_limits_the_size_of_metadata() {
+        assert!(validate_metadata(&serde_json::json!({ "a": "b".repeat(1000) })).is_ok());
+        assert!(validate_metadata(&serde_json::json!({ "a": "b".repeat(1024) })).is_err());
+    }
+
+    #[test]
+    fn it_rejects_keys_expiring_within_a_minute() {
+        let now = 1_600_000_000;
+        assert!(validate_expiration(None, None, now).is_ok());
+        assert!(validate_expiration(None, Some(60), now).is_ok());
+        assert!(validate_expiration(None, Some(59), now).is_err());
+        assert!(validate_expiration(Some(now + 60), None, now).is_ok());
+        assert!(validate_expiration(Some(now + 59), None, now).is_err());
+        assert!(validate_expiration(Some(now - 3600), Some(3600), now).is_err());
+    }
+
+    #[test]
+    fn it_rejects_expirations_in_milliseconds() {
+        let now = 1_600_000_000;
+        let error = validate_expiration(Some(now * 1000), None, now).unwrap_err();
+        assert!(error.to_string().contains("milliseconds"));
+    }
+
     #[test]
     fn metadata_parser_error_message_unquoted_string_error_message() -> Result<(), &'static str> {
         for input in &["abc", "'abc'", "'abc", "abc'", "\"abc", "abc\""] {
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::value::Value as JsonValue;
+
+use crate::http;
+use crate::kv::key::{get_metadata, get_value, KeyList};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::styles;
+
+// Longer values are cut short when they are printed
+const MAX_PRINTED_VALUE: usize = 200;
+
+// Every poll reads each watched key, so polls are kept this far apart
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What is watched by `wrangler kv:key watch`.
+#[derive(Clone, Copy, Debug)]
+pub enum Watched<'a> {
+    Key(&'a str),
+    Prefix(&'a str),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    value: Vec<u8>,
+    metadata: Option<JsonValue>,
+}
+
+type Snapshot = BTreeMap<String, Entry>;
+
+fn validate_interval(interval: Duration) -> Result<()> {
+    if interval < MIN_INTERVAL {
+        anyhow::bail!(
+            "--interval must be at least {}s, but is {}s",
+            MIN_INTERVAL.as_secs(),
+            interval.as_secs()
+        )
+    }
+    Ok(())
+}
+
+/// Polls a key, or the keys starting with a prefix, printing what changed every time
+/// their values or metadata change, until it's stopped.
+pub fn watch(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    watched: Watched,
+    interval: Duration,
+) -> Result<()> {
+    validate_interval(interval)?;
+    let client = http::legacy_auth_client(user);
+    let snapshot = || -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+        match watched {
+            Watched::Key(key) => {
+                if let Some(value) = get_value(target, &client, namespace_id, key)? {
+                    let metadata = get_metadata(target, &client, namespace_id, key)?;
+                    snapshot.insert(key.to_owned(), Entry { value, metadata });
+                }
+            }
+            Watched::Prefix(prefix) => {
+                for key in KeyList::new(target, user, namespace_id, Some(prefix))? {
+                    let key = key?;
+                    if let Some(value) = get_value(target, &client, namespace_id, &key.name)? {
+                        let metadata = key.metadata;
+                        snapshot.insert(key.name, Entry { value, metadata });
+                    }
+                }
+            }
+        }
+        Ok(snapshot)
+    };
+
+    let mut last = snapshot()?;
+    StdErr::info(&format!(
+        "Watching {} keys every {}s, press Ctrl-C to stop",
+        last.len(),
+        interval.as_secs()
+    ));
+    loop {
+        thread::sleep(interval);
+        let time = chrono::Local::now().format("%H:%M:%S");
+        // A failed poll is retried on the next interval, comparing against the last good one
+        match snapshot() {
+            Ok(next) => {
+                for change in changes(&last, &next) {
+                    println!("[{}] {}", time, change);
+                }
+                last = next;
+            }
+            Err(e) => StdErr::warn(&format!("[{}] Failed to poll the keys: {}", time, e)),
+        }
+    }
+}
+
+/// Describes how the keys changed between two polls.
+fn changes(before: &Snapshot, after: &Snapshot) -> Vec<String> {
+    let mut changes = vec![];
+    for (key, entry) in after.iter() {
+        match before.get(key) {
+            None => changes.push(format!(
+                "{} {} = {}",
+                styles::highlight("added"),
+                key,
+                printed(entry)
+            )),
+            Some(previous) if previous != entry => changes.push(format!(
+                "{} {}: {} -> {}",
+                styles::warning("changed"),
+                key,
+                printed(previous),
+                printed(entry)
+            )),
+            Some(_) => {}
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        changes.push(format!("{} {}", styles::warning("deleted"), key));
+    }
+    changes
+}
+
+fn printed(entry: &Entry) -> String {
+    let mut value = match std::str::from_utf8(&entry.value) {
+        Ok(text) if text.chars().count() > MAX_PRINTED_VALUE => format!(
+            "{:?}...",
+            text.chars().take(MAX_PRINTED_VALUE).collect::<String>()
+        ),
+        Ok(text) => format!("{:?}", text),
+        Err(_) => format!("<{} bytes>", entry.value.len()),
+    };
+    if let Some(metadata) = &entry.metadata {
+        value = format!("{} (metadata {})", value, metadata);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(value: &str) -> Entry {
+        Entry {
+            value: value.as_bytes().to_vec(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn it_rejects_intervals_that_poll_too_often() {
+        assert!(validate_interval(Duration::from_secs(5)).is_ok());
+        assert!(validate_interval(Duration::from_secs(60)).is_ok());
+        assert!(validate_interval(Duration::from_secs(1)).is_err());
+        assert!(validate_interval(Duration::from_secs(0)).is_err());
+    }
+
+    #[test]
+    fn it_prints_added_changed_and_deleted_keys() {
+        let mut before = Snapshot::new();
+        before.insert("flag".to_owned(), entry("off"));
+        before.insert("gone".to_owned(), entry("1"));
+        before.insert("same".to_owned(), entry("1"));
+
+        let mut after = Snapshot::new();
+        after.insert("flag".to_owned(), entry("on"));
+        after.insert("new".to_owned(), entry("1"));
+        after.insert("same".to_owned(), entry("1"));
+
+        let changes = changes(&before, &after);
+        assert_eq!(changes.len(), 3);
+        assert!(changes[0].contains("flag: \"off\" -> \"on\""));
+        assert!(changes[1].contains("new = \"1\""));
+        assert!(changes[2].contains("gone"));
+    }
+
+    #[test]
+    fn it_prints_metadata_changes() {
+        let before = entry("on");
+        let mut after = before.clone();
+        after.metadata = Some(serde_json::json!({ "by": "ops" }));
+        assert_eq!(printed(&after), r#""on" (metadata {"by":"ops"})"#);
+
+        let before = vec![("flag".to_owned(), before)].into_iter().collect();
+        let after = vec![("flag".to_owned(), after)].into_iter().collect();
+        assert_eq!(changes(&before, &after).len(), 1);
+    }
+}
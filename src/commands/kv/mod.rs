@@ -80,7 +80,7 @@ pub fn get_namespace_id(target: &Target, binding: &str) -> Result<String> {
 
 const KV_ASCII_SET: &AsciiSet = &CONTROLS.add(b'/');
 
-fn url_encode_key(key: &str) -> String {
+pub fn url_encode_key(key: &str) -> String {
     utf8_percent_encode(key, KV_ASCII_SET).to_string()
 }
 
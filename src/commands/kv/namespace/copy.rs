@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::http;
+use crate::kv::bulk;
+use crate::kv::bulk::{batch_keys_values, BulkPair};
+use crate::kv::key::{encode_value, get_value, KeyList};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+/// Where the key-value pairs of `wrangler kv:namespace copy` come from and go to.
+pub struct NamespaceCopy<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// The account of the namespace to copy to, when it isn't the account of the source
+    pub to_account_id: Option<String>,
+    pub prefix: Option<&'a str>,
+    /// Deletes the keys of the destination that the source doesn't have
+    pub delete_missing: bool,
+}
+
+/// Copies a namespace a page of keys at a time, so the whole namespace never has to be
+/// held in memory.
+pub fn run(target: &Target, user: &GlobalUser, copy: NamespaceCopy) -> Result<()> {
+    if copy.from == copy.to && copy.to_account_id.is_none() {
+        anyhow::bail!("--from and --to are the same namespace, there is nothing to copy");
+    }
+    let mut destination = target.clone();
+    if let Some(account_id) = &copy.to_account_id {
+        destination.account_id = Some(account_id.to_owned()).into();
+    }
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(ProgressStyle::default_spinner().template("{spinner} {msg}"));
+    let client = http::legacy_auth_client(user);
+    let mut copied = HashSet::new();
+    let mut key_list = KeyList::new(target, user, copy.from, copy.prefix)?;
+    while let Some(keys) = key_list.next_page()? {
+        let mut pairs = Vec::with_capacity(keys.len());
+        for key in keys {
+            // keys deleted since they were listed are left out
+            if let Some(value) = get_value(target, &client, copy.from, &key.name)? {
+                let (value, base64) = encode_value(value);
+                copied.insert(key.name.clone());
+                pairs.push(BulkPair {
+                    key: key.name,
+                    value,
+                    expiration: key.expiration,
+                    metadata: key.metadata,
+                    base64,
+                    ..Default::default()
+                });
+            }
+        }
+        let batches = batch_keys_values(pairs).into_iter().enumerate().collect();
+        bulk::put_batches(&destination, user, copy.to, batches, 1, &None, |_| Ok(()))?;
+        spinner.set_message(&format!("copied {} keys", copied.len()));
+    }
+    spinner.finish_and_clear();
+
+    let mut deleted = 0;
+    if copy.delete_missing {
+        let mut missing = vec![];
+        for key in KeyList::new(&destination, user, copy.to, copy.prefix)? {
+            let name = key?.name;
+            if !copied.contains(&name) {
+                missing.push(name);
+            }
+        }
+        deleted = missing.len();
+        bulk::delete(&destination, user, copy.to, missing, &None)?;
+    }
+
+    let mut msg = format!(
+        "Copied {} keys from {} to {}",
+        copied.len(),
+        copy.from,
+        copy.to
+    );
+    if copy.delete_missing {
+        msg = format!(
+            "{}, deleting {} keys that {} doesn't have",
+            msg, deleted, copy.from
+        );
+    }
+    StdErr::success(&msg);
+    Ok(())
+}
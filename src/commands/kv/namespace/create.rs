@@ -1,4 +1,7 @@
-use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
 use regex::Regex;
 
 use crate::commands::kv;
@@ -7,12 +10,16 @@ use crate::kv::namespace::create;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{ConfigKvNamespace, KvNamespace, Manifest};
 use crate::terminal::message::{Message, StdOut};
+
+/// Creates a namespace, adding it to the configuration file at `update_config` when it's
+/// passed, or else printing what to add.
 pub fn run(
     manifest: &Manifest,
     is_preview: bool,
     env: Option<&str>,
     user: &GlobalUser,
     binding: &str,
+    update_config: Option<&Path>,
 ) -> Result<()> {
     let account_id = manifest.get_account_id(env)?;
     let worker_name = manifest.worker_name(env);
@@ -32,6 +39,17 @@ pub fn run(
         Ok(success) => {
             let namespace = success.result;
             StdOut::success("Success!");
+            if let Some(config_path) = update_config {
+                let config = fs::read_to_string(config_path)?;
+                let config = add_to_config(&config, env, binding, &namespace.id, is_preview)?;
+                fs::write(config_path, config)?;
+                StdOut::success(&format!(
+                    "Added the binding {} to {}",
+                    binding,
+                    config_path.display()
+                ));
+                return Ok(());
+            }
             println!(
                 "{}",
                 toml_modification_instructions(
@@ -61,6 +79,63 @@ fn validate_binding(binding: &str) -> Result<()> {
     Ok(())
 }
 
+/// Adds a namespace to the kv_namespaces of a configuration file, next to the other
+/// namespace of its binding if there is one, keeping the rest of the file as it is.
+fn add_to_config(
+    config: &str,
+    env: Option<&str>,
+    binding: &str,
+    id: &str,
+    is_preview: bool,
+) -> Result<String> {
+    // toml-edit keeps the comments and formatting of the file, unlike toml-rs
+    let mut doc = config
+        .parse::<toml_edit::Document>()
+        .map_err(|e| anyhow!("Failed to parse your configuration file: {}", e))?;
+    let field = if is_preview { "preview_id" } else { "id" };
+    let table = match env {
+        Some(env) => &mut doc["env"][env],
+        None => &mut doc.root,
+    };
+
+    let namespaces = &mut table["kv_namespaces"];
+    let len = match (namespaces.as_array(), namespaces.as_array_of_tables()) {
+        (Some(array), _) => array.len(),
+        (_, Some(tables)) => tables.len(),
+        _ if namespaces.is_none() => 0,
+        _ => anyhow::bail!("kv_namespaces in your configuration file should be an array"),
+    };
+    for i in 0..len {
+        if namespaces[i]["binding"].as_str() == Some(binding) {
+            namespaces[i][field] = toml_edit::value(id);
+            return Ok(doc.to_string_in_original_order());
+        }
+    }
+
+    if let Some(tables) = namespaces.as_array_of_tables_mut() {
+        let table = tables.append(toml_edit::Table::new());
+        table["binding"] = toml_edit::value(binding);
+        table[field] = toml_edit::value(id);
+    } else {
+        let mut namespace = toml_edit::InlineTable::default();
+        namespace.get_or_insert("binding", binding);
+        namespace.get_or_insert(field, id);
+        match namespaces.as_array_mut() {
+            Some(array) => {
+                array
+                    .push(namespace)
+                    .map_err(|_| anyhow!("kv_namespaces should only have namespaces"))?;
+            }
+            None => {
+                let mut array = toml_edit::Array::default();
+                array.push(namespace).unwrap();
+                *namespaces = toml_edit::value(array);
+            }
+        }
+    }
+    Ok(doc.to_string_in_original_order())
+}
+
 fn toml_modification_instructions(
     new_namespace: KvNamespace,
     all_namespaces: Option<&Vec<ConfigKvNamespace>>,
@@ -127,6 +202,64 @@ fn toml_modification_instructions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_adds_a_namespace_and_its_preview_to_the_config() {
+        let config = r#"# my worker
+name = "worker"
+type = "javascript"
+kv_namespaces = [
+  { binding = "CACHE", id = "cache_id" }, # the cache
+]
+"#;
+        let config = add_to_config(config, None, "FLAGS", "flags_id", false).unwrap();
+        let config = add_to_config(&config, None, "CACHE", "cache_preview_id", true).unwrap();
+        assert!(config.starts_with("# my worker\n"));
+        assert!(config.contains("# the cache"));
+
+        let manifest = Manifest::from_str(&config).unwrap();
+        assert_eq!(
+            manifest.kv_namespaces.unwrap(),
+            vec![
+                ConfigKvNamespace {
+                    binding: "CACHE".to_string(),
+                    id: Some("cache_id".to_string()),
+                    preview_id: Some("cache_preview_id".to_string()),
+                },
+                ConfigKvNamespace {
+                    binding: "FLAGS".to_string(),
+                    id: Some("flags_id".to_string()),
+                    preview_id: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_adds_a_namespace_to_an_environment() {
+        let config = r#"name = "worker"
+type = "javascript"
+
+[env.staging]
+name = "worker-staging"
+
+[[env.staging.kv_namespaces]]
+binding = "CACHE"
+id = "cache_id"
+"#;
+        let config = add_to_config(config, Some("staging"), "FLAGS", "flags_id", false).unwrap();
+        let manifest = Manifest::from_str(&config).unwrap();
+        let staging = manifest.get_environment(Some("staging")).unwrap().unwrap();
+        let bindings = staging
+            .kv_namespaces
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|namespace| namespace.binding.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bindings, vec!["CACHE", "FLAGS"]);
+    }
 
     #[test]
     fn it_messages_about_env() {
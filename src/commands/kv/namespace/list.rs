@@ -1,18 +1,96 @@
 use crate::http;
 use crate::kv::namespace::list;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::Target;
+use crate::settings::toml::{Manifest, Target};
 
 use anyhow::Result;
+use cloudflare::endpoints::workerskv::WorkersKvNamespace;
+use globset::{Glob, GlobMatcher};
+use serde::Serialize;
+
+use super::{bound_as, configured_namespaces};
+
+/// A namespace as `wrangler kv:namespace list` prints it, with the binding of the
+/// configuration file it's bound to when there is one.
+#[derive(Debug, PartialEq, Serialize)]
+struct ListedNamespace {
+    id: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binding: Option<String>,
+}
+
+pub fn run(
+    manifest: &Manifest,
+    env: Option<&str>,
+    target: &Target,
+    user: &GlobalUser,
+    title: Option<&str>,
+) -> Result<()> {
+    let title = title.map(title_matcher).transpose()?;
+    let configured = configured_namespaces(manifest, env)?;
 
-pub fn run(target: &Target, user: &GlobalUser) -> Result<()> {
     let client = http::cf_v4_client(user)?;
     let result = list(&client, target);
     match result {
         Ok(namespaces) => {
+            let namespaces = namespaces
+                .into_iter()
+                .filter(|namespace| {
+                    title
+                        .as_ref()
+                        .map_or(true, |t| t.is_match(&namespace.title))
+                })
+                .map(|namespace| {
+                    let binding = bound_as(configured, &namespace.id).map(str::to_owned);
+                    listed(namespace, binding)
+                })
+                .collect::<Vec<_>>();
             println!("{}", serde_json::to_string(&namespaces)?);
         }
         Err(e) => anyhow::bail!(e),
     }
     Ok(())
 }
+
+fn listed(namespace: WorkersKvNamespace, binding: Option<String>) -> ListedNamespace {
+    ListedNamespace {
+        id: namespace.id,
+        title: namespace.title,
+        binding,
+    }
+}
+
+fn title_matcher(title: &str) -> Result<GlobMatcher> {
+    match Glob::new(title) {
+        Ok(glob) => Ok(glob.compile_matcher()),
+        Err(e) => anyhow::bail!("--title {} isn't a valid glob: {}", title, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_filters_titles_with_globs() {
+        let matcher = title_matcher("my-worker-*").unwrap();
+        assert!(matcher.is_match("my-worker-CACHE"));
+        assert!(matcher.is_match("my-worker-CACHE_preview"));
+        assert!(!matcher.is_match("other-worker-CACHE"));
+        assert!(title_matcher("my-worker-[").is_err());
+    }
+
+    #[test]
+    fn it_leaves_out_unknown_bindings() {
+        let namespace = ListedNamespace {
+            id: "abc".to_owned(),
+            title: "my-worker-CACHE".to_owned(),
+            binding: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&namespace).unwrap(),
+            serde_json::json!({ "id": "abc", "title": "my-worker-CACHE" })
+        );
+    }
+}
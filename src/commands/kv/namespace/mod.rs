@@ -1,7 +1,56 @@
+use anyhow::Result;
+
+use crate::settings::toml::{ConfigKvNamespace, Manifest};
+
+mod copy;
 mod create;
 mod delete;
 mod list;
+mod rename;
 
+pub use copy::run as copy;
+pub use copy::NamespaceCopy;
 pub use create::run as create;
 pub use delete::run as delete;
 pub use list::run as list;
+pub use rename::run as rename;
+
+/// The namespaces bound in the configuration file for an environment, as kv_namespaces of an
+/// environment aren't inherited from the top level.
+fn configured_namespaces<'a>(
+    manifest: &'a Manifest,
+    env: Option<&str>,
+) -> Result<Option<&'a Vec<ConfigKvNamespace>>> {
+    Ok(match manifest.get_environment(env)? {
+        Some(environment) => environment.kv_namespaces.as_ref(),
+        None => manifest.kv_namespaces.as_ref(),
+    })
+}
+
+/// The binding a namespace is bound to in the configuration file, as the namespace or its preview.
+fn bound_as<'a>(namespaces: Option<&'a Vec<ConfigKvNamespace>>, id: &str) -> Option<&'a str> {
+    namespaces?
+        .iter()
+        .find(|namespace| {
+            namespace.id.as_deref() == Some(id) || namespace.preview_id.as_deref() == Some(id)
+        })
+        .map(|namespace| namespace.binding.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_binding_of_a_namespace_or_its_preview() {
+        let namespaces = vec![ConfigKvNamespace {
+            binding: "CACHE".to_owned(),
+            id: Some("production".to_owned()),
+            preview_id: Some("preview".to_owned()),
+        }];
+        assert_eq!(bound_as(Some(&namespaces), "production"), Some("CACHE"));
+        assert_eq!(bound_as(Some(&namespaces), "preview"), Some("CACHE"));
+        assert_eq!(bound_as(Some(&namespaces), "other"), None);
+        assert_eq!(bound_as(None, "production"), None);
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::Result;
+
+use super::{bound_as, configured_namespaces};
+use crate::commands::kv;
+use crate::http;
+use crate::kv::namespace::rename;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Manifest;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+pub fn run(
+    manifest: &Manifest,
+    env: Option<&str>,
+    user: &GlobalUser,
+    id: &str,
+    title: &str,
+) -> Result<()> {
+    let account_id = manifest.get_account_id(env)?;
+    if title.trim().is_empty() {
+        anyhow::bail!("The new title of the namespace can't be empty")
+    }
+
+    StdOut::working(&format!("Renaming namespace {} to \"{}\"", id, title));
+    let client = http::cf_v4_client(user)?;
+    if let Err(e) = rename(&client, &account_id, id, title) {
+        anyhow::bail!("{}", kv::format_error(e))
+    }
+    StdOut::success("Success!");
+
+    if let Some(binding) = bound_as(configured_namespaces(manifest, env)?, id) {
+        StdOut::info(&format!(
+            "The binding {} refers to this namespace by its ID, so your configuration file stays the same",
+            styles::highlight(binding)
+        ));
+    }
+    Ok(())
+}
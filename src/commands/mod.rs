@@ -1,7 +1,11 @@
 use std::process::Command;
 
 pub mod config;
+pub mod delete;
+pub mod deployments;
 pub mod dev;
+pub mod diff;
+pub mod dispatch_namespace;
 pub mod generate;
 pub mod init;
 pub mod kv;
@@ -13,7 +17,10 @@ pub mod route;
 pub mod secret;
 pub mod subdomain;
 pub mod tail;
+pub mod triggers;
+pub mod versions;
 pub mod whoami;
+pub mod workspace;
 
 pub use self::config::global_config;
 pub use self::preview::run as preview;
@@ -1,4 +1,5 @@
 use std::env;
+use std::io::{self, Write};
 use std::path::Path;
 
 use anyhow::Result;
@@ -6,38 +7,63 @@ use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
 
 use crate::build::build_target;
+use crate::commands::diff::Diff;
+use crate::commands::versions;
 use crate::deploy::{self, DeploymentSet};
 use crate::http::{self, Feature};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::Target;
+use crate::settings::toml::{Hooks, Target};
 use crate::sites;
 use crate::terminal::emoji;
-use crate::terminal::message::{Message, Output, StdErr, StdOut};
-use crate::upload;
+use crate::terminal::message::{Message, Output, StdErr};
+use crate::upload::{self, SizeReport};
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct PublishOutput {
     pub success: bool,
     pub name: String,
+    /// the etag of the uploaded script, or the id of the uploaded version
+    pub version: Option<String>,
     pub urls: Vec<String>,
+    pub routes: Vec<String>,
+    pub workers_dev: Option<String>,
     pub schedules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queues: Vec<String>,
+    pub size: Option<SizeReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch_namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
 }
 
+/// Builds and publishes the Worker, returning what was published for `--output json`.
 pub fn publish(
     user: &GlobalUser,
     target: &mut Target,
     deployments: DeploymentSet,
     out: Output,
-) -> Result<()> {
+) -> Result<PublishOutput> {
     validate_target_required_fields_present(target)?;
-
-    let run_deploy = |target: &Target| match deploy::deploy(user, &deployments) {
-        Ok(results) => {
-            build_output_message(results, target.name.clone(), out);
-            Ok(())
-        }
-        Err(e) => Err(e),
+    check_compatibility_date(target)?;
+    validate_services(user, target)?;
+    run_pre_publish(target, out)?;
+    fetch_migration_tag(user, target)?;
+
+    let run_deploy = |target: &Target, version: Option<String>, size: &SizeReport| {
+        // the scripts of a dispatch namespace are only reached through a dispatcher
+        let results = if target.dispatch_namespace.is_some() {
+            deploy::DeployResults::default()
+        } else {
+            deploy::deploy(user, &deployments)?
+        };
+        let hook_env = hook_env(target, Some(&results), version.as_deref());
+        let output = build_output_message(results, target, version, size);
+        run_post_publish(target, hook_env, out)?;
+        Ok(output)
     };
 
     // Build the script before uploading and log build result
@@ -54,8 +80,9 @@ pub fn publish(
     if let Some(build_config) = &target.build {
         build_config.verify_upload_dir()?;
     }
+    let size = check_size(target)?;
 
-    if let Some(site_config) = &target.site {
+    let output = if let Some(site_config) = &target.site {
         let path = &site_config.bucket.clone();
         validate_bucket_location(path)?;
 
@@ -90,9 +117,9 @@ pub fn publish(
         let upload_client = http::featured_legacy_auth_client(user, Feature::Sites);
 
         // Next, upload and deploy the worker with the updated asset_manifest
-        upload::script(&upload_client, target, Some(asset_manifest))?;
+        let version = upload::script(&upload_client, target, Some(asset_manifest))?;
 
-        run_deploy(target)?;
+        let output = run_deploy(target, version, &size)?;
 
         // Finally, remove any stale files
         if !to_delete.is_empty() {
@@ -120,35 +147,348 @@ pub fn publish(
                 pb.finish_with_message("Done deleting");
             }
         }
+        output
     } else {
         let upload_client = http::legacy_auth_client(user);
 
-        upload::script(&upload_client, target, None)?;
-        run_deploy(target)?;
+        let version = upload::script(&upload_client, target, None)?;
+        run_deploy(target, version, &size)?
+    };
+
+    Ok(output)
+}
+
+/// Uploads a new version of the Worker and sends a percentage of its traffic to it,
+/// leaving the rest with the version that is live.
+pub fn gradual(
+    user: &GlobalUser,
+    target: &mut Target,
+    deployments: DeploymentSet,
+    percentage: f64,
+    out: Output,
+) -> Result<PublishOutput> {
+    validate_target_required_fields_present(target)?;
+    check_compatibility_date(target)?;
+    if target.site.is_some() {
+        anyhow::bail!("--percentage can't be used with Workers Sites, as every version shares the files of the site");
+    }
+    validate_services(user, target)?;
+    run_pre_publish(target, out)?;
+    fetch_migration_tag(user, target)?;
+
+    let msg = build_target(target)?;
+    StdErr::success(&msg);
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+    let size = check_size(target)?;
+
+    let upload_client = http::legacy_auth_client(user);
+    let version_id = upload::version(&upload_client, target)?;
+    versions::split(user, target, &version_id, percentage, out)?;
+
+    let results = deploy::deploy(user, &deployments)?;
+    let hook_env = hook_env(target, Some(&results), Some(&version_id));
+    let output = build_output_message(results, target, Some(version_id), &size);
+    run_post_publish(target, hook_env, out)?;
+    Ok(output)
+}
+
+/// Builds the Worker and assembles its upload, then prints how publishing it would
+/// change the deployed Worker, without uploading anything. With JSON output, the diff
+/// is returned to be printed instead.
+pub fn dry_run(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &DeploymentSet,
+    out: Output,
+) -> Result<Diff> {
+    let diff = diff(user, target, deployments)?;
+    if out == Output::PlainText {
+        diff.print(out);
+    }
+    StdErr::info("Nothing was uploaded, as this was a dry run.");
+    Ok(diff)
+}
+
+/// Builds the Worker and assembles its upload, then compares it with the deployed Worker.
+pub fn diff(user: &GlobalUser, target: &Target, deployments: &DeploymentSet) -> Result<Diff> {
+    validate_target_required_fields_present(target)?;
+    check_compatibility_date(target)?;
+
+    let msg = build_target(target)?;
+    StdErr::success(&msg);
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+    check_size(target)?;
+
+    // the site's manifest is only known once its files are uploaded
+    if let Some(site_config) = &target.site {
+        validate_bucket_location(&site_config.bucket)?;
+        StdErr::info("The files of the site would be uploaded, but are not compared.");
     }
+    let mut target = target.clone();
+    fetch_migration_tag(user, &mut target)?;
+    upload::form::build(&target, None, None)?;
 
+    Diff::new(user, &target, deployments)
+}
+
+/// Builds the Worker and reports the size of what it would upload, without uploading it.
+pub fn size_only(target: &Target) -> Result<()> {
+    validate_target_required_fields_present(target)?;
+
+    let msg = build_target(target)?;
+    StdErr::success(&msg);
+    if let Some(build_config) = &target.build {
+        build_config.verify_upload_dir()?;
+    }
+    check_size(target)?;
+    StdErr::success("The built project is within the size limit");
     Ok(())
 }
 
-fn build_output_message(deploy_results: deploy::DeployResults, target_name: String, out: Output) {
-    let deploy::DeployResults { urls, schedules } = deploy_results;
+#[derive(Deserialize)]
+struct ScriptDetailsResponse {
+    result: ScriptDetails,
+}
+
+/// The details of a script in a dispatch namespace, or of a service, whose script is
+/// that of its default environment.
+#[derive(Deserialize)]
+struct ScriptDetails {
+    script: Option<ScriptResult>,
+    default_environment: Option<Box<ScriptDetails>>,
+}
+
+#[derive(Deserialize)]
+struct ScriptResult {
+    migration_tag: Option<String>,
+}
+
+/// Looks up which of the [[migrations]] in wrangler.toml the deployed script has applied,
+/// so only the ones after it are uploaded.
+fn fetch_migration_tag(user: &GlobalUser, target: &mut Target) -> Result<()> {
+    let addr = match &target.dispatch_namespace {
+        Some(_) => format!("{}/{}", upload::scripts_addr(target)?, target.name),
+        None => format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/services/{}",
+            target.account_id.load()?,
+            target.name
+        ),
+    };
+    let migrations = match &mut target.migrations {
+        Some(migrations) if migrations.is_tagged() => migrations,
+        _ => return Ok(()),
+    };
+
+    let response = http::legacy_auth_client(user).get(&addr).send()?;
+    // a script that hasn't been published yet has applied none of them
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        migrations.deployed_tag = None;
+        return Ok(());
+    }
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let ScriptDetails {
+        script,
+        default_environment,
+    } = response.json::<ScriptDetailsResponse>()?.result;
+    migrations.deployed_tag = script
+        .or_else(|| default_environment?.script)
+        .and_then(|script| script.migration_tag);
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ServiceResponse {
+    result: Service,
+}
+
+#[derive(Deserialize)]
+struct Service {
+    #[serde(default)]
+    environments: Vec<ServiceEnvironment>,
+}
+
+#[derive(Deserialize)]
+struct ServiceEnvironment {
+    environment: String,
+}
+
+/// Checks that the workers the service bindings point at exist, as a binding to a
+/// missing one only fails once the script calls it.
+fn validate_services(user: &GlobalUser, target: &Target) -> Result<()> {
+    if target.services.is_empty() {
+        return Ok(());
+    }
+    let client = http::legacy_auth_client(user);
+    for service in target.services.iter() {
+        let addr = format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/services/{}",
+            target.account_id.load()?,
+            service.service
+        );
+        let response = client.get(&addr).send()?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!(
+                "The service binding {} points at {}, which is not a worker of this account",
+                service.binding,
+                service.service
+            )
+        }
+        if !response.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(response.text()?))
+        }
+        let found: ServiceResponse = response.json()?;
+        let environments = found.result.environments;
+        if !environments.is_empty()
+            && !environments
+                .iter()
+                .any(|environment| environment.environment == service.environment())
+        {
+            anyhow::bail!(
+                "The service binding {} points at the {} environment of {}, which it doesn't have",
+                service.binding,
+                service.environment(),
+                service.service
+            )
+        }
+    }
+    Ok(())
+}
+
+/// The environment variables the hooks are run with, describing what is published.
+fn hook_env(
+    target: &Target,
+    results: Option<&deploy::DeployResults>,
+    version: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![("WRANGLER_SCRIPT_NAME", target.name.clone())];
+    if let Some(results) = results {
+        if let Some(url) = results.urls.first() {
+            env.push(("WRANGLER_URL", url.clone()));
+        }
+        env.push(("WRANGLER_URLS", results.urls.join(" ")));
+    }
+    if let Some(version) = version {
+        env.push(("WRANGLER_VERSION", version.to_owned()));
+    }
+    env
+}
+
+fn run_pre_publish(target: &Target, out: Output) -> Result<()> {
+    match target
+        .hooks
+        .as_ref()
+        .and_then(|hooks| hooks.pre_publish.as_ref())
+    {
+        Some(command) => run_hook(command, hook_env(target, None, None), out).map_err(|e| {
+            anyhow::anyhow!(
+                "The pre_publish hook failed, so nothing was published: {}",
+                e
+            )
+        }),
+        None => Ok(()),
+    }
+}
+
+fn run_post_publish(target: &Target, env: Vec<(&str, String)>, out: Output) -> Result<()> {
+    match target
+        .hooks
+        .as_ref()
+        .and_then(|hooks| hooks.post_publish.as_ref())
+    {
+        Some(command) => run_hook(command, env, out).map_err(|e| {
+            anyhow::anyhow!(
+                "{} was published, but the post_publish hook failed: {}",
+                target.name,
+                e
+            )
+        }),
+        None => Ok(()),
+    }
+}
+
+fn run_hook(command: &str, env: Vec<(&str, String)>, out: Output) -> Result<()> {
+    StdErr::working(&format!("Running {}", command));
+    let mut cmd = Hooks::command(command);
+    cmd.envs(env);
+
+    // stdout is kept for the json output, so what the hook prints goes to stderr instead
+    let status = if out == Output::Json {
+        let output = cmd.output()?;
+        io::stderr().write_all(&output.stdout)?;
+        io::stderr().write_all(&output.stderr)?;
+        output.status
+    } else {
+        cmd.status()?
+    };
+
+    match status.code() {
+        _ if status.success() => Ok(()),
+        Some(code) => anyhow::bail!("{} exited with status code {}", command, code),
+        None => anyhow::bail!("{} was stopped", command),
+    }
+}
+
+fn check_size(target: &Target) -> Result<SizeReport> {
+    let report = SizeReport::new(target)?;
+    report.print();
+    report.check()?;
+    Ok(report)
+}
+
+fn build_output_message(
+    deploy_results: deploy::DeployResults,
+    target: &Target,
+    version: Option<String>,
+    size: &SizeReport,
+) -> PublishOutput {
+    let deploy::DeployResults {
+        urls,
+        routes,
+        workers_dev,
+        schedules,
+        queues,
+    } = deploy_results;
 
     let mut msg = "Successfully published your script ".to_owned();
+    if let Some(namespace) = &target.dispatch_namespace {
+        msg.push_str(&format!("to the dispatch namespace {}\n", namespace));
+    }
     if !urls.is_empty() {
         msg.push_str(&format!("to\n {}\n", urls.join("\n ")));
     }
     if !schedules.is_empty() {
         msg.push_str(&format!("with this schedule\n {}\n", schedules.join("\n ")));
     }
+    if !queues.is_empty() {
+        msg.push_str(&format!("consuming\n {}\n", queues.join("\n ")));
+    }
+    if let Some(tag) = &target.annotations.tag {
+        msg.push_str(&format!("tagged {}\n", tag));
+    }
+    if let Some(message) = &target.annotations.message {
+        msg.push_str(&format!("\"{}\"\n", message));
+    }
 
     StdErr::success(&msg);
-    if out == Output::Json {
-        StdOut::as_json(&PublishOutput {
-            success: true,
-            name: target_name,
-            urls,
-            schedules,
-        });
+    PublishOutput {
+        success: true,
+        name: target.name.clone(),
+        version,
+        urls,
+        routes,
+        workers_dev,
+        schedules,
+        queues,
+        size: Some(size.clone()),
+        dispatch_namespace: target.dispatch_namespace.clone(),
+        message: target.annotations.message.clone(),
+        tag: target.annotations.tag.clone(),
     }
 }
 
@@ -181,6 +521,13 @@ pub fn validate_bucket_location(bucket: &Path) -> Result<()> {
     Ok(())
 }
 
+fn check_compatibility_date(target: &Target) -> Result<()> {
+    if let Some(warning) = target.compatibility_date_warning()? {
+        StdErr::warn(&warning);
+    }
+    Ok(())
+}
+
 fn validate_target_required_fields_present(target: &Target) -> Result<()> {
     let mut missing_fields = Vec::new();
 
@@ -2,9 +2,12 @@ use anyhow::Result;
 use cloudflare::endpoints::workers::{DeleteRoute, ListRoutes};
 use cloudflare::framework::apiclient::ApiClient;
 
+use crate::deploy;
 use crate::http;
 use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Route;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
 
 pub fn list(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
     let client = http::cf_v4_client(user)?;
@@ -22,6 +25,86 @@ pub fn list(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
     Ok(())
 }
 
+pub fn add(
+    zone_identifier: &str,
+    user: &GlobalUser,
+    pattern: &str,
+    script: &str,
+    force: bool,
+) -> Result<()> {
+    let route = Route {
+        id: None,
+        script: Some(script.to_owned()),
+        pattern: pattern.to_owned(),
+    };
+    let existing = deploy::fetch_routes(user, zone_identifier)?
+        .into_iter()
+        .find(|existing| existing.pattern == route.pattern);
+
+    match existing {
+        None => {
+            let created = deploy::create_route(user, zone_identifier, &route)?;
+            StdOut::success(&format!(
+                "{} now goes to {} (route id {})",
+                styles::highlight(pattern),
+                script,
+                created.id.unwrap_or_default()
+            ));
+        }
+        Some(existing) if existing.script == route.script => {
+            StdOut::info(&format!(
+                "{} already goes to {}",
+                styles::highlight(pattern),
+                script
+            ));
+        }
+        Some(existing) => {
+            let owner = existing
+                .script
+                .clone()
+                .unwrap_or_else(|| "no worker".to_owned());
+            if !force {
+                anyhow::bail!(
+                    "{} is already routed to {}, pass --force to route it to {} instead",
+                    pattern,
+                    owner,
+                    script
+                )
+            }
+            let id = existing
+                .id
+                .ok_or_else(|| anyhow::anyhow!("The route {} has no id", pattern))?;
+            update(zone_identifier, user, &id, &route)?;
+            StdOut::success(&format!(
+                "{} now goes to {} instead of {}",
+                styles::highlight(pattern),
+                script,
+                owner
+            ));
+        }
+    }
+    Ok(())
+}
+
+// the cloudflare crate has no endpoint for updating a route
+fn update(zone_identifier: &str, user: &GlobalUser, route_id: &str, route: &Route) -> Result<()> {
+    let route_addr = format!(
+        "https://api.cloudflare.com/client/v4/zones/{}/workers/routes/{}",
+        zone_identifier, route_id
+    );
+    let client = http::legacy_auth_client(user);
+    let response = http::retry::send("Updating the route", || {
+        Ok(client.put(&route_addr).json(&serde_json::json!({
+            "pattern": route.pattern,
+            "script": route.script,
+        })))
+    })?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    Ok(())
+}
+
 pub fn delete(zone_identifier: &str, user: &GlobalUser, route_id: &str) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
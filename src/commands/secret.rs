@@ -2,6 +2,10 @@ use cloudflare::endpoints::workers::{CreateSecret, CreateSecretParams, DeleteSec
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+
 use anyhow::Result;
 
 use crate::http;
@@ -28,6 +32,16 @@ fn secret_errors(error_code: u16) -> &'static str {
     }
 }
 
+// missing_script tells whether an API error is the one for a worker that doesn't exist yet.
+fn missing_script(e: &ApiFailure) -> bool {
+    match e {
+        ApiFailure::Error(_, api_errors) => {
+            api_errors.errors.iter().any(|error| error.code == 10007)
+        }
+        ApiFailure::Invalid(_) => false,
+    }
+}
+
 // upload_draft_worker will attempt to upload a "draft" version of a worker script if it does not
 // already exist in the API (API error code 10007 is returned). The function returns None if this draft
 // script was uploaded, or else returns Some (with a Result type so we can return a potential script upload error
@@ -37,26 +51,40 @@ pub fn upload_draft_worker(
     user: &GlobalUser,
     target: &Target,
 ) -> Option<Result<()>> {
-    match e {
-        ApiFailure::Error(_, api_errors) => {
-            let error = &api_errors.errors[0];
-            if error.code == 10007 {
-                StdOut::working(&format!("Worker {} doesn't exist in the API yet. Creating a draft Worker so we can create new secret.", target.name));
-                let upload_client = http::legacy_auth_client(user);
-                Some(upload::script(&upload_client, target, None))
-            } else {
-                None
-            }
-        }
-        ApiFailure::Invalid(_) => None,
+    if missing_script(e) {
+        StdOut::working(&format!("Worker {} doesn't exist in the API yet. Creating a draft Worker so we can create new secret.", target.name));
+        let upload_client = http::legacy_auth_client(user);
+        Some(upload::script(&upload_client, target, None).map(|_| ()))
+    } else {
+        None
     }
 }
 
-pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
-    let secret_value = interactive::get_user_input_multi_line(&format!(
-        "Enter the secret text you'd like assigned to the variable {} on the script named {}:",
-        name, target.name
-    ));
+// create_secret reads the value of a secret from a file as it is, from stdin as it's piped
+// in, or else by prompting for it. The newline at the end of a file or of piped input is
+// only removed with `trim_newline`.
+pub fn create_secret(
+    name: &str,
+    from_file: Option<&Path>,
+    trim_newline: bool,
+    user: &GlobalUser,
+    target: &Target,
+) -> Result<()> {
+    let read = match from_file {
+        Some(path) => Some(
+            fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?,
+        ),
+        None => interactive::read_piped_input()?,
+    };
+    let secret_value = match read {
+        Some(value) if trim_newline => interactive::strip_final_newline(value),
+        Some(value) => value,
+        None => interactive::get_user_input_multi_line(&format!(
+            "Enter the secret text you'd like assigned to the variable {} on the script named {}:",
+            name, target.name
+        )),
+    };
 
     if secret_value.is_empty() {
         anyhow::bail!("Your secret cannot be empty.")
@@ -68,10 +96,24 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     ));
 
     let client = http::cf_v4_client(user)?;
+    put_secret(&client, user, target, name, secret_value)?;
+    StdOut::success(&format!("Success! Uploaded secret {}.", name));
 
+    Ok(())
+}
+
+// put_secret creates or updates a secret, uploading a draft of the worker first if it
+// doesn't exist yet.
+fn put_secret(
+    client: &impl ApiClient,
+    user: &GlobalUser,
+    target: &Target,
+    name: &str,
+    value: String,
+) -> Result<()> {
     let params = CreateSecretParams {
         name: name.to_string(),
-        text: secret_value,
+        text: value,
         secret_type: "secret_text".to_string(),
     };
 
@@ -82,7 +124,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     });
 
     match response {
-        Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
+        Ok(_) => Ok(()),
         Err(e) => match upload_draft_worker(&e, user, target) {
             None => anyhow::bail!(format_error(e)),
             Some(draft_upload_response) => match draft_upload_response {
@@ -94,7 +136,7 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
                     });
 
                     match retry_response {
-                        Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
+                        Ok(_) => Ok(()),
                         Err(e) => anyhow::bail!(format_error(e)),
                     }
                 }
@@ -102,7 +144,166 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
             },
         },
     }
+}
+
+/// Reads secrets from a .env file, or from a JSON object of names to values.
+pub fn read_secrets(path: &Path, json: bool) -> Result<Vec<(String, String)>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    if json {
+        let secrets: BTreeMap<String, String> = serde_json::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!(
+                "{} should be a JSON object of secret names to their values, like {{\"API_KEY\": \"...\"}}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        Ok(secrets.into_iter().collect())
+    } else {
+        parse_env(&contents)
+    }
+}
+
+/// Parses the KEY=value lines of a .env file, skipping comments and blank lines. Values can
+/// be quoted, where double quoted values can have escaped newlines, quotes and backslashes.
+/// A name that is set more than once has the last of its values.
+fn parse_env(contents: &str) -> Result<Vec<(String, String)>> {
+    let mut secrets: Vec<(String, String)> = vec![];
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = match line.find('=') {
+            Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+            None => anyhow::bail!("line {} should be like NAME=value: {}", number + 1, line),
+        };
+        if name.is_empty() {
+            anyhow::bail!("line {} has no secret name: {}", number + 1, line)
+        }
+        let value = parse_env_value(value)
+            .map_err(|e| anyhow::anyhow!("line {} {}: {}", number + 1, e, line))?;
+
+        match secrets.iter_mut().find(|(existing, _)| existing == name) {
+            Some(secret) => secret.1 = value,
+            None => secrets.push((name.to_owned(), value)),
+        }
+    }
+    Ok(secrets)
+}
+
+// parse_env_value reads the value of a .env line, which ends at a comment unless it's quoted.
+fn parse_env_value(value: &str) -> Result<String, &'static str> {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some('"') => {
+            let mut unescaped = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => return after_quotes(chars.as_str()).map(|_| unescaped),
+                    '\\' => match chars.next() {
+                        Some('n') => unescaped.push('\n'),
+                        Some(c @ '"') | Some(c @ '\\') => unescaped.push(c),
+                        Some(c) => {
+                            unescaped.push('\\');
+                            unescaped.push(c);
+                        }
+                        None => break,
+                    },
+                    c => unescaped.push(c),
+                }
+            }
+            Err("has a double quote that isn't closed")
+        }
+        Some('\'') => match chars.as_str().find('\'') {
+            Some(end) => {
+                let quoted = chars.as_str();
+                after_quotes(&quoted[end + 1..]).map(|_| quoted[..end].to_owned())
+            }
+            None => Err("has a single quote that isn't closed"),
+        },
+        // unquoted values end at a comment
+        _ => match value.find(" #") {
+            Some(index) => Ok(value[..index].trim_end().to_owned()),
+            None => Ok(value.to_owned()),
+        },
+    }
+}
+
+// after_quotes checks that a quoted value is only followed by a comment, if anything.
+fn after_quotes(rest: &str) -> Result<(), &'static str> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err("has more after the closing quote of its value")
+    }
+}
+
+/// Uploads many secrets at once, carrying on past the secrets that fail so that every
+/// secret that can be uploaded is.
+pub fn bulk_secrets(
+    secrets: Vec<(String, String)>,
+    user: &GlobalUser,
+    target: &Target,
+) -> Result<()> {
+    if secrets.is_empty() {
+        anyhow::bail!("There are no secrets to upload")
+    }
+    if let Some((name, _)) = secrets.iter().find(|(_, value)| value.is_empty()) {
+        anyhow::bail!("The secret {} is empty, and secrets cannot be empty.", name)
+    }
+
+    let client = http::cf_v4_client(user)?;
+    // a worker that doesn't exist yet has no secrets, and is created with the first one
+    let existing = match client.request(&ListSecrets {
+        account_identifier: target.account_id.load()?,
+        script_name: &target.name,
+    }) {
+        Ok(success) => success
+            .result
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect::<HashSet<_>>(),
+        Err(e) if missing_script(&e) => HashSet::new(),
+        Err(e) => anyhow::bail!(
+            "Failed to list the secrets of {}: {}",
+            target.name,
+            format_error(e)
+        ),
+    };
+
+    StdOut::working(&format!(
+        "Uploading {} secrets to the script named {}",
+        secrets.len(),
+        target.name
+    ));
+    let (mut created, mut updated, mut failed) = (vec![], vec![], vec![]);
+    for (name, value) in secrets {
+        match put_secret(&client, user, target, &name, value) {
+            Ok(_) if existing.contains(&name) => updated.push(name),
+            Ok(_) => created.push(name),
+            Err(e) => {
+                StdOut::user_error(&format!("Failed to upload {}: {}", name, e));
+                failed.push(name);
+            }
+        }
+    }
 
+    for (label, names) in &[("Created", &created), ("Updated", &updated)] {
+        if !names.is_empty() {
+            StdOut::success(&format!("{} {}", label, names.join(", ")));
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Failed to upload {} of {} secrets: {}",
+            failed.len(),
+            created.len() + updated.len() + failed.len(),
+            failed.join(", ")
+        )
+    }
     Ok(())
 }
 
@@ -158,3 +359,239 @@ pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<()> {
 
     Ok(())
 }
+
+fn secret_names(client: &impl ApiClient, target: &Target) -> Result<BTreeSet<String>> {
+    let response = client.request(&ListSecrets {
+        account_identifier: target.account_id.load()?,
+        script_name: &target.name,
+    });
+
+    match response {
+        Ok(success) => Ok(success
+            .result
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect()),
+        // a worker that isn't published yet has no secrets
+        Err(e) if missing_script(&e) => Ok(BTreeSet::new()),
+        Err(e) => anyhow::bail!(
+            "Failed to list the secrets of {}: {}",
+            target.name,
+            format_error(e)
+        ),
+    }
+}
+
+// missing_secrets returns the names of the secrets only `from` has, and then only `to` has.
+fn missing_secrets(from: &BTreeSet<String>, to: &BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+    (
+        from.difference(to).cloned().collect(),
+        to.difference(from).cloned().collect(),
+    )
+}
+
+fn compare_secrets(
+    client: &impl ApiClient,
+    from: &Target,
+    to: &Target,
+) -> Result<(Vec<String>, Vec<String>)> {
+    if from.name == to.name && from.account_id.load()? == to.account_id.load()? {
+        anyhow::bail!(
+            "--from and --to are both the script named {}, pass two different environments",
+            from.name
+        )
+    }
+    Ok(missing_secrets(
+        &secret_names(client, from)?,
+        &secret_names(client, to)?,
+    ))
+}
+
+// diff_secrets only ever shows the names of secrets, as their values can't be read back. It
+// fails when the secrets differ, so it can be used to check for drift.
+pub fn diff_secrets(user: &GlobalUser, from: &Target, to: &Target) -> Result<()> {
+    let client = http::cf_v4_client(user)?;
+    let (only_from, only_to) = compare_secrets(&client, from, to)?;
+
+    if only_from.is_empty() && only_to.is_empty() {
+        StdOut::success(&format!(
+            "{} and {} have the same secrets.",
+            from.name, to.name
+        ));
+        return Ok(());
+    }
+    for (name, missing) in &[(&from.name, &only_from), (&to.name, &only_to)] {
+        if !missing.is_empty() {
+            StdOut::info(&format!("Only {} has {}", name, missing.join(", ")));
+        }
+    }
+    anyhow::bail!("{} and {} have different secrets", from.name, to.name)
+}
+
+// sync_secrets prompts for every secret `to` is missing, as the values of secrets can't be
+// copied over from `from`. Secrets only `to` has are left alone.
+pub fn sync_secrets(user: &GlobalUser, from: &Target, to: &Target) -> Result<()> {
+    let client = http::cf_v4_client(user)?;
+    let (missing, _) = compare_secrets(&client, from, to)?;
+    if missing.is_empty() {
+        StdOut::success(&format!(
+            "{} already has all the secrets of {}.",
+            to.name, from.name
+        ));
+        return Ok(());
+    }
+
+    StdOut::info(&format!(
+        "{} is missing {} secrets of {}: {}",
+        to.name,
+        missing.len(),
+        from.name,
+        missing.join(", ")
+    ));
+    for name in missing.iter() {
+        let value = interactive::get_user_input_multi_line(&format!(
+            "Enter the secret text you'd like assigned to the variable {} on the script named {}, or leave it empty to skip it:",
+            name, to.name
+        ));
+        if value.is_empty() {
+            StdOut::info(&format!("Skipped secret {}.", name));
+            continue;
+        }
+        put_secret(&client, user, to, name, value)?;
+        StdOut::success(&format!("Success! Uploaded secret {}.", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_env_files() {
+        let secrets = parse_env(
+            r#"
+# the database
+DATABASE_URL=postgres://localhost/db
+export API_KEY = "abc=123"
+GREETING='hello # world'
+PRIVATE_KEY="-----BEGIN KEY-----\nabc\n-----END KEY-----"
+QUOTED="say \"hi\""
+TOKEN=xyz # rotated monthly
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            secrets,
+            vec![
+                (
+                    "DATABASE_URL".to_owned(),
+                    "postgres://localhost/db".to_owned()
+                ),
+                ("API_KEY".to_owned(), "abc=123".to_owned()),
+                ("GREETING".to_owned(), "hello # world".to_owned()),
+                (
+                    "PRIVATE_KEY".to_owned(),
+                    "-----BEGIN KEY-----\nabc\n-----END KEY-----".to_owned()
+                ),
+                ("QUOTED".to_owned(), "say \"hi\"".to_owned()),
+                ("TOKEN".to_owned(), "xyz".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_the_secrets_missing_from_each_environment() {
+        let staging = vec!["API_KEY", "DATABASE_URL", "DEBUG_TOKEN"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        let production = vec!["API_KEY", "DATABASE_URL", "SENTRY_DSN"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        assert_eq!(
+            missing_secrets(&staging, &production),
+            (
+                vec!["DEBUG_TOKEN".to_owned()],
+                vec!["SENTRY_DSN".to_owned()]
+            )
+        );
+    }
+
+    #[test]
+    fn it_rejects_lines_without_names() {
+        assert!(parse_env("API_KEY").is_err());
+        assert!(parse_env("=abc").is_err());
+    }
+
+    #[test]
+    fn it_unescapes_double_quoted_values() {
+        let secrets = parse_env(
+            r#"
+WINDOWS_PATH="C:\\new\\table"
+ESCAPED_NEWLINE="a\\nb"
+QUOTE_AT_THE_END="say \"hi\""
+OTHER="a\tb"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            secrets,
+            vec![
+                ("WINDOWS_PATH".to_owned(), r"C:\new\table".to_owned()),
+                ("ESCAPED_NEWLINE".to_owned(), r"a\nb".to_owned()),
+                ("QUOTE_AT_THE_END".to_owned(), "say \"hi\"".to_owned()),
+                ("OTHER".to_owned(), r"a\tb".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_strips_comments_after_quoted_values() {
+        let secrets = parse_env(
+            r#"
+API_KEY="abc" # rotated monthly
+GREETING='hello # world' # in single quotes
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            secrets,
+            vec![
+                ("API_KEY".to_owned(), "abc".to_owned()),
+                ("GREETING".to_owned(), "hello # world".to_owned()),
+            ]
+        );
+
+        assert!(parse_env(r#"API_KEY="abc" def"#).is_err());
+        assert!(parse_env(r#"API_KEY="abc"#).is_err());
+        assert!(parse_env("API_KEY='abc").is_err());
+    }
+
+    #[test]
+    fn it_keeps_the_last_value_of_a_name_set_twice() {
+        let secrets = parse_env("API_KEY=old\nTOKEN=xyz\nAPI_KEY=new").unwrap();
+        assert_eq!(
+            secrets,
+            vec![
+                ("API_KEY".to_owned(), "new".to_owned()),
+                ("TOKEN".to_owned(), "xyz".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_only_treats_missing_scripts_as_having_no_secrets() {
+        let failure = |code: u16| {
+            let errors = serde_json::from_str(&format!(
+                r#"{{"errors": [{{"code": {}, "message": "error"}}]}}"#,
+                code
+            ))
+            .unwrap();
+            ApiFailure::Error(reqwest::StatusCode::NOT_FOUND, errors)
+        };
+        assert!(missing_script(&failure(10007)));
+        assert!(!missing_script(&failure(10000)));
+    }
+}
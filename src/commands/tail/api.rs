@@ -1,5 +1,6 @@
 use crate::http;
 use crate::settings::global_user::GlobalUser;
+use crate::terminal::styles;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
@@ -74,6 +75,12 @@ impl Tail {
                     )?);
                     Ok(())
                 }
+                Err(ApiFailure::Error(StatusCode::NOT_FOUND, _)) => {
+                    anyhow::bail!(
+                        "Could not find a Worker named {}, make sure it has been published with `wrangler publish` using the same environment",
+                        styles::bold(&self.script_name)
+                    )
+                }
                 Err(err) => {
                     anyhow::bail!("Failed to create tail: {}", http::format_error(err, None))
                 }
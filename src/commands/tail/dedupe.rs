@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use tokio::time::{Duration, Instant};
+
+/// Collapses identical log lines that repeat within a window of time.
+///
+/// The first line is printed as usual, while repeats are only counted. Once the
+/// window has passed, a single summary line is printed with the number of repeats.
+#[derive(Debug)]
+pub struct Dedupe {
+    pub window: Duration,
+    seen: HashMap<String, (Instant, u64)>,
+}
+
+impl Dedupe {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Checks if a line should be printed, or if it is a repeat.
+    pub fn check(&mut self, line: &str, now: Instant) -> bool {
+        match self.seen.get_mut(line) {
+            Some((first_seen, count)) if now.duration_since(*first_seen) < self.window => {
+                *count += 1;
+                false
+            }
+            _ => {
+                self.seen.insert(line.to_owned(), (now, 1));
+                true
+            }
+        }
+    }
+
+    /// Removes the lines whose window has passed, returning the ones that repeated.
+    pub fn expire(&mut self, now: Instant) -> Vec<(String, u64)> {
+        let window = self.window;
+        let mut repeated = vec![];
+        self.seen.retain(|line, (first_seen, count)| {
+            if now.duration_since(*first_seen) < window {
+                return true;
+            }
+            if *count > 1 {
+                repeated.push((line.to_owned(), *count));
+            }
+            false
+        });
+        repeated.sort();
+        repeated
+    }
+
+    /// The time at which the window of the first line that repeated passes.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.seen
+            .values()
+            .filter(|(_, count)| *count > 1)
+            .map(|(first_seen, _)| *first_seen + self.window)
+            .min()
+    }
+
+    /// Removes every line, returning the ones that repeated.
+    pub fn drain(&mut self) -> Vec<(String, u64)> {
+        let mut repeated = self
+            .seen
+            .drain()
+            .filter(|(_, (_, count))| *count > 1)
+            .map(|(line, (_, count))| (line, count))
+            .collect::<Vec<_>>();
+        repeated.sort();
+        repeated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_collapses_repeats_within_window() {
+        let mut dedupe = Dedupe::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert!(dedupe.check("warning", start));
+        assert!(!dedupe.check("warning", start + Duration::from_secs(1)));
+        assert!(!dedupe.check("warning", start + Duration::from_secs(2)));
+        assert!(dedupe.check("other", start + Duration::from_secs(2)));
+        assert!(dedupe.expire(start + Duration::from_secs(3)).is_empty());
+
+        assert_eq!(
+            dedupe.expire(start + Duration::from_secs(6)),
+            vec![("warning".to_owned(), 3)]
+        );
+        assert!(dedupe.check("warning", start + Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn it_reports_repeats_before_the_window_passes() {
+        let mut dedupe = Dedupe::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert!(dedupe.check("warning", start));
+        assert!(dedupe.check("other", start + Duration::from_secs(1)));
+        assert_eq!(dedupe.next_expiry(), None);
+
+        assert!(!dedupe.check("warning", start + Duration::from_secs(2)));
+        assert_eq!(dedupe.next_expiry(), Some(start + Duration::from_secs(5)));
+        assert_eq!(dedupe.drain(), vec![("warning".to_owned(), 2)]);
+        assert_eq!(dedupe.next_expiry(), None);
+    }
+}
@@ -1,8 +1,9 @@
 use chrono::{Local, TimeZone};
 use console::style;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
 
 /// A unique protocol ID that is passed by the `Sec-WebSocket-Protocol` header.
 ///
@@ -18,7 +19,11 @@ pub const PROTOCOL_ID: &str = "trace-v1";
 pub struct TraceEvent {
     #[serde(alias = "eventTimestamp")]
     pub timestamp: i64,
+    #[serde(default, rename = "scriptName")]
+    pub script_name: Option<String>,
     pub outcome: String,
+    #[serde(default, rename = "cpuTime")]
+    pub cpu_time: Option<f64>,
     pub logs: Vec<LogItem>,
     pub exceptions: Vec<ExceptionItem>,
     pub event: EventItem,
@@ -33,6 +38,21 @@ pub struct TraceEvent {
 pub struct EventItem {
     pub request: Option<RequestItem>,
     pub cron: Option<String>,
+    #[serde(default, rename = "scheduledTime")]
+    pub scheduled_time: Option<i64>,
+}
+
+impl EventItem {
+    /// The type of event, either "fetch", "scheduled" or "unknown".
+    pub fn event_type(&self) -> &'static str {
+        if self.request.is_some() {
+            "fetch"
+        } else if self.cron.is_some() {
+            "scheduled"
+        } else {
+            "unknown"
+        }
+    }
 }
 
 /// A request item.
@@ -64,6 +84,132 @@ pub struct ExceptionItem {
     // TODO(soon): we really need to implement stacktraces.
 }
 
+/// A field of a `TraceEvent`, which can be selected with `wrangler tail --fields`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Timestamp,
+    Script,
+    Status,
+    Event,
+    Method,
+    Url,
+    Colo,
+    CpuTime,
+    Logs,
+    Exceptions,
+}
+
+/// The names of each `Field`.
+pub const FIELDS: &[&str] = &[
+    "timestamp",
+    "script",
+    "status",
+    "event",
+    "method",
+    "url",
+    "colo",
+    "cpu",
+    "logs",
+    "exceptions",
+];
+
+impl Field {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Timestamp => "timestamp",
+            Field::Script => "script",
+            Field::Status => "status",
+            Field::Event => "event",
+            Field::Method => "method",
+            Field::Url => "url",
+            Field::Colo => "colo",
+            Field::CpuTime => "cpu",
+            Field::Logs => "logs",
+            Field::Exceptions => "exceptions",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+    fn from_str(string: &str) -> anyhow::Result<Self> {
+        match string {
+            "timestamp" => Ok(Field::Timestamp),
+            "script" => Ok(Field::Script),
+            "status" => Ok(Field::Status),
+            "event" => Ok(Field::Event),
+            "method" => Ok(Field::Method),
+            "url" => Ok(Field::Url),
+            "colo" => Ok(Field::Colo),
+            "cpu" => Ok(Field::CpuTime),
+            "logs" => Ok(Field::Logs),
+            "exceptions" => Ok(Field::Exceptions),
+            _ => anyhow::bail!(
+                "unknown field, expected one of {}: {}",
+                FIELDS.join(", "),
+                string
+            ),
+        }
+    }
+}
+
+impl TraceEvent {
+    /// Gets the value of a field, or `null` if it is not present.
+    pub fn field(&self, field: Field) -> Value {
+        let request = self.event.request.as_ref();
+        let value = match field {
+            Field::Timestamp => Some(Value::from(self.timestamp)),
+            Field::Script => self.script_name.clone().map(Value::from),
+            Field::Status => Some(Value::from(self.outcome.clone())),
+            Field::Event => Some(Value::from(self.event.event_type())),
+            Field::Method => request.map(|request| Value::from(request.method.clone())),
+            Field::Url => request.map(|request| Value::from(request.url.clone())),
+            Field::Colo => request
+                .and_then(|request| request.cf.as_ref())
+                .map(|cf| Value::from(cf.colo.clone())),
+            Field::CpuTime => self.cpu_time.map(Value::from),
+            Field::Logs => serde_json::to_value(&self.logs).ok(),
+            Field::Exceptions => serde_json::to_value(&self.exceptions).ok(),
+        };
+        value.unwrap_or(Value::Null)
+    }
+
+    /// Creates a JSON object with only the selected fields.
+    pub fn project(&self, fields: &[Field]) -> Value {
+        let mut object = Map::new();
+        for field in fields {
+            object.insert(field.name().to_owned(), self.field(*field));
+        }
+        Value::Object(object)
+    }
+
+    /// Formats only the selected fields, with logs and exceptions on their own lines.
+    pub fn display_fields(&self, fields: &[Field]) -> String {
+        let mut line = vec![];
+        let mut details = vec![];
+        for field in fields {
+            match field {
+                Field::Logs => {
+                    for log in self.logs.iter() {
+                        details.push(format!("\n {} {}", style("|").dim(), log));
+                    }
+                }
+                Field::Exceptions => {
+                    for err in self.exceptions.iter() {
+                        details.push(format!("\n {} {}", style("!").dim(), err));
+                    }
+                }
+                field => match self.field(*field) {
+                    Value::Null => line.push("-".to_owned()),
+                    Value::String(value) => line.push(value),
+                    value => line.push(value.to_string()),
+                },
+            }
+        }
+        format!("{}{}", line.join(" "), details.join(""))
+    }
+}
+
 impl Display for TraceEvent {
     fn fmt(&self, f: &mut Formatter) -> Result {
         let timestamp = style(
@@ -98,10 +244,36 @@ impl Display for TraceEvent {
             }
             _ => match self.event.cron.clone() {
                 // TODO(soon): add colo to scheduled event.
-                Some(cron) => write!(f, "[{}] [?] [{}] {}", timestamp, outcome, cron),
+                Some(cron) => {
+                    write!(
+                        f,
+                        "[{}] [?] [{}] {} {}",
+                        timestamp,
+                        outcome,
+                        style("Scheduled").dim(),
+                        style(cron).bold()
+                    )?;
+                    match self.event.scheduled_time {
+                        Some(scheduled_time) => write!(
+                            f,
+                            " {}",
+                            style(format!(
+                                "(for {})",
+                                Local
+                                    .timestamp_millis(scheduled_time)
+                                    .format("%Y-%m-%d %H:%M:%S")
+                            ))
+                            .dim()
+                        ),
+                        None => Ok(()),
+                    }
+                }
                 _ => write!(f, "[{}] [?] [{}] <unknown event>", timestamp, outcome),
             },
         }?;
+        if let Some(cpu_time) = self.cpu_time {
+            write!(f, " {}", style(format!("({}ms CPU)", cpu_time)).dim())?;
+        }
         for log in self.logs.iter() {
             let prefix = style("|").dim();
             write!(f, "\n {} {}", prefix, log)?;
@@ -160,3 +332,66 @@ impl Display for ExceptionItem {
         write!(f, "[{}] {}", name, message)
     }
 }
+
+/// Builds a `TraceEvent` for tests, from the fields that differ from an empty event.
+#[cfg(test)]
+pub fn test_event(fields: Value) -> TraceEvent {
+    let mut event = serde_json::json!({
+        "outcome": "ok",
+        "eventTimestamp": 0,
+        "logs": [],
+        "exceptions": [],
+        "event": {},
+    });
+    if let (Value::Object(event), Value::Object(fields)) = (&mut event, fields) {
+        event.extend(fields);
+    }
+    serde_json::from_value(event).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_execution_metadata() {
+        let event = test_event(serde_json::json!({
+            "scriptName": "my-worker",
+            "cpuTime": 1.5,
+            "event": { "cron": "*/5 * * * *", "scheduledTime": 1000 },
+        }));
+        assert_eq!(event.script_name.as_deref(), Some("my-worker"));
+        assert_eq!(event.cpu_time, Some(1.5));
+        assert_eq!(event.event.event_type(), "scheduled");
+        assert_eq!(event.event.scheduled_time, Some(1000));
+    }
+
+    #[test]
+    fn it_parses_without_optional_metadata() {
+        let event = test_event(serde_json::json!({
+            "event": { "request": { "url": "https://example.com", "method": "GET" } },
+        }));
+        assert_eq!(event.script_name, None);
+        assert_eq!(event.cpu_time, None);
+        assert_eq!(event.event.event_type(), "fetch");
+    }
+
+    #[test]
+    fn it_projects_selected_fields() {
+        let event = test_event(serde_json::json!({
+            "outcome": "exception",
+            "logs": [{ "level": "log", "message": ["hello"] }],
+            "event": { "request": { "url": "https://example.com/", "method": "POST" } },
+        }));
+        let fields = vec![Field::Url, Field::Status, Field::Colo];
+        assert_eq!(
+            event.project(&fields),
+            serde_json::json!({ "url": "https://example.com/", "status": "exception", "colo": null })
+        );
+        assert_eq!(
+            event.display_fields(&fields),
+            "https://example.com/ exception -"
+        );
+        assert!(Field::from_str("bogus").is_err());
+    }
+}
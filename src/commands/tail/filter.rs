@@ -1,6 +1,12 @@
+use super::event::TraceEvent;
+
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
 use serde::Serialize;
 use std::collections::HashSet;
-use std::convert::From;
+use std::convert::{From, TryFrom};
+use url::Url;
 
 /// A filter that accepts trace events.
 ///
@@ -8,6 +14,14 @@ use std::convert::From;
 /// it only uploads them to the `WebSocketTail`.
 pub trait TraceFilter: erased_serde::Serialize {}
 
+/// A filter that accepts trace events after they are received.
+///
+/// Unlike a `TraceFilter`, this filtering is handled by wrangler,
+/// since the `WebSocketTail` does not support it.
+pub trait EventFilter {
+    fn accepts(&self, event: &TraceEvent) -> bool;
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OutcomeFilter {
     pub outcome: Vec<String>,
@@ -111,5 +125,181 @@ impl From<String> for QueryFilter {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    pub paths: GlobSet,
+}
+
+impl EventFilter for PathFilter {
+    fn accepts(&self, event: &TraceEvent) -> bool {
+        match &event.event.request {
+            Some(request) => match Url::parse(&request.url) {
+                Ok(url) => self.paths.is_match(url.path()),
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+}
+
+impl TryFrom<Vec<String>> for PathFilter {
+    type Error = anyhow::Error;
+    fn try_from(paths: Vec<String>) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for path in paths {
+            builder.add(Glob::new(&path)?);
+        }
+        Ok(Self {
+            paths: builder.build()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrepFilter {
+    pub pattern: Regex,
+}
+
+impl EventFilter for GrepFilter {
+    fn accepts(&self, event: &TraceEvent) -> bool {
+        let logs = event.logs.iter().map(|log| match &log.message {
+            serde_json::Value::String(message) => message.to_owned(),
+            message => message.to_string(),
+        });
+        let exceptions = event
+            .exceptions
+            .iter()
+            .map(|err| format!("{}: {}", err.name, err.message));
+        logs.chain(exceptions)
+            .any(|line| self.pattern.is_match(&line))
+    }
+}
+
+impl TryFrom<String> for GrepFilter {
+    type Error = anyhow::Error;
+    fn try_from(pattern: String) -> Result<Self> {
+        Ok(Self {
+            pattern: Regex::new(&pattern)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventTypeFilter {
+    pub event_type: String,
+}
+
+impl EventFilter for EventTypeFilter {
+    fn accepts(&self, event: &TraceEvent) -> bool {
+        event.event.event_type() == self.event_type
+    }
+}
+
+impl From<String> for EventTypeFilter {
+    fn from(event_type: String) -> Self {
+        Self { event_type }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExceptionFilter;
+
+impl EventFilter for ExceptionFilter {
+    fn accepts(&self, event: &TraceEvent) -> bool {
+        !event.exceptions.is_empty()
+    }
+}
+
 // By default, serde::Serialize does not handle embeded traits, this fixes that.
 serialize_trait_object!(TraceFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::tail::event::test_event;
+
+    fn outcomes(statuses: &[&str]) -> Vec<String> {
+        let filter =
+            OutcomeFilter::from(statuses.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        let mut outcome = filter.outcome;
+        outcome.sort();
+        outcome
+    }
+
+    #[test]
+    fn it_maps_error_status_to_outcomes() {
+        assert_eq!(
+            outcomes(&["error"]),
+            vec!["exceededCpu", "exception", "unknown"]
+        );
+    }
+
+    #[test]
+    fn it_dedupes_and_ignores_unknown_statuses() {
+        assert_eq!(
+            outcomes(&["ok", "ok", "canceled", "bogus"]),
+            vec!["canceled", "ok"]
+        );
+    }
+
+    #[test]
+    fn it_filters_by_request_path() {
+        let filter = PathFilter::try_from(vec!["/api/*".to_string()]).unwrap();
+        let event = |url: &str| {
+            test_event(serde_json::json!({
+                "event": { "request": { "url": url, "method": "GET" } },
+            }))
+        };
+        assert!(filter.accepts(&event("https://example.com/api/users")));
+        assert!(!filter.accepts(&event("https://example.com/static/app.js")));
+    }
+
+    #[test]
+    fn it_filters_by_log_and_exception_text() {
+        let filter = GrepFilter::try_from("req-[0-9]+".to_string()).unwrap();
+        let event = |logs: serde_json::Value, exceptions: serde_json::Value| {
+            test_event(serde_json::json!({ "logs": logs, "exceptions": exceptions }))
+        };
+        assert!(filter.accepts(&event(
+            serde_json::json!([{ "level": "log", "message": ["handling", "req-42"] }]),
+            serde_json::json!([])
+        )));
+        assert!(filter.accepts(&event(
+            serde_json::json!([]),
+            serde_json::json!([{ "name": "Error", "message": "req-7 failed" }])
+        )));
+        assert!(!filter.accepts(&event(
+            serde_json::json!([{ "level": "log", "message": ["hello"] }]),
+            serde_json::json!([])
+        )));
+    }
+
+    #[test]
+    fn it_parses_header_filter() {
+        let filter = HeaderFilter::from("X-Debug: 1".to_string());
+        assert_eq!(filter.key, "X-Debug");
+        assert_eq!(filter.query.as_deref(), Some("1"));
+
+        let filter = HeaderFilter::from("X-Debug".to_string());
+        assert_eq!(filter.key, "X-Debug");
+        assert_eq!(filter.query, None);
+    }
+
+    #[test]
+    fn it_serializes_header_filter() {
+        let filter: Box<dyn TraceFilter> = Box::new(HeaderFilter::from("X-Debug:1".to_string()));
+        assert_eq!(
+            serde_json::to_string(&filter).unwrap(),
+            r#"{"key":"X-Debug","query":"1"}"#
+        );
+    }
+
+    #[test]
+    fn it_serializes_outcome_filter() {
+        let filter: Box<dyn TraceFilter> = Box::new(OutcomeFilter::from(vec!["ok".to_string()]));
+        assert_eq!(
+            serde_json::to_string(&filter).unwrap(),
+            r#"{"outcome":["ok"]}"#
+        );
+    }
+}
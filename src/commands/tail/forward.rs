@@ -0,0 +1,125 @@
+use crate::http::feature::user_agent;
+
+use super::websocket::sleep_until;
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tokio::time::{Duration, Instant};
+use url::Url;
+
+/// The number of events that can wait to be forwarded, before new ones are dropped.
+const BUFFER_SIZE: usize = 1000;
+
+/// The number of events that are sent in one request.
+const BATCH_SIZE: usize = 100;
+
+/// The longest time an event waits for its batch to fill up, before the batch is sent anyway.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Forwards each `TraceEvent` to an HTTP endpoint, such as a log collector.
+///
+/// Events are buffered and sent in batches by a `Batcher`, as a JSON array in the body
+/// of a `POST` request, so a slow collector does not hold up the tail. A failed request
+/// is only logged, and events are dropped while the buffer is full, so an unavailable
+/// collector does not interrupt the tail either.
+#[derive(Debug, Clone)]
+pub struct Forwarder {
+    pub url: Url,
+    sender: Sender<Value>,
+}
+
+/// Sends the events of a `Forwarder` in batches, until every clone of it is dropped.
+#[derive(Debug)]
+pub struct Batcher {
+    url: Url,
+    client: Client,
+    receiver: Receiver<Value>,
+}
+
+impl Forwarder {
+    pub fn new(url: Url) -> Result<(Self, Batcher)> {
+        let client = Client::builder()
+            .user_agent(user_agent())
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
+        let forwarder = Self {
+            url: url.clone(),
+            sender,
+        };
+        let batcher = Batcher {
+            url,
+            client,
+            receiver,
+        };
+        Ok((forwarder, batcher))
+    }
+
+    /// Buffers an event to be sent with the next batch.
+    pub fn send(&self, event: &Value) {
+        match self.sender.try_send(event.to_owned()) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => {
+                log::warn!("Dropped an event, since {} is not keeping up", self.url)
+            }
+            Err(TrySendError::Closed(_)) => {
+                log::debug!("Dropped an event, since forwarding has stopped")
+            }
+        }
+    }
+}
+
+impl Batcher {
+    /// Sends a batch once it is full or has waited for `FLUSH_INTERVAL`, then sends
+    /// the last one once the tail has ended.
+    pub async fn run(mut self) {
+        let mut batch = Vec::new();
+        let mut deadline = None;
+        loop {
+            tokio::select! {
+                event = self.receiver.recv() => match event {
+                    Some(event) => {
+                        deadline.get_or_insert_with(|| Instant::now() + FLUSH_INTERVAL);
+                        batch.push(event);
+                        if batch.len() >= BATCH_SIZE {
+                            self.flush(&mut batch).await;
+                            deadline = None;
+                        }
+                    }
+                    None => break,
+                },
+                _ = sleep_until(deadline) => {
+                    self.flush(&mut batch).await;
+                    deadline = None;
+                }
+            }
+        }
+        self.flush(&mut batch).await;
+    }
+
+    /// Sends the events of a batch to the endpoint.
+    async fn flush(&self, batch: &mut Vec<Value>) {
+        if batch.is_empty() {
+            return;
+        }
+        let events = std::mem::take(batch);
+        match self
+            .client
+            .post(self.url.clone())
+            .json(&events)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => log::debug!("Forwarded {} events to {}", events.len(), self.url),
+            Err(err) => log::warn!(
+                "Failed to forward {} events to {}: {}",
+                events.len(),
+                self.url,
+                err
+            ),
+        }
+    }
+}
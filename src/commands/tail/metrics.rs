@@ -0,0 +1,121 @@
+use super::event::TraceEvent;
+
+use anyhow::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Response, Server, StatusCode};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Counters about the `TraceEvent`s received by a tail, in the Prometheus text format.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub received: u64,
+    pub parse_failures: u64,
+    pub outcomes: BTreeMap<String, u64>,
+}
+
+impl Metrics {
+    /// Counts a message from the tail, or a parse failure if it was not an event.
+    pub fn record(&mut self, event: Option<&TraceEvent>) {
+        self.received += 1;
+        match event {
+            Some(event) => *self.outcomes.entry(event.outcome.clone()).or_insert(0) += 1,
+            None => self.parse_failures += 1,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut body = String::new();
+        let _ = writeln!(
+            body,
+            "# HELP wrangler_tail_events_total Messages received from the tail.\n# TYPE wrangler_tail_events_total counter\nwrangler_tail_events_total {}",
+            self.received
+        );
+        let _ = writeln!(
+            body,
+            "# HELP wrangler_tail_parse_failures_total Messages from the tail that could not be parsed as an event.\n# TYPE wrangler_tail_parse_failures_total counter\nwrangler_tail_parse_failures_total {}",
+            self.parse_failures
+        );
+        let _ = writeln!(
+            body,
+            "# HELP wrangler_tail_outcomes_total Events received from the tail, by outcome.\n# TYPE wrangler_tail_outcomes_total counter"
+        );
+        for (outcome, count) in self.outcomes.iter() {
+            let _ = writeln!(
+                body,
+                "wrangler_tail_outcomes_total{{outcome=\"{}\"}} {}",
+                outcome, count
+            );
+        }
+        body
+    }
+}
+
+/// Binds a server for the metrics at `/metrics`, which serves them until the future is dropped.
+///
+/// The port is bound right away, so a port that is taken is reported before any tail is created.
+pub fn bind(
+    metrics: Arc<Mutex<Metrics>>,
+    port: u16,
+) -> Result<impl Future<Output = hyper::Result<()>>> {
+    let listening_address = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.to_owned();
+        async move {
+            Ok::<_, anyhow::Error>(service_fn(move |req| {
+                let metrics = metrics.to_owned();
+                async move {
+                    let response = match (req.method(), req.uri().path()) {
+                        (&Method::GET, "/metrics") => {
+                            let body = match metrics.lock() {
+                                Ok(metrics) => metrics.render(),
+                                Err(_) => String::new(),
+                            };
+                            Response::builder()
+                                .header("Content-Type", "text/plain; version=0.0.4")
+                                .body(Body::from(body))?
+                        }
+                        _ => Response::builder()
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::empty())?,
+                    };
+                    Ok::<_, anyhow::Error>(response)
+                }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&listening_address) {
+        Ok(server) => server.serve(make_service),
+        Err(err) => anyhow::bail!("Failed to serve metrics on {}: {}", listening_address, err),
+    };
+    log::info!(
+        "Serving tail metrics on http://{}/metrics",
+        listening_address
+    );
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::tail::event::test_event;
+
+    #[test]
+    fn it_renders_counters_by_outcome() {
+        let event = test_event(serde_json::json!({}));
+        let mut metrics = Metrics::default();
+        metrics.record(Some(&event));
+        metrics.record(Some(&event));
+        metrics.record(None);
+
+        let body = metrics.render();
+        assert!(body.contains("wrangler_tail_events_total 3\n"));
+        assert!(body.contains("wrangler_tail_parse_failures_total 1\n"));
+        assert!(body.contains("wrangler_tail_outcomes_total{outcome=\"ok\"} 2\n"));
+    }
+}
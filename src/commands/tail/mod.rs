@@ -5,8 +5,14 @@ pub mod api;
 ///        which will act as a log forwarder. The API returns the URL of the log forwarder.
 ///     2. wrangler connects to the log forwarder using a WebSocket.
 ///     3. Upon receipt of messages, wrangler prints log events to stdout.
+pub mod dedupe;
 pub mod event;
 pub mod filter;
+pub mod forward;
+pub mod metrics;
+pub mod output;
+pub mod stats;
+pub mod stream;
 pub mod websocket;
 
 use crate::settings::global_user::GlobalUser;
@@ -16,74 +22,253 @@ use api::Tail;
 use websocket::{TailOptions, WebSocketTail};
 
 use anyhow::Result;
+use futures_util::future::{join_all, pending};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::future::Future;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use url::Url;
 
 /// Runs a tail session from creation to deletion.
 ///
+/// Each script gets its own tail, and their events are printed as they arrive.
+///
 /// It can be interrupted by:
 /// * an API error when creating the tail
-/// * a WebSocket error when receiving events
-/// * a user typing ctrl-c
-/// * an expiration of the tail
+/// * a WebSocket error when receiving events, after failing to reconnect
+/// * a user typing ctrl-c, or the process being terminated
+/// * an expiration of the tail, when using a URL to forward logs
+/// * the end of the requested `--duration`
 ///
 /// A fancy progress bar is also updated throughout the session.
 pub async fn run(
     user: GlobalUser,
     account_id: String,
-    script_name: String,
+    scripts: Vec<(String, TailOptions)>,
     url: Option<Url>,
-    options: TailOptions,
 ) -> Result<()> {
-    let progress = &mut ProgressBar::new_spinner()
+    let progress = ProgressBar::new_spinner()
         .with_style(ProgressStyle::default_spinner().template("{spinner} {msg}"));
     progress.enable_steady_tick(20);
+    session(user, account_id, scripts, url, progress, pending()).await
+}
+
+/// Runs a tail session alongside another command, such as `wrangler dev`, which
+/// prints its events without a progress bar and stops it once `stop` completes.
+pub async fn run_in_background(
+    user: GlobalUser,
+    account_id: String,
+    scripts: Vec<(String, TailOptions)>,
+    stop: impl Future<Output = ()>,
+) -> Result<()> {
+    session(user, account_id, scripts, None, ProgressBar::hidden(), stop).await
+}
+
+/// Creates the tails, prints or forwards their events until they end or `stop`
+/// completes, and then deletes them.
+async fn session(
+    user: GlobalUser,
+    account_id: String,
+    scripts: Vec<(String, TailOptions)>,
+    url: Option<Url>,
+    progress: ProgressBar,
+    stop: impl Future<Output = ()>,
+) -> Result<()> {
     progress.set_message("Creating tail...");
 
-    let tail = &mut Tail::new(user, account_id, script_name, url);
-    tail.create().await?;
+    let mut tails = Vec::new();
+    let mut options = Vec::new();
+    for (script_name, script_options) in scripts {
+        let mut tail = Tail::new(user.clone(), account_id.clone(), script_name, url.clone());
+        if let Err(err) = tail.create().await {
+            delete(&mut tails).await?;
+            return Err(err);
+        }
+        tails.push(tail);
+        options.push(script_options);
+    }
 
-    if tail.is_web_socket() {
+    let script_names = tails
+        .iter()
+        .map(|tail| styles::bold(&tail.script_name).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut result = Ok(());
+    if tails.iter().all(Tail::is_web_socket) {
         progress.set_message("Connecting to tail...");
 
-        match &mut WebSocketTail::connect(tail.clone(), options).await {
-            Ok(websocket) => {
-                progress.abandon_with_message(&format!(
-                    "Connected! Streaming logs from {}... (ctrl-c to quit)",
-                    styles::bold(&tail.script_name)
-                ));
-
-                if let Err(err) = websocket.update().await {
-                    log::warn!("{}", err);
-                };
-                if let Err(err) = websocket.read().await {
-                    log::warn!("{}", err);
+        let mut websockets = Vec::new();
+        for (tail, options) in tails.iter().zip(options) {
+            match WebSocketTail::connect(tail.clone(), options).await {
+                Ok(websocket) => websockets.push(websocket),
+                Err(err) => {
+                    progress.finish_and_clear();
+                    result = Err(err);
+                    break;
                 }
             }
-            Err(err) => progress.abandon_with_message(&format!("{}", err)),
+        }
+
+        if websockets.len() == tails.len() {
+            progress.abandon_with_message(&format!(
+                "Connected! Streaming logs from {}... (ctrl-c to quit)",
+                script_names
+            ));
+
+            let reads = join_all(websockets.iter_mut().map(WebSocketTail::read));
+            tokio::select! {
+                results = reads => {
+                    result = results.into_iter().collect();
+                }
+                _ = stop => {
+                    for websocket in websockets.iter_mut() {
+                        let _ = websocket
+                            .close(CloseCode::Away, "wrangler is closing as its command has stopped")
+                            .await;
+                    }
+                }
+            }
+
+            // The tails may have been replaced after reconnecting.
+            tails = websockets
+                .into_iter()
+                .map(|websocket| websocket.stream.tail)
+                .collect();
         }
     } else {
         progress.set_message(&format!(
             "Forwarding logs from {} to {} (ctrl-c to quit)",
-            styles::bold(&tail.script_name),
+            script_names,
             styles::url(
-                tail.url
-                    .clone()
-                    .map(String::from)
+                url.map(String::from)
                     .unwrap_or_else(|| "an endpoint".to_owned())
             )
         ));
 
-        if let Err(err) = loop {
-            tokio::select! {
-                _ = tokio::signal::ctrl_c() => break Ok(()),
-                _ = tokio::time::sleep_until(tail.expires_at) => if let Err(err) = tail.keep_alive().await { break Err(err) }
+        tokio::select! {
+            results = join_all(tails.iter_mut().map(keep_alive)) => {
+                result = results.into_iter().collect();
             }
-        } {
-            progress.abandon_with_message(&format!("{}", err));
+            _ = stop => {}
+        }
+        if result.is_err() {
+            progress.finish_and_clear();
         }
     }
 
-    tail.delete().await?;
-    Ok(())
+    // the tails are deleted even if the session failed, so they don't linger until they expire
+    delete(&mut tails).await.and(result)
+}
+
+/// Keeps a tail alive until wrangler is asked to shut down.
+async fn keep_alive(tail: &mut Tail) -> Result<()> {
+    loop {
+        tokio::select! {
+            _ = shutdown() => break Ok(()),
+            _ = tokio::time::sleep_until(tail.expires_at) => tail.keep_alive().await?
+        }
+    }
+}
+
+/// Deletes all of the tails, even if some of them fail.
+async fn delete(tails: &mut [Tail]) -> Result<()> {
+    let mut result = Ok(());
+    for tail in tails.iter_mut() {
+        if let Err(err) = tail.delete().await {
+            result = Err(err);
+        }
+    }
+    result
+}
+
+/// Waits until wrangler is asked to shut down.
+///
+/// Besides ctrl-c, this also handles the process being terminated or its terminal
+/// being closed, so the tail can be deleted instead of lingering until it expires.
+pub async fn shutdown() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::SignalKind;
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = wait_for(SignalKind::terminate()) => {},
+            _ = wait_for(SignalKind::hangup()) => {},
+        }
+    }
+    #[cfg(windows)]
+    {
+        let ctrl_break = async {
+            match tokio::signal::windows::ctrl_break() {
+                Ok(mut signal) => signal.recv().await,
+                Err(_) => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = ctrl_break => {},
+            _ = console_closed() => {},
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for(kind: tokio::signal::unix::SignalKind) {
+    match tokio::signal::unix::signal(kind) {
+        Ok(mut signal) => {
+            signal.recv().await;
+        }
+        Err(err) => {
+            log::debug!("Failed to listen for signal: {}", err);
+            std::future::pending().await
+        }
+    }
+}
+
+/// Waits until the console is closed, or the user logs off or shuts down Windows.
+///
+/// tokio only handles ctrl-c and ctrl-break, so these events are handled through the
+/// console API instead. Windows ends the process once the handler returns, so it waits
+/// a moment for the tails to be deleted first.
+#[cfg(windows)]
+async fn console_closed() {
+    use once_cell::sync::Lazy;
+    use tokio::sync::watch;
+
+    const CTRL_CLOSE_EVENT: u32 = 2;
+    const CTRL_LOGOFF_EVENT: u32 = 5;
+    const CTRL_SHUTDOWN_EVENT: u32 = 6;
+
+    extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    unsafe extern "system" fn handler(ctrl_type: u32) -> i32 {
+        match ctrl_type {
+            CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+                let _ = CLOSED.0.send(true);
+                // The process is ended 5 seconds after the console is closed.
+                std::thread::sleep(std::time::Duration::from_secs(4));
+                1
+            }
+            _ => 0,
+        }
+    }
+
+    static CLOSED: Lazy<(watch::Sender<bool>, watch::Receiver<bool>)> = Lazy::new(|| {
+        let closed = watch::channel(false);
+        if unsafe { SetConsoleCtrlHandler(Some(handler), 1) } == 0 {
+            log::debug!("Failed to listen for the console closing");
+        }
+        closed
+    });
+
+    let mut closed = CLOSED.1.clone();
+    while !*closed.borrow() {
+        if closed.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
 }
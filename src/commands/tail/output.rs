@@ -0,0 +1,112 @@
+use anyhow::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The number of rotated files to keep, e.g. `tail.log.1` to `tail.log.5`.
+const MAX_ROTATED_FILES: usize = 5;
+
+/// A file that receives a copy of each `TraceEvent`, as newline-delimited JSON.
+///
+/// When `max_size` is set, the file is rotated once it exceeds that many bytes.
+#[derive(Debug)]
+pub struct OutFile {
+    pub path: PathBuf,
+    pub max_size: Option<u64>,
+    file: File,
+    size: u64,
+}
+
+impl OutFile {
+    /// Opens the file for appending, creating it if it does not exist.
+    pub fn new(path: PathBuf, max_size: Option<u64>) -> Result<Self> {
+        let file = open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            file,
+            size,
+        })
+    }
+
+    /// Writes a line to the file, rotating it first if it is too large.
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        if let Some(max_size) = self.max_size {
+            if self.size > 0 && self.size + line.len() as u64 + 1 > max_size {
+                self.rotate()?;
+            }
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Shifts `path.N` to `path.N+1`, then moves `path` to `path.1`.
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = open(&self.path)?;
+        self.size = 0;
+        log::info!("Rotated tail output file: {}", self.path.display());
+        Ok(())
+    }
+}
+
+fn open(path: &Path) -> Result<File> {
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => Ok(file),
+        Err(err) => anyhow::bail!("Failed to open {}: {}", path.display(), err),
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rotates_when_max_size_is_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tail.log");
+        let mut out = OutFile::new(path.clone(), Some(10)).unwrap();
+
+        out.write_line("first").unwrap();
+        out.write_line("second").unwrap();
+        out.write_line("third").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "second\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "first\n"
+        );
+    }
+
+    #[test]
+    fn it_does_not_rotate_without_max_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tail.log");
+        let mut out = OutFile::new(path.clone(), None).unwrap();
+
+        out.write_line("first").unwrap();
+        out.write_line("second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        assert!(!rotated_path(&path, 1).exists());
+    }
+}
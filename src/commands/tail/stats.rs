@@ -0,0 +1,171 @@
+use super::event::TraceEvent;
+
+use console::{style, Term};
+use std::collections::HashMap;
+use tokio::time::{Duration, Instant};
+use url::Url;
+
+/// How often the summary is redrawn.
+pub const DRAW_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The number of paths and exceptions to show in the summary.
+const TOP_COUNT: usize = 5;
+
+/// Rolling counters about `TraceEvent`s, shown instead of the events themselves.
+#[derive(Debug)]
+pub struct Stats {
+    pub started_at: Instant,
+    pub next_draw: Instant,
+    pub total: u64,
+    pub errors: u64,
+    pub paths: HashMap<String, u64>,
+    pub exceptions: HashMap<String, u64>,
+    since_draw: u64,
+    drawn_at: Instant,
+    drawn_lines: usize,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: now,
+            next_draw: now + DRAW_INTERVAL,
+            total: 0,
+            errors: 0,
+            paths: HashMap::new(),
+            exceptions: HashMap::new(),
+            since_draw: 0,
+            drawn_at: now,
+            drawn_lines: 0,
+        }
+    }
+
+    /// Adds an event to the counters.
+    pub fn record(&mut self, event: &TraceEvent) {
+        self.total += 1;
+        self.since_draw += 1;
+        if !matches!(event.outcome.as_ref(), "ok" | "canceled") {
+            self.errors += 1;
+        }
+        if let Some(request) = &event.event.request {
+            let path = match Url::parse(&request.url) {
+                Ok(url) => url.path().to_owned(),
+                Err(_) => request.url.to_owned(),
+            };
+            *self.paths.entry(path).or_insert(0) += 1;
+        }
+        for err in event.exceptions.iter() {
+            *self
+                .exceptions
+                .entry(format!("{}: {}", err.name, err.message))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Redraws the summary, if enough time has passed since the last time.
+    pub fn draw_if_due(&mut self) {
+        if Instant::now() >= self.next_draw {
+            self.draw();
+        }
+    }
+
+    /// Redraws the summary in place of the previous one.
+    pub fn draw(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.drawn_at).as_secs_f64();
+        let rate = match elapsed > 0.0 {
+            true => self.since_draw as f64 / elapsed,
+            false => 0.0,
+        };
+        let error_rate = match self.total {
+            0 => 0.0,
+            total => self.errors as f64 / total as f64 * 100.0,
+        };
+
+        let mut lines = vec![
+            format!(
+                "{} {} events in {}s, {:.1} events/sec, {:.1}% errors",
+                style("Stats").bold(),
+                self.total,
+                now.duration_since(self.started_at).as_secs(),
+                rate,
+                error_rate
+            ),
+            format!("{}", style("Top paths").dim()),
+        ];
+        lines.extend(top(&self.paths).map(|(path, count)| format!("  {:>6}  {}", count, path)));
+        lines.push(format!("{}", style("Top exceptions").dim()));
+        lines.extend(
+            top(&self.exceptions)
+                .map(|(err, count)| format!("  {:>6}  {}", count, style(err).red())),
+        );
+
+        let term = Term::stdout();
+        if term.is_term() && self.drawn_lines > 0 {
+            if let Err(err) = term.clear_last_lines(self.drawn_lines) {
+                log::debug!("Failed to clear stats: {}", err);
+            }
+        }
+        for line in lines.iter() {
+            println!("{}", line);
+        }
+
+        self.drawn_lines = lines.len();
+        self.drawn_at = now;
+        self.next_draw = now + DRAW_INTERVAL;
+        self.since_draw = 0;
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sorts the counters from highest to lowest, then takes the first few.
+fn top(counters: &HashMap<String, u64>) -> impl Iterator<Item = (&String, &u64)> {
+    let mut counters = counters.iter().collect::<Vec<_>>();
+    counters.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    counters.into_iter().take(TOP_COUNT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::tail::event::test_event;
+
+    fn event(outcome: &str, url: &str, exceptions: serde_json::Value) -> TraceEvent {
+        test_event(serde_json::json!({
+            "outcome": outcome,
+            "exceptions": exceptions,
+            "event": { "request": { "url": url, "method": "GET" } },
+        }))
+    }
+
+    #[test]
+    fn it_counts_events_errors_and_paths() {
+        let mut stats = Stats::new();
+        stats.record(&event(
+            "ok",
+            "https://example.com/a?x=1",
+            serde_json::json!([]),
+        ));
+        stats.record(&event("ok", "https://example.com/a", serde_json::json!([])));
+        stats.record(&event(
+            "exception",
+            "https://example.com/b",
+            serde_json::json!([{ "name": "TypeError", "message": "oops" }]),
+        ));
+
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.paths.get("/a"), Some(&2));
+        assert_eq!(stats.exceptions.get("TypeError: oops"), Some(&1));
+        assert_eq!(
+            top(&stats.paths).collect::<Vec<_>>(),
+            vec![(&"/a".to_owned(), &2), (&"/b".to_owned(), &1)]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use crate::http::feature::user_agent;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdErr};
+
+use super::api::Tail;
+use super::event::{TraceEvent, PROTOCOL_ID};
+use super::filter::TraceFilter;
+
+use anyhow::Result;
+use futures_util::{stream, SinkExt, Stream, StreamExt};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cmp::min;
+use tokio::net::TcpStream;
+use tokio::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::error::Error::{AlreadyClosed, ConnectionClosed};
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{self, CloseFrame};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// The number of times to try reconnecting to a tail, before giving up.
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// The longest time to wait between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How often to keep-alive a tail created by `EventStream::connect`.
+const DEFAULT_HEARTBEAT: Duration = Duration::from_secs(60);
+
+/// A stream of messages from the tail of a published Worker.
+///
+/// The tail is kept-alive, replaced when it expires and reconnected when the WebSocket
+/// is lost, so the stream only ends once it's closed. `wrangler tail` prints its messages,
+/// and other programs can read its `TraceEvent`s without any of that printing. For example:
+///
+/// ```no_run
+/// # use futures_util::{pin_mut, StreamExt};
+/// # use wrangler::commands::tail::stream::EventStream;
+/// # async fn example(user: wrangler::settings::global_user::GlobalUser) -> anyhow::Result<()> {
+/// let mut tail = EventStream::connect(user, "account".into(), "worker".into(), vec![]).await?;
+/// {
+///     let events = tail.events();
+///     pin_mut!(events);
+///     while let Some(event) = events.next().await {
+///         println!("{}", event?.outcome);
+///     }
+/// }
+/// tail.close().await
+/// # }
+/// ```
+///
+/// Call `close` when done, otherwise the tail lingers until it expires.
+pub struct EventStream {
+    pub tail: Tail,
+    websocket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    filters: Vec<Box<dyn TraceFilter>>,
+    heartbeat: Duration,
+    next_heartbeat: Instant,
+    closed: bool,
+    disconnected: bool,
+}
+
+#[derive(Serialize)]
+struct Filters<'a> {
+    filters: &'a [Box<dyn TraceFilter>],
+}
+
+impl EventStream {
+    /// Creates a tail, then connects to it.
+    pub async fn connect(
+        user: GlobalUser,
+        account_id: String,
+        script_name: String,
+        filters: Vec<Box<dyn TraceFilter>>,
+    ) -> Result<Self> {
+        let mut tail = Tail::new(user, account_id, script_name, None);
+        tail.create().await?;
+        match Self::attach(tail.clone(), filters, DEFAULT_HEARTBEAT).await {
+            Ok(stream) => Ok(stream),
+            Err(err) => {
+                tail.delete().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Connects to a tail that was already created, then sends the filters.
+    pub async fn attach(
+        tail: Tail,
+        filters: Vec<Box<dyn TraceFilter>>,
+        heartbeat: Duration,
+    ) -> Result<Self> {
+        let websocket = open(&tail).await?;
+        let mut stream = Self {
+            tail,
+            websocket,
+            filters,
+            heartbeat,
+            next_heartbeat: Instant::now() + heartbeat,
+            closed: false,
+            disconnected: false,
+        };
+        stream.update().await?;
+        Ok(stream)
+    }
+
+    /// Tests if the stream was closed for good, so it has no more messages.
+    pub fn is_closed(&self) -> bool {
+        self.closed && !self.disconnected
+    }
+
+    /// Reads the next text message from the tail, keeping it alive in the meantime.
+    ///
+    /// Returns `None` once the stream is closed, or an error if it could not reconnect.
+    pub async fn next_message(&mut self) -> Option<Result<String>> {
+        loop {
+            if self.closed && self.disconnected {
+                if let Err(err) = self.reconnect().await {
+                    self.disconnected = false;
+                    return Some(Err(err));
+                }
+                continue;
+            }
+            if self.closed {
+                return None;
+            }
+            let result = tokio::select! {
+                frame = self.websocket.next() => {
+                    match frame {
+                        Some(Ok(message)) if message.is_text() || message.is_binary() => {
+                            return Some(Ok(message.to_string()));
+                        },
+                        Some(Ok(message)) if message.is_close() => {
+                            log::debug!("Received close from WebSocket tail: {}", message);
+                            self.closed = true;
+                            self.disconnected = true;
+                            Ok(())
+                        },
+                        Some(Err(err)) => {
+                            log::debug!("Received error from WebSocket tail: {}", err);
+                            let result = self
+                                .close_websocket(CloseCode::Abnormal, "wrangler is closing due to an error")
+                                .await;
+                            self.disconnected = true;
+                            result
+                        },
+                        // Pings and pongs are handled by the WebSocket itself.
+                        Some(Ok(_)) => Ok(()),
+                        None => {
+                            log::debug!("WebSocket tail ended unexpectedly");
+                            self.closed = true;
+                            self.disconnected = true;
+                            Ok(())
+                        },
+                    }
+                },
+                _ = tokio::time::sleep_until(self.next_heartbeat) => {
+                    self.heartbeat().await
+                },
+                _ = tokio::time::sleep_until(self.tail.expires_at) => {
+                    log::info!("Tail expired, creating a new one");
+                    self.renew().await
+                },
+            };
+            if let Err(err) = result {
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// The `TraceEvent`s from the tail, skipping the messages that are not events.
+    pub fn events(&mut self) -> impl Stream<Item = Result<TraceEvent>> + '_ {
+        stream::unfold(self, |stream| async move {
+            loop {
+                match stream.next_message().await? {
+                    Ok(message) => match serde_json::from_str::<TraceEvent>(&message) {
+                        Ok(event) => return Some((Ok(event), stream)),
+                        Err(err) => log::warn!(
+                            "Skipped a tail message that is not an event ({}): {}",
+                            err,
+                            message
+                        ),
+                    },
+                    Err(err) => return Some((Err(err), stream)),
+                }
+            }
+        })
+    }
+
+    /// Reconnects to a new tail after the WebSocket was disconnected, with an exponential backoff.
+    async fn reconnect(&mut self) -> Result<()> {
+        let mut delay = Duration::from_secs(1);
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            StdErr::warn(&format!(
+                "Lost connection to tail, reconnecting in {}s... (attempt {} of {})",
+                delay.as_secs(),
+                attempt,
+                MAX_RECONNECT_ATTEMPTS
+            ));
+            tokio::time::sleep(delay).await;
+            match self.resume().await {
+                Ok(_) => {
+                    StdErr::info("Reconnected to tail.");
+                    return Ok(());
+                }
+                Err(err) => log::warn!("{}", err),
+            }
+            delay = min(delay * 2, MAX_RECONNECT_DELAY);
+        }
+        anyhow::bail!(
+            "Failed to reconnect to tail after {} attempts",
+            MAX_RECONNECT_ATTEMPTS
+        )
+    }
+
+    /// Sends a keep-alive to the tail, or replaces the tail if it has expired.
+    async fn heartbeat(&mut self) -> Result<()> {
+        self.next_heartbeat = Instant::now() + self.heartbeat;
+        match self.tail.keep_alive().await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log::info!("{}, creating a new tail", err);
+                self.renew().await
+            }
+        }
+    }
+
+    /// Replaces the tail with a new one, or reconnects with a backoff if that fails.
+    async fn renew(&mut self) -> Result<()> {
+        if let Err(err) = self.resume().await {
+            log::debug!("{}", err);
+            self.closed = true;
+            self.disconnected = true;
+        }
+        Ok(())
+    }
+
+    /// Replaces the tail with a new one, then re-sends the tail filters.
+    async fn resume(&mut self) -> Result<()> {
+        if let Err(err) = self.tail.delete().await {
+            // The old tail will expire on its own, so it's safe to forget it.
+            log::debug!("{}", err);
+            self.tail.id = None;
+            self.tail.url = None;
+        }
+        self.tail.create().await?;
+        self.websocket = open(&self.tail).await?;
+        self.closed = false;
+        self.disconnected = false;
+        self.next_heartbeat = Instant::now() + self.heartbeat;
+        self.update().await
+    }
+
+    /// Sends the tail filters to the WebSocket.
+    async fn update(&mut self) -> Result<()> {
+        if self.filters.is_empty() {
+            return Ok(());
+        }
+        let filters = match serde_json::to_string(&Filters {
+            filters: &self.filters,
+        }) {
+            Ok(filters) => filters,
+            Err(err) => anyhow::bail!("Failed to serialize filters: {}", err),
+        };
+        log::debug!("Sending message to WebSocket tail: {}", filters);
+        match self.websocket.send(protocol::Message::Text(filters)).await {
+            Err(err) => anyhow::bail!("Failed to write to WebSocket tail: {}", err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Closes the WebSocket, so the stream ends without reconnecting.
+    pub async fn close_with(&mut self, code: CloseCode, reason: &str) -> Result<()> {
+        self.disconnected = false;
+        self.close_websocket(code, reason).await
+    }
+
+    /// Closes the WebSocket and deletes the tail.
+    pub async fn close(mut self) -> Result<()> {
+        if let Err(err) = self
+            .close_with(
+                CloseCode::Normal,
+                "wrangler is closing as the stream has ended",
+            )
+            .await
+        {
+            log::debug!("{}", err);
+        }
+        self.tail.delete().await
+    }
+
+    /// Closes the WebSocket, unless it was already closed.
+    async fn close_websocket(&mut self, code: CloseCode, reason: &str) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        } else {
+            self.closed = true;
+        }
+        let frame = CloseFrame {
+            code,
+            reason: Cow::Borrowed(reason),
+        };
+        match self.websocket.close(Some(frame)).await {
+            Ok(_) => {
+                log::info!("Closed WebSocket tail: {}", reason);
+                Ok(())
+            }
+            Err(AlreadyClosed | ConnectionClosed) => Ok(()),
+            Err(err) => anyhow::bail!("Failed to close WebSocket tail: {}", err),
+        }
+    }
+}
+
+/// Opens a WebSocket to the tail.
+async fn open(tail: &Tail) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    let request = Request::builder()
+        .uri(&tail.url.clone().map(String::from).unwrap())
+        .header("User-Agent", user_agent())
+        .header("Sec-WebSocket-Protocol", PROTOCOL_ID)
+        .body(())?;
+    log::info!("Connecting to WebSocket tail: {:?}", request);
+    match tokio_tungstenite::connect_async(request).await {
+        Ok((websocket, _)) => Ok(websocket),
+        Err(err) => anyhow::bail!("Failed to create WebSocket tail: {}", err),
+    }
+}
@@ -1,20 +1,24 @@
-use crate::http::feature::user_agent;
+use crate::terminal::styles;
 
 use super::api::Tail;
-use super::event::{TraceEvent, PROTOCOL_ID};
-use super::filter::TraceFilter;
+use super::dedupe::Dedupe;
+use super::event::{Field, TraceEvent};
+use super::filter::{EventFilter, TraceFilter};
+use super::forward::Forwarder;
+use super::metrics::Metrics;
+use super::output::OutFile;
+use super::shutdown;
+use super::stats::Stats;
+use super::stream::EventStream;
 
 use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
+use serde_json::Value;
+use std::fmt::Display;
 use std::str::FromStr;
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::error::Error::{AlreadyClosed, ConnectionClosed};
-use tokio_tungstenite::tungstenite::handshake::client::Request;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
-use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Message};
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 /// The format to print a `TraceEvent`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,99 +38,126 @@ impl FromStr for TailFormat {
     }
 }
 
-/// Options that are sent to the `WebSocketTail`.
-#[derive(Serialize)]
+/// Options for how a `WebSocketTail` handles the messages of its stream.
 pub struct TailOptions {
-    #[serde(skip_serializing)]
     pub once: bool,
-    #[serde(skip_serializing)]
     pub format: TailFormat,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub filters: Vec<Box<dyn TraceFilter>>,
+    pub event_filters: Vec<Box<dyn EventFilter>>,
+    pub out_file: Option<Arc<Mutex<OutFile>>>,
+    pub label: bool,
+    pub forward: Option<Forwarder>,
+    pub duration: Option<Duration>,
+    pub stats: Option<Arc<Mutex<Stats>>>,
+    pub fields: Vec<Field>,
+    pub heartbeat: Duration,
+    pub metrics: Option<Arc<Mutex<Metrics>>>,
+    pub dedupe: Option<Dedupe>,
+    /// Print to stderr, when stdout is kept for the JSON output of another command.
+    pub stderr: bool,
 }
 
-/// A tail that sends `TraceEvent`s to a WebSocket.
+impl TailOptions {
+    /// Checks if an event should be printed, based on the filters handled by wrangler.
+    fn accepts(&self, event: Option<&TraceEvent>) -> bool {
+        if self.event_filters.is_empty() {
+            return true;
+        }
+        match event {
+            Some(event) => self.event_filters.iter().all(|f| f.accepts(event)),
+            None => false,
+        }
+    }
+
+    /// The time to redraw the stats, if they are shown.
+    fn next_draw(&self) -> Option<Instant> {
+        match &self.stats {
+            Some(stats) => stats.lock().ok().map(|stats| stats.next_draw),
+            None => None,
+        }
+    }
+
+    /// The time that the oldest collapsed log line expires, if logs are deduplicated.
+    fn next_expiry(&self) -> Option<Instant> {
+        match &self.dedupe {
+            Some(dedupe) => dedupe.next_expiry(),
+            None => None,
+        }
+    }
+}
+
+/// A tail that prints, forwards or counts the messages of an `EventStream`.
 pub struct WebSocketTail {
-    pub tail: Tail,
+    pub stream: EventStream,
     pub options: TailOptions,
-    pub websocket: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    pub closed: bool,
+    pub printed: usize,
+    pub deadline: Option<Instant>,
 }
 
 impl WebSocketTail {
     /// Connects to WebSocket tail.
-    pub async fn connect(tail: Tail, options: TailOptions) -> Result<Self> {
+    pub async fn connect(tail: Tail, mut options: TailOptions) -> Result<Self> {
         if tail.id.is_none() && tail.url.is_none() && !tail.is_web_socket() {
             anyhow::bail!("Precondition failed for WebSocket tail: {:?}", &tail);
         }
-        let request = Request::builder()
-            .uri(&tail.url.clone().map(String::from).unwrap())
-            .header("User-Agent", user_agent())
-            .header("Sec-WebSocket-Protocol", PROTOCOL_ID)
-            .body(())?;
-        log::info!("Connecting to WebSocket tail: {:?}", request);
-        match tokio_tungstenite::connect_async(request).await {
-            Ok((websocket, _)) => Ok(Self {
-                tail,
-                options,
-                websocket,
-                closed: false,
-            }),
-            Err(err) => anyhow::bail!("Failed to create WebSocket tail: {}", err),
-        }
+        let filters = std::mem::take(&mut options.filters);
+        let stream = EventStream::attach(tail, filters, options.heartbeat).await?;
+        let deadline = options.duration.map(|duration| Instant::now() + duration);
+        Ok(Self {
+            stream,
+            options,
+            printed: 0,
+            deadline,
+        })
     }
 
-    /// Reads a message from the WebSocket and prints it.
+    /// Reads a message from the stream and prints it.
     pub async fn read_once(&mut self) -> Result<()> {
-        tokio::select! {
-            frame = self.websocket.next() => {
-                match frame {
-                    Some(Ok(message)) if message.is_text() || message.is_binary() => {
-                        match self.options.format {
-                            TailFormat::Json => {
-                                println!("{}", message);
-                                Ok(())
-                            },
-                            TailFormat::Pretty => match serde_json::from_str::<TraceEvent>(&message.to_string()) {
-                                Ok(event) => {
-                                    println!("{}", event);
-                                    Ok(())
-                                },
-                                Err(err) => {
-                                    log::debug!("Failed to pretty-print tail: {}", err);
-                                    self.close(CloseCode::Protocol, "wrangler is closing due to a protocol violation").await
-                                },
-                            }
-                        }
+        // The read is not restarted when stats are drawn or repeats expire, since it may be
+        // in the middle of keeping the tail alive or reconnecting.
+        let message = {
+            let mut next = Box::pin(self.stream.next_message());
+            loop {
+                let next_draw = self.options.next_draw();
+                let next_expiry = self.options.next_expiry();
+                tokio::select! {
+                    message = &mut next => break Ok(message),
+                    _ = shutdown() => {
+                        break Err((CloseCode::Away, "wrangler is closing due to a shutdown signal"))
                     },
-                    Some(Ok(message)) if message.is_close() => {
-                        anyhow::bail!("Received close from WebSocket tail: {}", message)
+                    _ = sleep_until(self.deadline) => {
+                        break Err((CloseCode::Normal, "wrangler is closing after the requested duration"))
                     },
-                    Some(Err(err)) => {
-                        log::debug!("Received error from WebSocket tail: {}", err);
-                        self.close(CloseCode::Abnormal, "wrangler is closing due to an error").await
+                    _ = sleep_until(next_draw) => {
+                        if let Some(Ok(mut stats)) = self.options.stats.as_ref().map(|stats| stats.lock()) {
+                            stats.draw_if_due();
+                        }
                     },
-                    _ => Ok(()),
+                    _ = sleep_until(next_expiry) => {
+                        if let Some(dedupe) = &mut self.options.dedupe {
+                            print_repeats(dedupe.expire(Instant::now()), self.options.stderr);
+                        }
+                    }
                 }
-            },
-            _ = tokio::signal::ctrl_c() => {
-                self.close(CloseCode::Away, "wrangler is closing due to ctrl-c").await
-            },
-            _ = tokio::time::sleep_until(self.tail.expires_at) => {
-                self.close(CloseCode::Normal, "wrangler is closing due to expiration").await
             }
+        };
+        match message {
+            Ok(Some(Ok(message))) => self.print(message),
+            Ok(Some(Err(err))) => Err(err),
+            Ok(None) => Ok(()),
+            Err((code, reason)) => self.close(code, reason).await,
         }
     }
 
-    /// Reads and prints messages from the WebSocket in a loop.
+    /// Reads and prints messages from the stream in a loop.
     pub async fn read(&mut self) -> Result<()> {
-        loop {
-            if self.closed {
+        let result = loop {
+            if self.stream.is_closed() {
                 break Ok(());
             }
             match self.read_once().await {
                 Err(err) => break Err(err),
-                Ok(_) if self.options.once => {
+                Ok(_) if self.options.once && self.printed > 0 => {
                     break self
                         .close(
                             CloseCode::Normal,
@@ -136,47 +167,211 @@ impl WebSocketTail {
                 }
                 _ => {}
             };
+        };
+        // Print the repeats that are still counted, so they are not lost when the tail ends.
+        if let Some(dedupe) = &mut self.options.dedupe {
+            print_repeats(dedupe.drain(), self.options.stderr);
         }
+        result
     }
 
-    /// Writes a text message to the WebSocket.
-    pub async fn write(&mut self, message: String) -> Result<()> {
-        log::debug!("Sending message to WebSocket tail: {}", message);
-        match self.websocket.send(Message::Text(message)).await {
-            Err(err) => anyhow::bail!("Failed to write to WebSocket tail: {}", err),
-            _ => Ok(()),
+    /// Counts a message from the stream, if metrics are enabled.
+    fn record_metrics(&self, event: Option<&TraceEvent>) {
+        if let Some(Ok(mut metrics)) = self.options.metrics.as_ref().map(|metrics| metrics.lock()) {
+            metrics.record(event);
         }
     }
 
-    /// Sends the tail filters to the WebSocket.
-    pub async fn update(&mut self) -> Result<()> {
-        match self.options.filters.is_empty() {
-            false => match serde_json::to_string(&self.options) {
-                Ok(options) => self.write(options).await,
-                Err(err) => anyhow::bail!("Failed to deserialize options: {}", err),
+    /// Filters and prints a message from the stream.
+    fn print(&mut self, message: String) -> Result<()> {
+        let mut value = match serde_json::from_str::<Value>(&message) {
+            Ok(value) => value,
+            Err(err) => {
+                // A message that is not JSON can't be printed as NDJSON, so it's skipped
+                // instead of ending the tail.
+                log::warn!(
+                    "Skipped a tail message that is not JSON ({}): {}",
+                    err,
+                    message
+                );
+                self.record_metrics(None);
+                return Ok(());
+            }
+        };
+        let mut event = match serde_json::from_value::<TraceEvent>(value.clone()) {
+            Ok(event) => Some(event),
+            Err(err) => {
+                log::debug!("Failed to parse tail as an event: {}", err);
+                None
+            }
+        };
+        self.record_metrics(event.as_ref());
+        if !self.options.accepts(event.as_ref()) {
+            return Ok(());
+        }
+        // When tailing multiple scripts, label each event with the script it came from.
+        let label = match self.options.label {
+            true => Some(self.stream.tail.script_name.clone()),
+            false => None,
+        };
+        if let (Some(label), Value::Object(object)) = (&label, &mut value) {
+            object.insert("script".to_owned(), Value::String(label.to_owned()));
+        }
+        if let Some(out_file) = &self.options.out_file {
+            match out_file.lock() {
+                Ok(mut out_file) => out_file.write_line(&value.to_string())?,
+                Err(err) => anyhow::bail!("Failed to write to output file: {}", err),
+            }
+        }
+        if let Some(stats) = &self.options.stats {
+            if let (Ok(mut stats), Some(event)) = (stats.lock(), &event) {
+                stats.record(event);
+                stats.draw_if_due();
+            }
+            self.printed += 1;
+            return Ok(());
+        }
+        if let (TailFormat::Pretty, Some(dedupe), Some(event)) =
+            (&self.options.format, &mut self.options.dedupe, &mut event)
+        {
+            let now = Instant::now();
+            print_repeats(dedupe.expire(now), self.options.stderr);
+            event.logs.retain(|log| dedupe.check(&log.to_string(), now));
+        }
+        match (&self.options.format, event) {
+            (TailFormat::Json, Some(event)) if !self.options.fields.is_empty() => {
+                print_line(event.project(&self.options.fields), self.options.stderr)
+            }
+            (TailFormat::Pretty, Some(event)) if !self.options.fields.is_empty() => print_line(
+                event.display_fields(&self.options.fields),
+                self.options.stderr,
+            ),
+            // Re-serialize each event so it always fits on a single line (NDJSON).
+            (TailFormat::Json, _) => print_line(&value, self.options.stderr),
+            (TailFormat::Pretty, Some(event)) => match label {
+                Some(label) => print_line(
+                    format!("{} {}", styles::cyan(format!("[{}]", label)), event),
+                    self.options.stderr,
+                ),
+                None => print_line(event, self.options.stderr),
             },
-            true => Ok(()),
+            // A message of a shape wrangler doesn't know yet, such as a new event type, can't
+            // be pretty printed, so it's skipped like a message that is not JSON.
+            (TailFormat::Pretty, None) => {
+                log::warn!("Skipped a tail message that is not an event: {}", message);
+                return Ok(());
+            }
+        };
+        if let Some(forwarder) = &self.options.forward {
+            forwarder.send(&value);
         }
+        self.printed += 1;
+        Ok(())
     }
 
-    /// Closes the WebSocket.
+    /// Closes the stream, so the tail is not reconnected.
     pub async fn close(&mut self, code: CloseCode, reason: &str) -> Result<()> {
-        if self.closed {
-            return Ok(());
-        } else {
-            self.closed = true;
-        }
-        let frame = CloseFrame {
-            code,
-            reason: Cow::Borrowed(reason),
+        self.stream.close_with(code, reason).await
+    }
+}
+
+/// Prints the number of times that each collapsed log line repeated.
+fn print_repeats(repeated: Vec<(String, u64)>, stderr: bool) {
+    for (line, count) in repeated {
+        let prefix = console::style("|").dim();
+        let count = console::style(format!("x{}", count)).dim();
+        print_line(format!(" {} {} {}", prefix, line, count), stderr);
+    }
+}
+
+/// Prints a line to stdout, or to stderr if asked.
+fn print_line(line: impl Display, stderr: bool) {
+    if stderr {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Waits until the deadline, or forever if there is none.
+pub(super) async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::global_user::GlobalUser;
+
+    use super::super::event::test_event;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::protocol::Message;
+    use url::Url;
+
+    #[test]
+    fn it_prints_events_from_the_stream() {
+        let metrics = Arc::new(Mutex::new(Metrics::default()));
+        let options = TailOptions {
+            once: true,
+            format: TailFormat::Json,
+            filters: vec![],
+            event_filters: vec![],
+            out_file: None,
+            label: false,
+            forward: None,
+            duration: None,
+            stats: None,
+            fields: vec![],
+            heartbeat: Duration::from_secs(60),
+            metrics: Some(metrics.clone()),
+            dedupe: None,
+            stderr: false,
         };
-        match self.websocket.close(Some(frame)).await {
-            Ok(_) => {
-                log::info!("Closed WebSocket tail: {}", reason);
-                Ok(())
-            }
-            Err(AlreadyClosed | ConnectionClosed) => Ok(()),
-            Err(err) => anyhow::bail!("Failed to close WebSocket tail: {}", err),
-        }
+        let printed = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let url = format!("ws://{}", listener.local_addr().unwrap());
+                let server = async {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    let mut websocket = tokio_tungstenite::accept_async(socket).await.unwrap();
+                    let event = serde_json::to_string(&test_event(serde_json::json!({}))).unwrap();
+                    for message in vec!["not json".to_owned(), event] {
+                        websocket.send(Message::Text(message)).await.unwrap();
+                    }
+                    // The tail closes the WebSocket once it has printed an event.
+                    while let Some(Ok(message)) = websocket.next().await {
+                        if message.is_close() {
+                            break;
+                        }
+                    }
+                };
+                let client = async {
+                    let user = GlobalUser::TokenAuth {
+                        api_token: "token".to_owned(),
+                    };
+                    let mut tail = Tail::new(
+                        user,
+                        "account".to_owned(),
+                        "worker".to_owned(),
+                        Some(Url::parse(&url).unwrap()),
+                    );
+                    tail.expires_at = Instant::now() + Duration::from_secs(60);
+                    let mut websocket = WebSocketTail::connect(tail, options).await.unwrap();
+                    websocket.read().await.unwrap();
+                    websocket.printed
+                };
+                tokio::join!(server, client).1
+            });
+        assert_eq!(printed, 1);
+        let metrics = metrics.lock().unwrap();
+        assert_eq!((metrics.received, metrics.parse_failures), (2, 1));
     }
 }
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::deploy::ScheduleTarget;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+#[derive(Deserialize)]
+struct SchedulesResponse {
+    result: Schedules,
+}
+
+#[derive(Deserialize)]
+struct Schedules {
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Deserialize)]
+struct Schedule {
+    cron: String,
+    modified_on: Option<String>,
+}
+
+/// Prints the cron triggers of the deployed worker, which may differ from wrangler.toml.
+pub fn list(user: &GlobalUser, target: &Target) -> Result<()> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/schedules",
+        target.account_id.load()?,
+        target.name
+    );
+    let response = http::legacy_auth_client(user).get(&addr).send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let schedules: SchedulesResponse = response.json()?;
+    let schedules = schedules.result.schedules;
+
+    if schedules.is_empty() {
+        StdOut::info(&format!("{} has no cron triggers", target.name));
+    }
+    for schedule in schedules {
+        match schedule.modified_on {
+            Some(modified_on) => println!("{} (since {})", schedule.cron, modified_on),
+            None => println!("{}", schedule.cron),
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the cron triggers of the deployed worker, without uploading its script again.
+pub fn update(user: &GlobalUser, target: &Target, crons: Vec<String>) -> Result<()> {
+    let schedule = ScheduleTarget::build(
+        Some(target.account_id.load()?.to_string()),
+        target.name.clone(),
+        crons,
+    )?;
+    let crons = schedule.deploy(user)?;
+
+    if crons.is_empty() {
+        StdOut::success(&format!("Removed the cron triggers of {}", target.name));
+    } else {
+        StdOut::success(&format!(
+            "{} now runs on the schedules\n {}",
+            target.name,
+            styles::highlight(crons.join("\n "))
+        ));
+    }
+    Ok(())
+}
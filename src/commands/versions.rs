@@ -0,0 +1,232 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::styles;
+
+/// How much of the traffic of a script a version receives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionSplit {
+    pub version_id: String,
+    pub percentage: f64,
+}
+
+#[derive(Serialize)]
+struct DeploymentRequest<'a> {
+    strategy: &'a str,
+    versions: &'a [VersionSplit],
+}
+
+#[derive(Deserialize)]
+struct DeploymentsResponse {
+    result: Deployments,
+}
+
+#[derive(Deserialize)]
+struct Deployments {
+    deployments: Vec<Deployment>,
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    versions: Vec<VersionSplit>,
+}
+
+fn deployments_addr(target: &Target) -> Result<String> {
+    Ok(format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/deployments",
+        target.account_id.load()?,
+        target.name
+    ))
+}
+
+/// The versions the live deployment of a script splits its traffic between.
+fn fetch_live_versions(user: &GlobalUser, target: &Target) -> Result<Vec<VersionSplit>> {
+    let response = http::legacy_auth_client(user)
+        .get(&deployments_addr(target)?)
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    let deployments: DeploymentsResponse = response.json()?;
+    // the newest deployment is listed first
+    Ok(deployments
+        .result
+        .deployments
+        .into_iter()
+        .next()
+        .map(|deployment| deployment.versions)
+        .unwrap_or_default())
+}
+
+fn deploy_versions(user: &GlobalUser, target: &Target, versions: &[VersionSplit]) -> Result<()> {
+    let response = http::legacy_auth_client(user)
+        .post(&deployments_addr(target)?)
+        .json(&DeploymentRequest {
+            strategy: "percentage",
+            versions,
+        })
+        .send()?;
+    if !response.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(response.text()?))
+    }
+    Ok(())
+}
+
+/// Sends a percentage of the traffic of a script to a new version, and the rest to the
+/// version that had the most of it until now. With JSON output, the split is printed to
+/// stderr, so stdout only has the JSON.
+pub fn split(
+    user: &GlobalUser,
+    target: &Target,
+    version_id: &str,
+    percentage: f64,
+    out: Output,
+) -> Result<()> {
+    let live = fetch_live_versions(user, target)?;
+    let versions = split_versions(&live, version_id, percentage)?;
+    deploy_versions(user, target, &versions)?;
+
+    let (info, success): (fn(&str), fn(&str)) = match out {
+        Output::Json => (StdErr::info, StdErr::success),
+        Output::PlainText => (StdOut::info, StdOut::success),
+    };
+    for version in versions.iter() {
+        info(&format!(
+            "{}% of requests go to version {}",
+            version.percentage,
+            styles::highlight(&version.version_id)
+        ));
+    }
+    success(&format!(
+        "Run {} once you are happy with the new version",
+        styles::highlight("wrangler versions promote")
+    ));
+    Ok(())
+}
+
+/// Sends all of the traffic of a script to a version, completing a gradual rollout. Without
+/// a version, the one with the smaller share of a split deployment is promoted.
+pub fn promote(user: &GlobalUser, target: &Target, version_id: Option<String>) -> Result<()> {
+    let version_id = match version_id {
+        Some(version_id) => version_id,
+        None => {
+            let live = fetch_live_versions(user, target)?;
+            canary(&live)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is not rolling out a version, all of its traffic goes to one version",
+                        target.name
+                    )
+                })?
+                .version_id
+                .clone()
+        }
+    };
+
+    deploy_versions(
+        user,
+        target,
+        &[VersionSplit {
+            version_id: version_id.clone(),
+            percentage: 100.0,
+        }],
+    )?;
+    StdOut::success(&format!(
+        "All requests to {} go to version {}",
+        target.name,
+        styles::highlight(&version_id)
+    ));
+    Ok(())
+}
+
+fn split_versions(
+    live: &[VersionSplit],
+    version_id: &str,
+    percentage: f64,
+) -> Result<Vec<VersionSplit>> {
+    let current = live
+        .iter()
+        .filter(|version| version.version_id != version_id)
+        .max_by(|a, b| a.percentage.partial_cmp(&b.percentage).unwrap())
+        .ok_or_else(|| {
+            anyhow::anyhow!("There is no deployed version to roll out from, publish the worker without --percentage first")
+        })?;
+
+    Ok(vec![
+        VersionSplit {
+            version_id: version_id.to_owned(),
+            percentage,
+        },
+        VersionSplit {
+            version_id: current.version_id.clone(),
+            percentage: 100.0 - percentage,
+        },
+    ])
+}
+
+/// The version being rolled out, which has the smaller share of a split deployment.
+fn canary(live: &[VersionSplit]) -> Option<&VersionSplit> {
+    if live.len() < 2 {
+        return None;
+    }
+    live.iter()
+        .min_by(|a, b| a.percentage.partial_cmp(&b.percentage).unwrap())
+}
+
+pub fn parse_percentage(input: &str) -> Result<f64> {
+    let percentage: f64 = input.trim_end_matches('%').parse()?;
+    // NaN is not between them either
+    if !(percentage > 0.0 && percentage < 100.0) {
+        anyhow::bail!("the percentage has to be between 0 and 100, exclusive")
+    }
+    Ok(percentage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version_id: &str, percentage: f64) -> VersionSplit {
+        VersionSplit {
+            version_id: version_id.to_owned(),
+            percentage,
+        }
+    }
+
+    #[test]
+    fn it_splits_traffic_with_the_current_version() {
+        let live = vec![version("old", 80.0), version("older", 20.0)];
+
+        assert_eq!(
+            split_versions(&live, "new", 10.0).unwrap(),
+            vec![version("new", 10.0), version("old", 90.0)]
+        );
+        // rolling out the same version again only changes its share
+        assert_eq!(
+            split_versions(&[version("old", 90.0), version("new", 10.0)], "new", 50.0).unwrap(),
+            vec![version("new", 50.0), version("old", 50.0)]
+        );
+        assert!(split_versions(&[], "new", 10.0).is_err());
+    }
+
+    #[test]
+    fn it_promotes_the_version_being_rolled_out() {
+        let live = vec![version("old", 90.0), version("new", 10.0)];
+        assert_eq!(canary(&live).unwrap().version_id, "new");
+        assert!(canary(&[version("old", 100.0)]).is_none());
+    }
+
+    #[test]
+    fn it_parses_percentages() {
+        assert_eq!(parse_percentage("10").unwrap(), 10.0);
+        assert_eq!(parse_percentage("2.5%").unwrap(), 2.5);
+        assert!(parse_percentage("0").is_err());
+        assert!(parse_percentage("100").is_err());
+        assert!(parse_percentage("ten").is_err());
+        assert!(parse_percentage("NaN").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+
+use crate::commands::publish::PublishOutput;
+use crate::settings::toml::Manifest;
+use crate::terminal::message::{Message, Output, StdErr, StdOut};
+use crate::terminal::styles;
+
+/// A worker of the workspace, with the names of the workers of the workspace it binds to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub dir: PathBuf,
+    pub name: String,
+    pub dependencies: Vec<String>,
+}
+
+/// Finds the workers under a directory with a configuration file of the given name, skipping
+/// what's ignored by git such as node_modules.
+pub fn discover(root: &Path, config: &OsStr, environment: Option<&str>) -> Result<Vec<Member>> {
+    let mut members = vec![];
+    for entry in WalkBuilder::new(root).build() {
+        let entry = entry?;
+        if entry.file_name() != config
+            || entry
+                .path()
+                .components()
+                .any(|c| c.as_os_str() == "node_modules")
+        {
+            continue;
+        }
+        let manifest = Manifest::new(entry.path())?;
+        let target = manifest.get_target(environment, false)?;
+        members.push(Member {
+            dir: entry
+                .path()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| root.to_path_buf()),
+            name: manifest.worker_name(environment),
+            dependencies: target
+                .services
+                .iter()
+                .map(|service| service.service.clone())
+                .collect(),
+        });
+    }
+
+    let names = members
+        .iter()
+        .map(|member| member.name.clone())
+        .collect::<HashSet<_>>();
+    if names.len() != members.len() {
+        anyhow::bail!(
+            "Two {} files of the workspace publish a worker of the same name",
+            config.to_string_lossy()
+        );
+    }
+    // bindings to workers outside of the workspace don't decide the order
+    for member in members.iter_mut() {
+        member.dependencies.retain(|name| names.contains(name));
+    }
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(members)
+}
+
+/// Groups the workers so every worker comes after the workers it binds to, where the
+/// workers of a group don't bind to each other and can be published at the same time.
+pub fn levels(members: Vec<Member>) -> Result<Vec<Vec<Member>>> {
+    let mut published = HashSet::new();
+    let mut remaining = members;
+    let mut levels = vec![];
+    while !remaining.is_empty() {
+        let (level, rest): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|member| {
+            member
+                .dependencies
+                .iter()
+                .all(|dependency| published.contains(dependency) || dependency == &member.name)
+        });
+        if level.is_empty() {
+            let names = rest
+                .iter()
+                .map(|member| member.name.as_str())
+                .collect::<Vec<_>>();
+            anyhow::bail!(
+                "The service bindings of {} depend on each other, so they can't be published in order",
+                names.join(", ")
+            )
+        }
+        for member in level.iter() {
+            published.insert(member.name.clone());
+        }
+        levels.push(level);
+        remaining = rest;
+    }
+    Ok(levels)
+}
+
+/// Publishes every worker of the workspace, building the workers that don't depend on each
+/// other at the same time and stopping before the workers that depend on one that failed.
+///
+/// Each worker is published with the configuration file named like `config` in its directory,
+/// and with the `flags` of `wrangler publish` that apply to every worker.
+pub fn publish(
+    config: &Path,
+    environment: Option<&str>,
+    flags: &[String],
+    output: Output,
+) -> Result<()> {
+    let config = config.file_name().ok_or_else(|| {
+        anyhow::anyhow!(
+            "--config has to name a file to publish a workspace, {} does not",
+            config.display()
+        )
+    })?;
+    let members = discover(&env::current_dir()?, config, environment)?;
+    if members.is_empty() {
+        anyhow::bail!(
+            "--workspace was passed, but there are no {} files under this directory",
+            config.to_string_lossy()
+        );
+    }
+    let levels = levels(members)?;
+
+    let mut published: Vec<PublishOutput> = vec![];
+    let mut failed = vec![];
+    let mut skipped = vec![];
+    for level in levels {
+        let level = skip_blocked(level, &failed, &mut skipped);
+        if level.is_empty() {
+            continue;
+        }
+        let names = level
+            .iter()
+            .map(|member| member.name.as_str())
+            .collect::<Vec<_>>();
+        StdErr::working(&format!("Publishing {}", names.join(", ")));
+
+        // a worker that fails to start doesn't stop the ones that already are from finishing
+        let mut children = vec![];
+        for member in level.iter() {
+            match spawn(member, config, environment, flags) {
+                Ok(child) => children.push((member, child)),
+                Err(e) => {
+                    StdErr::user_error(&format!("Failed to publish {}: {}", member.name, e));
+                    failed.push(member.name.clone());
+                }
+            }
+        }
+        for (member, child) in children {
+            match child.wait_with_output() {
+                Ok(result) if result.status.success() => {
+                    StdErr::success(&format!("Published {}", styles::highlight(&member.name)));
+                    published.push(publish_output(&member.name, &result.stdout));
+                }
+                Ok(result) => {
+                    StdErr::user_error(&format!(
+                        "Failed to publish {}:\n{}",
+                        member.name,
+                        String::from_utf8_lossy(&result.stderr).trim_end()
+                    ));
+                    failed.push(member.name.clone());
+                }
+                Err(e) => {
+                    StdErr::user_error(&format!("Failed to publish {}: {}", member.name, e));
+                    failed.push(member.name.clone());
+                }
+            }
+        }
+    }
+
+    match output {
+        Output::Json => StdOut::as_json(&published),
+        Output::PlainText => {
+            for result in published.iter() {
+                let urls = if result.urls.is_empty() {
+                    "no routes".to_owned()
+                } else {
+                    result.urls.join(", ")
+                };
+                StdOut::info(&format!("{}: {}", result.name, urls));
+            }
+        }
+    }
+    if !skipped.is_empty() {
+        StdErr::info(&format!(
+            "Skipped {}, which depend on workers that failed to publish",
+            skipped.join(", ")
+        ));
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("Failed to publish {}", failed.join(", "))
+    }
+    Ok(())
+}
+
+/// Skips the members of a level that depend on a worker that failed or was skipped, and
+/// returns the ones left to publish.
+///
+/// The levels are published in order, so a worker that depends on a failed one through
+/// others is skipped too, as the ones in between were.
+fn skip_blocked(level: Vec<Member>, failed: &[String], skipped: &mut Vec<String>) -> Vec<Member> {
+    let (blocked, level): (Vec<_>, Vec<_>) = level.into_iter().partition(|member| {
+        member
+            .dependencies
+            .iter()
+            .any(|name| failed.contains(name) || skipped.contains(name))
+    });
+    skipped.extend(blocked.into_iter().map(|member| member.name));
+    level
+}
+
+/// Reads what a worker that published successfully printed with `--output json`.
+///
+/// Its build can print to stdout as well, so only the last line is the document. When it
+/// can't be read the worker was still published, so it's reported without its details.
+fn publish_output(name: &str, stdout: &[u8]) -> PublishOutput {
+    let stdout = String::from_utf8_lossy(stdout);
+    let document = stdout.lines().rev().find(|line| !line.trim().is_empty());
+    match document.map(serde_json::from_str::<PublishOutput>) {
+        Some(Ok(publish_output)) => publish_output,
+        _ => {
+            log::warn!("Failed to read the output of publishing {}", name);
+            PublishOutput {
+                success: true,
+                name: name.to_owned(),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Runs `wrangler publish` in the directory of a worker, so it builds the way it does on its own.
+///
+/// The workers of a level run at the same time, so they can't prompt for input.
+fn spawn(
+    member: &Member,
+    config: &OsStr,
+    environment: Option<&str>,
+    flags: &[String],
+) -> Result<Child> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .current_dir(&member.dir)
+        .args(&["publish", "--output", "json"])
+        .arg("--config")
+        .arg(config)
+        .args(flags)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(environment) = environment {
+        command.args(&["--env", environment]);
+    }
+    log::info!("Running {:?}", command);
+    Ok(command.spawn()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, dependencies: &[&str]) -> Member {
+        Member {
+            dir: PathBuf::from(name),
+            name: name.to_owned(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn it_publishes_services_before_the_workers_binding_them() {
+        let levels = levels(vec![
+            member("api", &["auth", "db"]),
+            member("auth", &["db"]),
+            member("db", &[]),
+            member("web", &[]),
+        ])
+        .unwrap();
+
+        let names = levels
+            .iter()
+            .map(|level| level.iter().map(|m| m.name.as_str()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![vec!["db", "web"], vec!["auth"], vec!["api"]]);
+    }
+
+    #[test]
+    fn it_skips_only_the_workers_depending_on_one_that_failed() {
+        let failed = vec!["db".to_owned()];
+        let mut skipped = vec![];
+
+        let level = skip_blocked(
+            vec![member("auth", &["db"]), member("web", &[])],
+            &failed,
+            &mut skipped,
+        );
+        assert_eq!(level.len(), 1);
+        assert_eq!(level[0].name, "web");
+
+        let level = skip_blocked(
+            vec![member("api", &["auth"]), member("cdn", &["web"])],
+            &failed,
+            &mut skipped,
+        );
+        assert_eq!(level.len(), 1);
+        assert_eq!(level[0].name, "cdn");
+        assert_eq!(skipped, vec!["auth", "api"]);
+    }
+
+    #[test]
+    fn it_reads_the_last_line_of_the_output_of_a_worker() {
+        let stdout = concat!(
+            "built in 2s\n",
+            r#"{"success":true,"name":"api","version":"1","urls":["https://api.example.com"],"routes":[],"workers_dev":null,"schedules":[],"size":null}"#,
+            "\n"
+        );
+        let published = publish_output("api", stdout.as_bytes());
+        assert_eq!(published.version.as_deref(), Some("1"));
+        assert_eq!(published.urls, vec!["https://api.example.com"]);
+
+        let published = publish_output("api", b"built in 2s\n");
+        assert!(published.success);
+        assert_eq!(published.name, "api");
+        assert!(published.urls.is_empty());
+    }
+
+    #[test]
+    fn it_errors_on_workers_binding_each_other() {
+        let error = levels(vec![
+            member("a", &["b"]),
+            member("b", &["a"]),
+            member("c", &[]),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("a, b"));
+    }
+}
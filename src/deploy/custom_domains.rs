@@ -0,0 +1,113 @@
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// The hostnames a script is attached to as custom domains, which Cloudflare
+/// creates the DNS records and certificates for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomDomainsTarget {
+    pub account_id: String,
+    pub script_name: String,
+    pub zone_id: Option<String>,
+    pub hostnames: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CustomDomainRequest<'a> {
+    hostname: &'a str,
+    service: &'a str,
+    environment: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zone_id: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct CustomDomainResponse {
+    result: CustomDomain,
+}
+
+#[derive(Deserialize)]
+struct CustomDomain {
+    hostname: String,
+    service: String,
+}
+
+impl CustomDomainsTarget {
+    pub fn build(
+        account_id: Option<String>,
+        script_name: String,
+        zone_id: Option<String>,
+        hostnames: Vec<String>,
+    ) -> Result<Self> {
+        for hostname in hostnames.iter() {
+            if hostname.contains('/') || hostname.contains('*') {
+                anyhow::bail!(
+                    "custom_domains are hostnames like api.example.com, without paths or wildcards, use routes for {}",
+                    hostname
+                )
+            }
+        }
+        let account_id = match account_id {
+            Some(account_id) => account_id,
+            None => anyhow::bail!(
+                "custom_domains can only be attached with an account_id, which is missing from wrangler.toml"
+            ),
+        };
+        Ok(Self {
+            account_id,
+            script_name,
+            zone_id,
+            hostnames,
+        })
+    }
+
+    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
+        log::info!("publishing custom domains");
+        let domains_addr = format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/domains",
+            self.account_id
+        );
+
+        let client = http::legacy_auth_client(user);
+
+        let mut urls = vec![];
+        for hostname in self.hostnames.iter() {
+            log::info!("Attaching {}...", hostname);
+            let res = client
+                .put(&domains_addr)
+                .json(&CustomDomainRequest {
+                    hostname,
+                    service: &self.script_name,
+                    environment: "production",
+                    zone_id: self.zone_id.as_deref(),
+                })
+                .send()?;
+
+            let status = res.status();
+            if !status.is_success() {
+                anyhow::bail!(
+                    "Could not attach {} to {}: {}",
+                    hostname,
+                    self.script_name,
+                    crate::format_api_errors(res.text()?)
+                )
+            }
+
+            // a hostname attached to another script is moved over, so check where it ended up
+            let domain: CustomDomainResponse = res.json()?;
+            if domain.result.service != self.script_name {
+                anyhow::bail!(
+                    "{} is attached to {} instead of {}",
+                    domain.result.hostname,
+                    domain.result.service,
+                    self.script_name
+                )
+            }
+            urls.push(format!("https://{}", domain.result.hostname));
+        }
+
+        Ok(urls)
+    }
+}
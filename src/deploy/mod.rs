@@ -1,11 +1,15 @@
+mod custom_domains;
+mod queue_consumers;
 mod schedule;
 mod zoned;
 mod zoneless;
 
 use anyhow::Result;
+pub use custom_domains::CustomDomainsTarget;
 use indicatif::{ProgressBar, ProgressStyle};
+pub use queue_consumers::QueueConsumersTarget;
 pub use schedule::ScheduleTarget;
-pub use zoned::ZonedTarget;
+pub use zoned::{create as create_route, fetch_all as fetch_routes, ZonedTarget};
 pub use zoneless::ZonelessTarget;
 
 use crate::settings::global_user::GlobalUser;
@@ -18,6 +22,8 @@ pub enum DeployTarget {
     Zoned(ZonedTarget),
     Zoneless(ZonelessTarget),
     Schedule(ScheduleTarget),
+    CustomDomains(CustomDomainsTarget),
+    QueueConsumers(QueueConsumersTarget),
 }
 
 pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<DeployResults> {
@@ -30,11 +36,13 @@ pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<Depl
             DeployTarget::Zoned(zoned) => {
                 spinner.set_message("Configuring routes...");
                 let route_urls = zoned.deploy(user)?;
+                results.routes.extend(route_urls.iter().cloned());
                 results.urls.extend(route_urls);
             }
             DeployTarget::Zoneless(zoneless) => {
                 spinner.set_message("Configuring workers.dev...");
                 let worker_dev = zoneless.deploy(user)?;
+                results.workers_dev = Some(worker_dev.clone());
                 results.urls.push(worker_dev);
             }
             DeployTarget::Schedule(schedule) => {
@@ -42,6 +50,16 @@ pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<Depl
                 let schedules = schedule.deploy(user)?;
                 results.schedules.extend(schedules);
             }
+            DeployTarget::CustomDomains(custom_domains) => {
+                spinner.set_message("Configuring custom domains...");
+                let domain_urls = custom_domains.deploy(user)?;
+                results.urls.extend(domain_urls);
+            }
+            DeployTarget::QueueConsumers(consumers) => {
+                spinner.set_message("Configuring queue consumers...");
+                let queues = consumers.deploy(user)?;
+                results.queues.extend(queues);
+            }
         }
     }
 
@@ -53,5 +71,9 @@ pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<Depl
 #[derive(Default)]
 pub struct DeployResults {
     pub urls: Vec<String>,
+    pub routes: Vec<String>,
+    pub workers_dev: Option<String>,
     pub schedules: Vec<String>,
+    /// the queues the script consumes
+    pub queues: Vec<String>,
 }
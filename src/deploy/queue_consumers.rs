@@ -0,0 +1,126 @@
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::QueueConsumer;
+
+use anyhow::Result;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// The queues a script consumes, which are attached to it once it is uploaded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueConsumersTarget {
+    pub account_id: String,
+    pub script_name: String,
+    pub consumers: Vec<QueueConsumer>,
+}
+
+#[derive(Serialize)]
+struct ConsumerRequest<'a> {
+    script_name: &'a str,
+    settings: ConsumerSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dead_letter_queue: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ConsumerSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch_size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_wait_time_ms: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct QueueResponse {
+    result: Queue,
+}
+
+#[derive(Deserialize)]
+struct Queue {
+    #[serde(default)]
+    consumers: Vec<Consumer>,
+}
+
+#[derive(Deserialize)]
+struct Consumer {
+    script_name: Option<String>,
+}
+
+impl QueueConsumersTarget {
+    pub fn build(
+        account_id: Option<String>,
+        script_name: String,
+        consumers: Vec<QueueConsumer>,
+    ) -> Self {
+        Self {
+            account_id: account_id.unwrap_or_default(),
+            script_name,
+            consumers,
+        }
+    }
+
+    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
+        log::info!("publishing queue consumers");
+        let client = http::legacy_auth_client(user);
+
+        let mut queues = vec![];
+        for consumer in self.consumers.iter() {
+            let queue_addr = format!(
+                "https://api.cloudflare.com/client/v4/accounts/{}/workers/queues/{}",
+                self.account_id, consumer.queue
+            );
+
+            let res = client.get(&queue_addr).send()?;
+            if res.status() == StatusCode::NOT_FOUND {
+                anyhow::bail!(
+                    "The queue {} doesn't exist, create it before consuming it",
+                    consumer.queue
+                )
+            }
+            if !res.status().is_success() {
+                anyhow::bail!(crate::format_api_errors(res.text()?))
+            }
+            let queue: QueueResponse = res.json()?;
+            let attached = queue
+                .result
+                .consumers
+                .iter()
+                .any(|existing| existing.script_name.as_deref() == Some(&self.script_name));
+
+            let body = ConsumerRequest {
+                script_name: &self.script_name,
+                settings: ConsumerSettings {
+                    batch_size: consumer.max_batch_size,
+                    max_retries: consumer.max_retries,
+                    max_wait_time_ms: consumer.max_batch_timeout.map(|seconds| seconds * 1000),
+                },
+                dead_letter_queue: consumer.dead_letter_queue.as_deref(),
+            };
+            log::info!("Attaching {}...", consumer.queue);
+            let res = if attached {
+                client
+                    .put(&format!("{}/consumers/{}", queue_addr, self.script_name))
+                    .json(&body)
+                    .send()?
+            } else {
+                client
+                    .post(&format!("{}/consumers", queue_addr))
+                    .json(&body)
+                    .send()?
+            };
+            if !res.status().is_success() {
+                anyhow::bail!(
+                    "Could not attach {} to {}: {}",
+                    consumer.queue,
+                    self.script_name,
+                    crate::format_api_errors(res.text()?)
+                )
+            }
+            queues.push(consumer.queue.clone());
+        }
+
+        Ok(queues)
+    }
+}
@@ -18,6 +18,17 @@ impl ScheduleTarget {
     ) -> Result<Self> {
         // TODO: add validation for expressions before pushing them to the API
         // we can do this once the cron parser is open sourced
+        // until then, catch the expressions that are missing a field or have one too many
+        for cron in crons.iter() {
+            let fields = cron.split_whitespace().count();
+            if fields != 5 {
+                anyhow::bail!(
+                    "The cron trigger \"{}\" has {} fields instead of 5 (minute, hour, day of the month, month and day of the week)",
+                    cron,
+                    fields
+                )
+            }
+        }
         Ok(Self {
             account_id: account_id.unwrap_or_default(),
             script_name,
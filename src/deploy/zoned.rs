@@ -82,10 +82,12 @@ pub fn publish_routes(
     Ok(deployed_routes)
 }
 
-fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
+pub fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
     let client = http::cf_v4_client(user)?;
 
-    let routes: Vec<Route> = match client.request(&ListRoutes { zone_identifier }) {
+    let routes: Vec<Route> = match http::retry::api("Listing routes", || {
+        client.request(&ListRoutes { zone_identifier })
+    }) {
         Ok(success) => success.result.iter().map(Route::from).collect(),
         Err(e) => anyhow::bail!("{}", http::format_error(e, None)), // TODO: add suggestion fn
     };
@@ -93,16 +95,18 @@ fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
     Ok(routes)
 }
 
-fn create(user: &GlobalUser, zone_identifier: &str, route: &Route) -> Result<Route> {
+pub fn create(user: &GlobalUser, zone_identifier: &str, route: &Route) -> Result<Route> {
     let client = http::cf_v4_client(user)?;
 
     log::info!("Creating your route {:#?}", &route.pattern,);
-    match client.request(&CreateRoute {
-        zone_identifier,
-        params: CreateRouteParams {
-            pattern: route.pattern.clone(),
-            script: route.script.clone(),
-        },
+    match http::retry::api_non_idempotent(&format!("Creating the route {}", route.pattern), || {
+        client.request(&CreateRoute {
+            zone_identifier,
+            params: CreateRouteParams {
+                pattern: route.pattern.clone(),
+                script: route.script.clone(),
+            },
+        })
     }) {
         Ok(response) => Ok(Route {
             id: Some(response.result.id),
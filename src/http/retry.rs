@@ -0,0 +1,168 @@
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use cloudflare::framework::response::ApiFailure;
+use http::StatusCode;
+use rand::Rng;
+use reqwest::blocking::{RequestBuilder, Response};
+
+use crate::terminal::message::{Message, StdErr};
+
+/// How many times a request is sent before its last failure is returned.
+pub const MAX_ATTEMPTS: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(16);
+
+/// Whether a failed request is worth sending again, as rate limits and server errors
+/// usually clear up on their own.
+pub fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Whether a failed request that isn't idempotent, such as a `POST` that creates something,
+/// is worth sending again. A server error can come after the request was handled, so only
+/// a rate limit is.
+fn is_rate_limit(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// How long to wait before sending a request again after `attempt` failures: twice as long
+/// as the last time, with jitter so that parallel deploys don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_DELAY
+        .checked_mul(1 << attempt.saturating_sub(1).min(16))
+        .map_or(MAX_DELAY, |delay| delay.min(MAX_DELAY));
+    // anywhere between half and all of the delay
+    delay / 2 + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+fn wait(description: &str, status: StatusCode, attempt: u32, delay: Duration) {
+    StdErr::warn(&format!(
+        "{} failed with {}, retrying in {:.1}s (attempt {} of {})",
+        description,
+        status,
+        delay.as_secs_f64(),
+        attempt + 1,
+        MAX_ATTEMPTS
+    ));
+    thread::sleep(delay);
+}
+
+/// Sends a request until it gets a response that isn't a rate limit or a server error, or
+/// runs out of attempts. The request is built again for every attempt, as a multipart body
+/// can only be sent once.
+pub fn send<F>(description: &str, request: F) -> Result<Response>
+where
+    F: FnMut() -> Result<RequestBuilder>,
+{
+    send_while(description, request, is_transient)
+}
+
+/// Sends a request that isn't idempotent, which is only sent again when it was rate limited.
+pub fn send_non_idempotent<F>(description: &str, request: F) -> Result<Response>
+where
+    F: FnMut() -> Result<RequestBuilder>,
+{
+    send_while(description, request, is_rate_limit)
+}
+
+fn send_while<F>(
+    description: &str,
+    mut request: F,
+    retry: fn(StatusCode) -> bool,
+) -> Result<Response>
+where
+    F: FnMut() -> Result<RequestBuilder>,
+{
+    let mut attempt = 1;
+    loop {
+        let response = request()?.send()?;
+        let status = response.status();
+        if !retry(status) || attempt >= MAX_ATTEMPTS {
+            return Ok(response);
+        }
+
+        // a rate limited response says how long to wait for
+        let delay = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs)
+            .filter(|delay| *delay <= MAX_DELAY)
+            .unwrap_or_else(|| backoff(attempt));
+        wait(description, status, attempt, delay);
+        attempt += 1;
+    }
+}
+
+/// Sends a request through the Cloudflare API client until it succeeds or fails with
+/// something other than a rate limit or a server error, or runs out of attempts.
+pub fn api<T, F>(description: &str, request: F) -> Result<T, ApiFailure>
+where
+    F: FnMut() -> Result<T, ApiFailure>,
+{
+    api_while(description, request, is_transient)
+}
+
+/// Sends a request that isn't idempotent through the Cloudflare API client, which is only
+/// sent again when it was rate limited.
+pub fn api_non_idempotent<T, F>(description: &str, request: F) -> Result<T, ApiFailure>
+where
+    F: FnMut() -> Result<T, ApiFailure>,
+{
+    api_while(description, request, is_rate_limit)
+}
+
+fn api_while<T, F>(
+    description: &str,
+    mut request: F,
+    retry: fn(StatusCode) -> bool,
+) -> Result<T, ApiFailure>
+where
+    F: FnMut() -> Result<T, ApiFailure>,
+{
+    let mut attempt = 1;
+    loop {
+        match request() {
+            Err(ApiFailure::Error(status, _)) if retry(status) && attempt < MAX_ATTEMPTS => {
+                wait(description, status, attempt, backoff(attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_retries_rate_limits_and_server_errors() {
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient(StatusCode::BAD_GATEWAY));
+        assert!(is_transient(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_transient(StatusCode::BAD_REQUEST));
+        assert!(!is_transient(StatusCode::FORBIDDEN));
+        assert!(!is_transient(StatusCode::OK));
+    }
+
+    #[test]
+    fn it_only_retries_rate_limits_of_requests_that_are_not_idempotent() {
+        assert!(is_rate_limit(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_rate_limit(StatusCode::BAD_GATEWAY));
+        assert!(!is_rate_limit(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        for attempt in 1..=MAX_ATTEMPTS {
+            let delay = BASE_DELAY * (1 << (attempt - 1));
+            let backoff = backoff(attempt);
+            assert!(backoff >= delay / 2 && backoff <= delay);
+        }
+        assert!(backoff(64) <= MAX_DELAY);
+    }
+}
@@ -1,7 +1,13 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 
 use anyhow::Result;
 use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use serde_json::value::Value as JsonValue;
 
 use cloudflare::endpoints::workerskv::delete_bulk::DeleteBulk;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
@@ -11,6 +17,7 @@ use cloudflare::framework::auth::Credentials;
 use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
 
 use crate::commands::kv::format_error;
+use crate::http;
 use crate::http::feature::headers;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
@@ -20,6 +27,41 @@ const API_MAX_PAIRS: usize = 10000;
 // hammering it with large requests.
 pub const BATCH_KEY_MAX: usize = API_MAX_PAIRS / 2;
 const UPLOAD_MAX_SIZE: usize = 50 * 1024 * 1024;
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A key-value pair of a bulk upload. Unlike `KeyValuePair` it has the metadata of the
+/// key, which the Cloudflare API client can't upload.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BulkPair {
+    pub key: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_ttl: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<bool>,
+}
+
+/// How much of the size limit of a bulk upload a pair takes up.
+pub trait UploadSize {
+    fn upload_size(&self) -> usize;
+}
+
+impl UploadSize for KeyValuePair {
+    fn upload_size(&self) -> usize {
+        self.key.len() + self.value.len()
+    }
+}
+
+impl UploadSize for BulkPair {
+    fn upload_size(&self) -> usize {
+        let metadata = self.metadata.as_ref().map(|m| m.to_string().len());
+        self.key.len() + self.value.len() + metadata.unwrap_or(0)
+    }
+}
 
 // Create a special API client that has a longer timeout than usual, given that KV operations
 // can be lengthy if payloads are large.
@@ -63,6 +105,100 @@ pub fn put(
     Ok(())
 }
 
+/// Uploads batches of key-value pairs on several threads at once. `on_uploaded` is called
+/// with the index of each batch once it is uploaded, and no more batches are started after
+/// one fails.
+pub fn put_batches(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    batches: Vec<(usize, Vec<BulkPair>)>,
+    concurrency: usize,
+    progress_bar: &Option<ProgressBar>,
+    mut on_uploaded: impl FnMut(usize) -> Result<()>,
+) -> Result<()> {
+    let api_endpoint = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/bulk",
+        target.account_id.load()?,
+        namespace_id
+    );
+    let queue = Arc::new(Mutex::new(batches));
+    let failed = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = channel();
+
+    let workers = (0..concurrency.max(1))
+        .map(|_| {
+            let (client, api_endpoint) = (http::legacy_auth_client(user), api_endpoint.clone());
+            let (queue, failed, tx) = (queue.clone(), failed.clone(), tx.clone());
+            thread::spawn(move || {
+                while !failed.load(Ordering::SeqCst) {
+                    let batch = queue
+                        .lock()
+                        .expect("the queue of batches is poisoned")
+                        .pop();
+                    let (index, pairs) = match batch {
+                        Some(batch) => batch,
+                        None => break,
+                    };
+                    let result =
+                        upload(&client, &api_endpoint, &pairs).map(|_| (index, pairs.len()));
+                    if result.is_err() {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    if tx.send(result).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    // the receiver stops once every worker has dropped its sender
+    drop(tx);
+
+    let mut error = None;
+    for result in rx {
+        match result {
+            Ok((index, len)) => {
+                if let Some(pb) = &progress_bar {
+                    pb.inc(len as u64);
+                }
+                if let Err(e) = on_uploaded(index) {
+                    failed.store(true, Ordering::SeqCst);
+                    error.get_or_insert(e);
+                }
+            }
+            Err(e) => {
+                failed.store(true, Ordering::SeqCst);
+                error.get_or_insert(e);
+            }
+        }
+    }
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("a bulk upload thread panicked"))?;
+    }
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn upload(
+    client: &reqwest::blocking::Client,
+    api_endpoint: &str,
+    pairs: &[BulkPair],
+) -> Result<()> {
+    let res = http::retry::send("Uploading a batch of key value pairs", || {
+        Ok(client.put(api_endpoint).json(pairs).timeout(UPLOAD_TIMEOUT))
+    })?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?))
+    }
+    Ok(())
+}
+
 pub fn delete(
     target: &Target,
     user: &GlobalUser,
@@ -90,8 +226,8 @@ pub fn delete(
     Ok(())
 }
 
-fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
-    let mut batches: Vec<Vec<KeyValuePair>> = Vec::new();
+pub fn batch_keys_values<T: Clone + UploadSize>(mut pairs: Vec<T>) -> Vec<Vec<T>> {
+    let mut batches: Vec<Vec<T>> = Vec::new();
 
     if !pairs.is_empty() {
         // Iterate over all key-value pairs and create batches of uploads, each of which are
@@ -99,7 +235,7 @@ fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
         // as it is created.
         let mut key_count = 0;
         let mut key_pair_bytes = 0;
-        let mut key_value_batch: Vec<KeyValuePair> = Vec::new();
+        let mut key_value_batch: Vec<T> = Vec::new();
 
         while !(pairs.is_empty() && key_value_batch.is_empty()) {
             if pairs.is_empty() {
@@ -110,7 +246,7 @@ fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
                 let pair = pairs.pop().unwrap();
                 if key_count + 1 > BATCH_KEY_MAX
                 // Keep upload size small to keep KV bulk API happy
-                || key_pair_bytes + pair.key.len() + pair.value.len() > UPLOAD_MAX_SIZE
+                || key_pair_bytes + pair.upload_size() > UPLOAD_MAX_SIZE
                 {
                     batches.push(key_value_batch.to_vec());
                     key_count = 0;
@@ -120,7 +256,7 @@ fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
 
                 // Add the popped key-value pair to the running batch of key-value pair uploads
                 key_count += 1;
-                key_pair_bytes = key_pair_bytes + pair.key.len() + pair.value.len();
+                key_pair_bytes += pair.upload_size();
                 key_value_batch.push(pair);
             }
         }
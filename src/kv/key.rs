@@ -1,21 +1,35 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use serde_json::value::Value as JsonValue;
 
-use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeys;
-use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeysParams;
-use cloudflare::endpoints::workerskv::Key;
-use cloudflare::framework::apiclient::ApiClient;
-use cloudflare::framework::response::ApiFailure;
-use cloudflare::framework::HttpApiClient;
-
+use crate::http;
+use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 
+/// A key as the API lists it, including its metadata.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ListedKey {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonValue>,
+}
+
+#[derive(Deserialize)]
+struct ListedKeys {
+    result: Vec<ListedKey>,
+    result_info: Option<JsonValue>,
+}
+
+/// Lists the keys of a namespace, fetching the next page of keys once the last one was
+/// iterated over.
 pub struct KeyList {
-    keys_result: Option<Vec<Key>>,
+    client: reqwest::blocking::Client,
+    api_endpoint: String,
     prefix: Option<String>,
-    client: HttpApiClient,
-    account_id: String,
-    namespace_id: String,
+    limit: Option<u32>,
+    keys: std::vec::IntoIter<ListedKey>,
     cursor: Option<String>,
     init_fetch: bool,
 }
@@ -23,80 +37,87 @@ pub struct KeyList {
 impl KeyList {
     pub fn new(
         target: &Target,
-        client: HttpApiClient,
+        user: &GlobalUser,
         namespace_id: &str,
         prefix: Option<&str>,
     ) -> Result<KeyList> {
         let iter = KeyList {
-            keys_result: None,
+            client: http::legacy_auth_client(user),
+            api_endpoint: format!(
+                "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/keys",
+                target.account_id.load()?,
+                namespace_id
+            ),
             prefix: prefix.map(str::to_string),
-            client,
-            account_id: target.account_id.load()?.to_owned(),
-            namespace_id: namespace_id.to_string(),
+            limit: None,
+            keys: Vec::new().into_iter(),
             cursor: None,
             init_fetch: false,
         };
         Ok(iter)
     }
 
-    fn request_params(&self) -> ListNamespaceKeys {
-        let params = ListNamespaceKeysParams {
-            limit: None, // Defaults to 1000 (the maximum)
-            cursor: self.cursor.to_owned(),
-            prefix: self.prefix.to_owned(),
-        };
+    /// Starts listing at a cursor returned with an earlier page, instead of at the first key.
+    pub fn from_cursor(mut self, cursor: Option<&str>) -> KeyList {
+        self.cursor = cursor.map(str::to_string);
+        self
+    }
 
-        ListNamespaceKeys {
-            account_identifier: &self.account_id,
-            namespace_identifier: &self.namespace_id,
-            params,
-        }
+    /// Lists a single page of this many keys, instead of every key.
+    pub fn limit(mut self, limit: Option<u32>) -> KeyList {
+        self.limit = limit;
+        self
     }
 
-    fn get_batch(&mut self) -> Result<Vec<Key>, ApiFailure> {
-        let response = self.client.request(&self.request_params());
+    /// The cursor of the keys after the ones listed so far, when there are more of them.
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
 
-        match response {
-            Ok(success) => {
-                self.cursor = extract_cursor(success.result_info.clone());
-                log::info!("{:?}", self.cursor);
-                Ok(success.result)
-            }
-            Err(e) => Err(e),
+    /// Lists the next page of keys, or `None` when there are no pages left to list.
+    pub fn next_page(&mut self) -> Result<Option<Vec<ListedKey>>> {
+        if self.init_fetch && (self.cursor.is_none() || self.limit.is_some()) {
+            return Ok(None);
+        }
+        self.init_fetch = true;
+
+        let mut query = vec![];
+        if let Some(prefix) = &self.prefix {
+            query.push(("prefix", prefix.to_owned()));
+        }
+        if let Some(cursor) = &self.cursor {
+            query.push(("cursor", cursor.to_owned()));
         }
+        if let Some(limit) = self.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        let res = http::retry::send("Listing keys", || {
+            Ok(self.client.get(&self.api_endpoint).query(&query))
+        })?;
+        if !res.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(res.text()?))
+        }
+
+        let listed: ListedKeys = res.json()?;
+        self.cursor = extract_cursor(listed.result_info);
+        log::info!("{:?}", self.cursor);
+        Ok(Some(listed.result))
     }
 }
 
 impl Iterator for KeyList {
-    type Item = Result<Key, ApiFailure>;
+    type Item = Result<ListedKey>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Attempt to extract next key from vector of keys in KeyList.
-        // If no key vector or no keys left, go to fallback case below to
-        // attempt to fetch the next page of keys from the Workers KV API.
-        if let Some(mut keys) = self.keys_result.to_owned() {
-            let key = keys.pop();
-            self.keys_result = Some(keys);
-
-            if let Some(k) = key {
-                return Some(Ok(k));
+        // a page can be empty while there are more pages after it
+        loop {
+            if let Some(key) = self.keys.next() {
+                return Some(Ok(key));
             }
-        }
-        // Fallback case (if no remaining keys are found)
-        if self.cursor.is_none() && self.init_fetch {
-            None // Nothing left to fetch
-        } else {
-            if !self.init_fetch {
-                // At this point, initial fetch is being performed.
-                self.init_fetch = true;
-            }
-            match self.get_batch() {
-                Ok(mut keys) => {
-                    let key = keys.pop();
-                    self.keys_result = Some(keys);
-                    key.map(Ok)
-                }
-                Err(e) => Some(Err(e)),
+            match self.next_page() {
+                Ok(Some(keys)) => self.keys = keys.into_iter(),
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
             }
         }
     }
@@ -104,12 +125,64 @@ impl Iterator for KeyList {
 
 // Returns Some(cursor) if cursor is non-empty, otherwise returns None.
 fn extract_cursor(result_info: Option<JsonValue>) -> Option<String> {
-    let result_info = result_info.unwrap();
-    let returned_cursor_value = &result_info["cursor"];
-    let returned_cursor = returned_cursor_value.as_str().unwrap().to_string();
-    if returned_cursor.is_empty() {
-        None
-    } else {
-        Some(returned_cursor)
+    result_info
+        .as_ref()
+        .and_then(|info| info["cursor"].as_str())
+        .filter(|cursor| !cursor.is_empty())
+        .map(str::to_owned)
+}
+
+/// Reads the value of a key as bytes, or `None` when the key doesn't exist (anymore).
+pub fn get_value(
+    target: &Target,
+    client: &reqwest::blocking::Client,
+    namespace_id: &str,
+    key: &str,
+) -> Result<Option<Vec<u8>>> {
+    let api_endpoint = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
+        target.account_id.load()?,
+        namespace_id,
+        crate::commands::kv::url_encode_key(key)
+    );
+    let res = http::retry::send(&format!("Reading the value of {}", key), || {
+        Ok(client.get(&api_endpoint))
+    })?;
+    match res.status() {
+        reqwest::StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(res.bytes()?.to_vec())),
+        _ => anyhow::bail!(crate::format_api_errors(res.text()?)),
+    }
+}
+
+/// Turns a value into a string for the bulk API, encoding values that aren't text as
+/// base64 and saying so in the second field.
+pub fn encode_value(value: Vec<u8>) -> (String, Option<bool>) {
+    match String::from_utf8(value) {
+        Ok(value) => (value, None),
+        Err(e) => (base64::encode(e.as_bytes()), Some(true)),
+    }
+}
+
+/// Reads the metadata of a key, which is `None` when the key has none.
+pub fn get_metadata(
+    target: &Target,
+    client: &reqwest::blocking::Client,
+    namespace_id: &str,
+    key: &str,
+) -> Result<Option<JsonValue>> {
+    let api_endpoint = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/metadata/{}",
+        target.account_id.load()?,
+        namespace_id,
+        crate::commands::kv::url_encode_key(key)
+    );
+    let res = http::retry::send(&format!("Reading the metadata of {}", key), || {
+        Ok(client.get(&api_endpoint))
+    })?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?))
     }
+    let body: JsonValue = res.json()?;
+    Ok(Some(body["result"].clone()).filter(|metadata| !metadata.is_null()))
 }
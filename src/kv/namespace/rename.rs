@@ -0,0 +1,19 @@
+use cloudflare::endpoints::workerskv::rename_namespace::RenameNamespace;
+use cloudflare::endpoints::workerskv::rename_namespace::RenameNamespaceParams;
+use cloudflare::framework::apiclient::ApiClient;
+use cloudflare::framework::response::{ApiFailure, ApiSuccess};
+
+pub fn rename(
+    client: &impl ApiClient,
+    account_id: &str,
+    id: &str,
+    title: &str,
+) -> Result<ApiSuccess<()>, ApiFailure> {
+    client.request(&RenameNamespace {
+        account_identifier: account_id,
+        namespace_identifier: id,
+        params: RenameNamespaceParams {
+            title: title.to_string(),
+        },
+    })
+}
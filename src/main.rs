@@ -59,6 +59,10 @@ fn run() -> Result<()> {
     let cli = Cli::from_args();
     let cli_params = cli.clone();
 
+    if cli.environments.len() > 1 && !matches!(cli.command, Command::Publish(_)) {
+        anyhow::bail!("Only `wrangler publish` can be given more than one --env");
+    }
+
     match cli.command {
         Command::Config { api_key, no_verify } => exec::configure(api_key, no_verify),
         Command::Generate {
@@ -80,57 +84,27 @@ fn run() -> Result<()> {
             watch,
             headless,
         } => exec::preview(method, url, body, watch, headless, &cli_params),
-        Command::Dev {
-            host,
-            ip,
-            port,
-            local_protocol,
-            upstream_protocol,
-        } => exec::dev(
-            host,
-            ip,
-            port,
-            local_protocol,
-            upstream_protocol,
-            &cli_params,
-        ),
+        Command::Dev(dev) => exec::dev(dev, &cli_params),
         Command::Whoami => exec::whoami(),
-        Command::Publish {
-            release,
-            output,
-            migration,
-        } => exec::publish(release, output, migration, &cli_params),
+        Command::Publish(publish) => exec::publish(publish, &cli_params),
+        Command::Versions(versions) => exec::versions(versions, &cli_params),
+        Command::Deployments { output } => exec::deployments(output, &cli_params),
+        Command::Rollback { deployment_id } => exec::rollback(deployment_id, &cli_params),
+        Command::Diff { output } => exec::diff(output, &cli_params),
+        Command::Delete {
+            routes,
+            durable_objects,
+            yes,
+        } => exec::delete(routes, durable_objects, yes, &cli_params),
         Command::Subdomain { name } => exec::subdomain(name, &cli_params),
         Command::Route(route) => exec::route(route, &cli_params),
+        Command::Triggers(triggers) => exec::triggers(triggers, &cli_params),
+        Command::DispatchNamespace(namespace) => exec::dispatch_namespace(namespace, &cli_params),
         Command::Secret(secret) => exec::secret(secret, &cli_params),
         Command::KvNamespace(namespace) => exec::kv_namespace(namespace, &cli_params),
         Command::KvKey(key) => exec::kv_key(key, &cli_params),
         Command::KvBulk(bulk) => exec::kv_bulk(bulk, &cli_params),
-        Command::Tail {
-            name,
-            url,
-            format,
-            once,
-            sampling_rate,
-            status,
-            method,
-            header,
-            ip_address,
-            search,
-            ..
-        } => exec::tail(
-            name,
-            url,
-            format,
-            once,
-            sampling_rate,
-            status,
-            method,
-            header,
-            ip_address,
-            search,
-            &cli_params,
-        ),
+        Command::Tail(tail) => exec::tail(tail, &cli_params),
         Command::Login => commands::login::run(),
         Command::Report { log } => commands::report::run(log.as_deref()).map(|_| {
             eprintln!("Report submission sucessful. Thank you!");
@@ -26,6 +26,20 @@ pub enum Binding {
         name: String,
         text: String,
     },
+    Service {
+        name: String,
+        service: String,
+        environment: String,
+    },
+    Queue {
+        name: String,
+        queue_name: String,
+    },
+    AnalyticsEngine {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dataset: Option<String>,
+    },
 }
 
 impl Binding {
@@ -56,4 +70,20 @@ impl Binding {
     pub fn new_plain_text(name: String, text: String) -> Binding {
         Binding::PlainText { name, text }
     }
+
+    pub fn new_service(name: String, service: String, environment: String) -> Binding {
+        Binding::Service {
+            name,
+            service,
+            environment,
+        }
+    }
+
+    pub fn new_queue(name: String, queue_name: String) -> Binding {
+        Binding::Queue { name, queue_name }
+    }
+
+    pub fn new_analytics_engine(name: String, dataset: Option<String>) -> Binding {
+        Binding::AnalyticsEngine { name, dataset }
+    }
 }
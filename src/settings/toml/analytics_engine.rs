@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// A binding to a Workers Analytics Engine dataset the script writes data points to.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AnalyticsEngineDataset {
+    pub binding: String,
+    /// the dataset written to, named after the script when left out
+    pub dataset: Option<String>,
+}
+
+impl AnalyticsEngineDataset {
+    pub fn binding(&self) -> Binding {
+        Binding::new_analytics_engine(self.binding.clone(), self.dataset.clone())
+    }
+}
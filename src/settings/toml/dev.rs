@@ -1,6 +1,8 @@
 use crate::commands::dev::Protocol;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::net::IpAddr;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -9,4 +11,7 @@ pub struct Dev {
     pub port: Option<u16>,
     pub local_protocol: Option<Protocol>,
     pub upstream_protocol: Option<Protocol>,
+    pub persist_to: Option<PathBuf>,
+    /// The properties of `request.cf` to simulate with `wrangler dev --local`
+    pub cf: Option<Map<String, Value>>,
 }
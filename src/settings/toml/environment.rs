@@ -4,12 +4,18 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
+use crate::settings::toml::analytics_engine::AnalyticsEngineDataset;
 use crate::settings::toml::builder::Builder;
 use crate::settings::toml::durable_objects::DurableObjects;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+use crate::settings::toml::queues::Queues;
 use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::services::ServiceBinding;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::triggers::Triggers;
+use crate::settings::toml::unsafe_config::Unsafe;
+use crate::settings::toml::UsageModel;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Environment {
@@ -20,6 +26,7 @@ pub struct Environment {
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
     pub routes: Option<Vec<String>>,
+    pub custom_domains: Option<Vec<String>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
@@ -32,6 +39,19 @@ pub struct Environment {
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
+    pub services: Option<Vec<ServiceBinding>>,
+    pub analytics_engine_datasets: Option<Vec<AnalyticsEngineDataset>>,
+    pub queues: Option<Queues>,
+    #[serde(rename = "unsafe")]
+    pub unsafe_config: Option<Unsafe>,
+    pub compatibility_date: Option<String>,
+    pub compatibility_flags: Option<Vec<String>>,
+    pub upload_source_maps: Option<bool>,
+    #[serde(default, with = "string_empty_as_none")]
+    pub usage_model: Option<UsageModel>,
+    pub hooks: Option<Hooks>,
+    pub keep_vars: Option<bool>,
+    pub minify: Option<bool>,
 }
 
 impl Environment {
@@ -0,0 +1,29 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// Commands run around `wrangler publish`, such as to purge a cache once a worker is live.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Hooks {
+    /// runs before the worker is built, and stops the publish when it fails
+    pub pre_publish: Option<String>,
+    /// runs once the worker is uploaded and deployed
+    pub post_publish: Option<String>,
+}
+
+impl Hooks {
+    pub fn command(cmd: &str) -> Command {
+        if cfg!(target_os = "windows") {
+            let args: Vec<&str> = cmd.split_whitespace().collect();
+            let mut c = Command::new("cmd");
+            c.arg("/C");
+            c.args(args.as_slice());
+            c
+        } else {
+            let mut c = Command::new("sh");
+            c.arg("-c");
+            c.arg(cmd);
+            c
+        }
+    }
+}
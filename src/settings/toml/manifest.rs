@@ -16,15 +16,21 @@ use crate::commands::whoami::fetch_accounts;
 use crate::commands::{validate_worker_name, whoami, DEFAULT_CONFIG_PATH};
 use crate::deploy::{self, DeployTarget, DeploymentSet};
 use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::analytics_engine::AnalyticsEngineDataset;
 use crate::settings::toml::builder::Builder;
 use crate::settings::toml::dev::Dev;
 use crate::settings::toml::durable_objects::DurableObjects;
 use crate::settings::toml::environment::Environment;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::{ConfigKvNamespace, KvNamespace};
+use crate::settings::toml::migrations::{MigrationConfig, Migrations};
+use crate::settings::toml::queues::Queues;
 use crate::settings::toml::route::RouteConfig;
+use crate::settings::toml::services::ServiceBinding;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::target_type::TargetType;
 use crate::settings::toml::triggers::Triggers;
+use crate::settings::toml::unsafe_config::Unsafe;
 use crate::settings::toml::Target;
 use crate::terminal::{
     emoji,
@@ -44,6 +50,7 @@ pub struct Manifest {
     #[serde(default, with = "string_empty_as_none")]
     pub route: Option<String>,
     pub routes: Option<Vec<String>>,
+    pub custom_domains: Option<Vec<String>>,
     #[serde(default, with = "string_empty_as_none")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
@@ -61,11 +68,21 @@ pub struct Manifest {
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
+    pub services: Option<Vec<ServiceBinding>>,
+    pub analytics_engine_datasets: Option<Vec<AnalyticsEngineDataset>>,
+    pub queues: Option<Queues>,
+    #[serde(rename = "unsafe")]
+    pub unsafe_config: Option<Unsafe>,
+    pub migrations: Option<Vec<MigrationConfig>>,
     #[serde(default, with = "string_empty_as_none")]
     pub usage_model: Option<UsageModel>,
     pub compatibility_date: Option<String>,
     #[serde(default)]
     pub compatibility_flags: Vec<String>,
+    pub upload_source_maps: Option<bool>,
+    pub hooks: Option<Hooks>,
+    pub keep_vars: Option<bool>,
+    pub minify: Option<bool>,
 }
 
 impl Manifest {
@@ -260,6 +277,94 @@ impl Manifest {
             add_routed_deployments(&self.route_config())
         }?;
 
+        deployments.extend(self.get_deployments_without_routes(&script, env)?);
+
+        // custom domains are not inherited, as a hostname can only be attached to one script
+        let custom_domains = match env {
+            Some(e) => e
+                .custom_domains
+                .as_ref()
+                .map(|domains| (domains, e.account_id.as_ref(), e.zone_id.as_ref())),
+            None => self
+                .custom_domains
+                .as_ref()
+                .map(|domains| (domains, None, None)),
+        };
+
+        if let Some((hostnames, account_id, zone_id)) = custom_domains {
+            let account_id = account_id.or_else(|| self.account_id.if_present());
+            let zone_id = zone_id.or_else(|| self.zone_id.as_ref());
+            let custom_domains = deploy::CustomDomainsTarget::build(
+                account_id.cloned(),
+                script.clone(),
+                zone_id.cloned(),
+                hostnames.clone(),
+            )?;
+            deployments.push(DeployTarget::CustomDomains(custom_domains));
+        }
+
+        let durable_objects = match env {
+            Some(e) => e.durable_objects.as_ref(),
+            None => self.durable_objects.as_ref(),
+        };
+
+        if durable_objects.is_none() && deployments.is_empty() {
+            anyhow::bail!("Please specify your deployment routes or `workers_dev = true` inside of your configuration file, or pass --no-routes to upload the script alone. For more information, see: https://developers.cloudflare.com/workers/cli-wrangler/configuration#keys")
+        }
+
+        Ok(deployments)
+    }
+
+    /// The deployments of a publish that leaves the routes and the workers.dev subdomain
+    /// of the script as they are, which are its crons and the queues it consumes.
+    pub fn get_unrouted_deployments(&self, env: Option<&str>) -> Result<DeploymentSet> {
+        let script = self.worker_name(env);
+        validate_worker_name(&script)?;
+
+        let env_config = self.get_environment(env)?;
+        self.get_deployments_without_routes(&script, env_config)
+    }
+
+    fn get_deployments_without_routes(
+        &self,
+        script: &str,
+        env: Option<&Environment>,
+    ) -> Result<DeploymentSet> {
+        let mut deployments = DeploymentSet::new();
+        if let Some(scheduled) = self.get_schedule(script, env)? {
+            deployments.push(DeployTarget::Schedule(scheduled));
+        }
+
+        let queues = self.get_queues(env);
+        if !queues.consumers.is_empty() {
+            let account_id = env
+                .and_then(|e| e.account_id.as_ref())
+                .or_else(|| self.account_id.if_present());
+            deployments.push(DeployTarget::QueueConsumers(
+                deploy::QueueConsumersTarget::build(
+                    account_id.cloned(),
+                    script.to_owned(),
+                    queues.consumers,
+                ),
+            ));
+        }
+        Ok(deployments)
+    }
+
+    // queues are not inherited, as each message goes to just one of the consumers
+    fn get_queues(&self, env: Option<&Environment>) -> Queues {
+        match env {
+            Some(e) => e.queues.clone(),
+            None => self.queues.clone(),
+        }
+        .unwrap_or_default()
+    }
+
+    fn get_schedule(
+        &self,
+        script: &str,
+        env: Option<&Environment>,
+    ) -> Result<Option<deploy::ScheduleTarget>> {
         let crons = match env {
             Some(e) => {
                 let account_id = e
@@ -277,22 +382,14 @@ impl Manifest {
                 .map(|t| (t.crons.as_slice(), self.account_id.if_present())),
         };
 
-        if let Some((crons, account)) = crons {
-            let scheduled =
-                deploy::ScheduleTarget::build(account.cloned(), script.clone(), crons.to_vec())?;
-            deployments.push(DeployTarget::Schedule(scheduled));
-        }
-
-        let durable_objects = match env {
-            Some(e) => e.durable_objects.as_ref(),
-            None => self.durable_objects.as_ref(),
-        };
-
-        if durable_objects.is_none() && deployments.is_empty() {
-            anyhow::bail!("Please specify your deployment routes or `workers_dev = true` inside of your configuration file. For more information, see: https://developers.cloudflare.com/workers/cli-wrangler/configuration#keys")
+        match crons {
+            Some((crons, account)) => Ok(Some(deploy::ScheduleTarget::build(
+                account.cloned(),
+                script.to_owned(),
+                crons.to_vec(),
+            )?)),
+            None => Ok(None),
         }
-
-        Ok(deployments)
     }
 
     pub fn get_account_id(&self, environment_name: Option<&str>) -> Result<String> {
@@ -331,6 +428,12 @@ impl Manifest {
             }
         }
 
+        let analytics_engine_datasets = self.analytics_engine_datasets.clone().unwrap_or_default();
+        let queues = self.get_queues(self.get_environment(environment_name)?);
+        queues.validate()?;
+        let unsafe_config = self.unsafe_config.clone().unwrap_or_default();
+        unsafe_config.validate()?;
+
         /*
         From https://developers.cloudflare.com/workers/cli-wrangler/configuration#keys
         Top level: required to be configured at the top level of your wrangler.toml only; multiple environments on the same project must share this property
@@ -349,14 +452,27 @@ impl Manifest {
             name: self.name.clone(), // Inherited
             kv_namespaces: get_namespaces(self.kv_namespaces.clone(), preview)?, // Not inherited
             durable_objects: self.durable_objects.clone(), // Not inherited
-            migrations: None,        // TODO(soon) Allow migrations in wrangler.toml
+            services: self.services.clone().unwrap_or_default(), // Not inherited
+            analytics_engine_datasets, // Not inherited
+            queue_producers: queues.producers, // Not inherited
+            unsafe_bindings: unsafe_config.bindings, // Not inherited
+            migrations: self.migrations.clone().map(|migrations| Migrations {
+                migrations,
+                deployed_tag: None,
+            }), // Top level
             site: self.site.clone(), // Inherited
             vars: self.vars.clone(), // Not inherited
             text_blobs: self.text_blobs.clone(), // Inherited
-            usage_model: self.usage_model, // Top level
+            usage_model: self.usage_model, // Inherited
             wasm_modules: self.wasm_modules.clone(),
-            compatibility_date: self.compatibility_date.clone(),
-            compatibility_flags: self.compatibility_flags.clone(),
+            compatibility_date: self.compatibility_date.clone(), // Inherited
+            compatibility_flags: self.compatibility_flags.clone(), // Inherited
+            upload_source_maps: self.upload_source_maps.unwrap_or_default(), // Inherited
+            dispatch_namespace: None,
+            annotations: Default::default(),
+            hooks: self.hooks.clone(),                     // Inherited
+            keep_vars: self.keep_vars.unwrap_or_default(), // Inherited
+            minify: self.minify.unwrap_or_default(),       // Inherited
         };
 
         let environment = self.get_environment(environment_name)?;
@@ -379,6 +495,20 @@ impl Manifest {
             // don't inherit durable object configuration
             target.durable_objects = environment.durable_objects.clone();
 
+            // don't inherit service bindings, an environment usually binds to the same environment of others
+            target.services = environment.services.clone().unwrap_or_default();
+
+            // don't inherit analytics engine datasets, so environments don't write to the same one
+            target.analytics_engine_datasets = environment
+                .analytics_engine_datasets
+                .clone()
+                .unwrap_or_default();
+
+            // don't inherit unsafe bindings, like the bindings wrangler knows about
+            let unsafe_config = environment.unsafe_config.clone().unwrap_or_default();
+            unsafe_config.validate()?;
+            target.unsafe_bindings = unsafe_config.bindings;
+
             // inherit site configuration
             if let Some(site) = &environment.site {
                 target.site = Some(site.clone());
@@ -386,6 +516,35 @@ impl Manifest {
 
             // don't inherit vars
             target.vars = environment.vars.clone();
+
+            // inherit the runtime behaviors the worker opts into
+            if let Some(compatibility_date) = &environment.compatibility_date {
+                target.compatibility_date = Some(compatibility_date.clone());
+            }
+            if let Some(compatibility_flags) = &environment.compatibility_flags {
+                target.compatibility_flags = compatibility_flags.clone();
+            }
+
+            // an environment can run on a different usage model, like a staging worker on bundled
+            if let Some(usage_model) = environment.usage_model {
+                target.usage_model = Some(usage_model);
+            }
+
+            if let Some(upload_source_maps) = environment.upload_source_maps {
+                target.upload_source_maps = upload_source_maps;
+            }
+
+            if let Some(hooks) = &environment.hooks {
+                target.hooks = Some(hooks.clone());
+            }
+
+            if let Some(keep_vars) = environment.keep_vars {
+                target.keep_vars = keep_vars;
+            }
+
+            if let Some(minify) = environment.minify {
+                target.minify = minify;
+            }
         }
 
         Ok(target)
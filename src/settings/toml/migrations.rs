@@ -3,24 +3,63 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Migrations {
     pub migrations: Vec<MigrationConfig>,
+    /// The tag of the last migration the deployed script has applied, once it is known
+    #[serde(skip)]
+    pub deployed_tag: Option<String>,
 }
 
 impl Migrations {
-    pub fn api_migration(&self) -> Result<ApiMigration, anyhow::Error> {
-        // TODO: make api call to get most recent tag, and coalesce tags afterwards.
-        // For now, migrations will only ever have a single adhoc migration in it.
-        match &self.migrations {
-            migrations if migrations.len() == 1 => Ok(ApiMigration {
+    /// The migrations to upload alongside the script, if there are any it has not applied yet.
+    pub fn api_migration(&self) -> Result<Option<ApiMigration>, anyhow::Error> {
+        match &self.migrations[..] {
+            // an adhoc migration from the command line is applied as is
+            [migration] if migration.tag.is_none() => Ok(Some(ApiMigration {
                 old_tag: None,
                 new_tag: None,
-                migration: migrations[0].migration.clone(),
-            }),
-            migrations => Self::coalesce_migrations(migrations),
+                steps: vec![migration.migration.clone()],
+            })),
+            migrations => self.pending_migrations(migrations),
         }
     }
 
-    fn coalesce_migrations(_migrations: &[MigrationConfig]) -> Result<ApiMigration, anyhow::Error> {
-        unimplemented!()
+    fn pending_migrations(
+        &self,
+        migrations: &[MigrationConfig],
+    ) -> Result<Option<ApiMigration>, anyhow::Error> {
+        if migrations.iter().any(|migration| migration.tag.is_none()) {
+            anyhow::bail!("Every [[migrations]] in wrangler.toml needs a tag, e.g. tag = \"v1\"")
+        }
+
+        let applied = match &self.deployed_tag {
+            Some(deployed_tag) => {
+                migrations
+                    .iter()
+                    .position(|migration| migration.tag.as_ref() == Some(deployed_tag))
+                    .ok_or_else(|| anyhow::anyhow!("The deployed script has applied the migration {}, which is not in the [[migrations]] of wrangler.toml", deployed_tag))?
+                    + 1
+            }
+            None => 0,
+        };
+
+        let pending = &migrations[applied..];
+        match pending.last() {
+            Some(last) => Ok(Some(ApiMigration {
+                old_tag: self.deployed_tag.clone(),
+                new_tag: last.tag.clone(),
+                steps: pending
+                    .iter()
+                    .map(|migration| migration.migration.clone())
+                    .collect(),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the migrations are tagged, so the tag of the deployed script has to be known.
+    pub fn is_tagged(&self) -> bool {
+        self.migrations
+            .iter()
+            .any(|migration| migration.tag.is_some())
     }
 }
 
@@ -37,8 +76,7 @@ pub struct ApiMigration {
     pub old_tag: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_tag: Option<String>,
-    #[serde(flatten)]
-    pub migration: Migration,
+    pub steps: Vec<Migration>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -48,6 +86,7 @@ pub struct Migration {
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
 pub struct DurableObjectsMigration {
     pub new_classes: Vec<String>,
     pub deleted_classes: Vec<String>,
@@ -67,3 +106,71 @@ pub struct TransferClass {
     pub from_script: String,
     pub to: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(tag: Option<&str>, new_class: &str) -> MigrationConfig {
+        MigrationConfig {
+            tag: tag.map(String::from),
+            migration: Migration {
+                durable_objects: DurableObjectsMigration {
+                    new_classes: vec![new_class.to_owned()],
+                    ..Default::default()
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn it_applies_adhoc_migrations_as_is() {
+        let migrations = Migrations {
+            migrations: vec![migration(None, "Counter")],
+            deployed_tag: Some("v1".to_owned()),
+        };
+
+        let api_migration = migrations.api_migration().unwrap().unwrap();
+        assert_eq!(api_migration.old_tag, None);
+        assert_eq!(api_migration.steps.len(), 1);
+    }
+
+    #[test]
+    fn it_uploads_the_migrations_after_the_deployed_tag() {
+        let mut migrations = Migrations {
+            migrations: vec![
+                migration(Some("v1"), "Counter"),
+                migration(Some("v2"), "Room"),
+                migration(Some("v3"), "Lobby"),
+            ],
+            deployed_tag: None,
+        };
+        let api_migration = migrations.api_migration().unwrap().unwrap();
+        assert_eq!(api_migration.new_tag.as_deref(), Some("v3"));
+        assert_eq!(api_migration.steps.len(), 3);
+
+        migrations.deployed_tag = Some("v1".to_owned());
+        let api_migration = migrations.api_migration().unwrap().unwrap();
+        assert_eq!(api_migration.old_tag.as_deref(), Some("v1"));
+        assert_eq!(api_migration.new_tag.as_deref(), Some("v3"));
+        assert_eq!(
+            api_migration.steps[0].durable_objects.new_classes,
+            vec!["Room".to_owned()]
+        );
+
+        migrations.deployed_tag = Some("v3".to_owned());
+        assert_eq!(migrations.api_migration().unwrap(), None);
+
+        migrations.deployed_tag = Some("v4".to_owned());
+        assert!(migrations.api_migration().is_err());
+    }
+
+    #[test]
+    fn it_requires_tags_in_wrangler_toml() {
+        let migrations = Migrations {
+            migrations: vec![migration(Some("v1"), "Counter"), migration(None, "Room")],
+            deployed_tag: None,
+        };
+        assert!(migrations.api_migration().is_err());
+    }
+}
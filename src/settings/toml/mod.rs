@@ -1,24 +1,34 @@
+mod analytics_engine;
 mod builder;
 mod dev;
 mod durable_objects;
 mod environment;
+mod hooks;
 mod kv_namespace;
 mod manifest;
 pub mod migrations;
+mod queues;
 mod route;
+mod services;
 mod site;
 mod target;
 mod target_type;
 mod triggers;
+mod unsafe_config;
 
-pub use builder::{ModuleRule, UploadFormat};
+pub use analytics_engine::AnalyticsEngineDataset;
+pub use builder::{Builder, ModuleRule, UploadFormat};
 pub use durable_objects::{DurableObjects, DurableObjectsClass};
+pub use hooks::Hooks;
 pub use kv_namespace::{ConfigKvNamespace, KvNamespace};
 pub use manifest::Manifest;
+pub use queues::{QueueConsumer, QueueProducer, Queues};
 pub use route::{Route, RouteConfig};
+pub use services::ServiceBinding;
 pub use site::Site;
-pub use target::Target;
+pub use target::{Annotations, Target};
 pub use target_type::TargetType;
+pub use unsafe_config::Unsafe;
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// The queues a script sends messages to, and the ones it consumes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Queues {
+    #[serde(default)]
+    pub producers: Vec<QueueProducer>,
+    #[serde(default)]
+    pub consumers: Vec<QueueConsumer>,
+}
+
+/// A binding to a queue the script sends messages to.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct QueueProducer {
+    pub binding: String,
+    pub queue: String,
+}
+
+/// A queue whose messages are delivered to the script in batches.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct QueueConsumer {
+    pub queue: String,
+    /// the most messages in a batch
+    pub max_batch_size: Option<u32>,
+    /// the most seconds to wait for a batch to fill up
+    pub max_batch_timeout: Option<u32>,
+    /// how many times a batch is retried before its messages are dropped, or sent to
+    /// the dead letter queue
+    pub max_retries: Option<u32>,
+    pub dead_letter_queue: Option<String>,
+}
+
+const MAX_BATCH_SIZE: u32 = 100;
+const MAX_BATCH_TIMEOUT: u32 = 30;
+
+impl Queues {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for producer in self.producers.iter() {
+            if producer.binding.is_empty() || producer.queue.is_empty() {
+                anyhow::bail!("[[queues.producers]] need both a binding and a queue")
+            }
+        }
+        for (i, consumer) in self.consumers.iter().enumerate() {
+            if consumer.queue.is_empty() {
+                anyhow::bail!("[[queues.consumers]] need a queue")
+            }
+            if self.consumers[..i]
+                .iter()
+                .any(|other| other.queue == consumer.queue)
+            {
+                anyhow::bail!(
+                    "The queue {} is consumed more than once in [[queues.consumers]]",
+                    consumer.queue
+                )
+            }
+            if let Some(size) = consumer.max_batch_size {
+                if size == 0 || size > MAX_BATCH_SIZE {
+                    anyhow::bail!(
+                        "max_batch_size of the consumer of {} must be between 1 and {}",
+                        consumer.queue,
+                        MAX_BATCH_SIZE
+                    )
+                }
+            }
+            if let Some(timeout) = consumer.max_batch_timeout {
+                if timeout > MAX_BATCH_TIMEOUT {
+                    anyhow::bail!(
+                        "max_batch_timeout of the consumer of {} can be at most {} seconds",
+                        consumer.queue,
+                        MAX_BATCH_TIMEOUT
+                    )
+                }
+            }
+            if consumer.dead_letter_queue.as_deref() == Some(consumer.queue.as_str()) {
+                anyhow::bail!(
+                    "The dead_letter_queue of the consumer of {} can't be the queue itself",
+                    consumer.queue
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QueueProducer {
+    pub fn binding(&self) -> Binding {
+        Binding::new_queue(self.binding.clone(), self.queue.clone())
+    }
+}
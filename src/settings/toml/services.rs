@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// A binding to another worker, which the script calls like it would fetch from it.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ServiceBinding {
+    pub binding: String,
+    pub service: String,
+    /// the environment of the service, its production one when left out
+    pub environment: Option<String>,
+}
+
+impl ServiceBinding {
+    pub fn environment(&self) -> &str {
+        self.environment.as_deref().unwrap_or("production")
+    }
+
+    pub fn binding(&self) -> Binding {
+        Binding::new_service(
+            self.binding.clone(),
+            self.service.clone(),
+            self.environment().to_owned(),
+        )
+    }
+}
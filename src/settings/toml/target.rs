@@ -1,6 +1,10 @@
+use super::analytics_engine::AnalyticsEngineDataset;
 use super::durable_objects::DurableObjects;
+use super::hooks::Hooks;
 use super::kv_namespace::KvNamespace;
 use super::manifest::LazyAccountId;
+use super::queues::QueueProducer;
+use super::services::ServiceBinding;
 use super::site::Site;
 use super::target_type::TargetType;
 use super::UsageModel;
@@ -11,11 +15,23 @@ use std::env;
 
 use std::path::PathBuf;
 
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// The most recent compatibility date this version of wrangler knows the runtime behaviors of.
+pub const LATEST_COMPATIBILITY_DATE: &str = "2021-11-10";
+
 #[derive(Clone, Debug, Default)]
 pub struct Target {
     pub account_id: LazyAccountId,
     pub kv_namespaces: Vec<KvNamespace>,
     pub durable_objects: Option<DurableObjects>,
+    pub services: Vec<ServiceBinding>,
+    pub analytics_engine_datasets: Vec<AnalyticsEngineDataset>,
+    pub queue_producers: Vec<QueueProducer>,
+    /// bindings from [[unsafe.bindings]], uploaded as they are
+    pub unsafe_bindings: Vec<serde_json::Value>,
     pub migrations: Option<Migrations>,
     pub name: String,
     pub target_type: TargetType,
@@ -28,6 +44,32 @@ pub struct Target {
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
     pub compatibility_date: Option<String>,
     pub compatibility_flags: Vec<String>,
+    /// whether the source map of the script is uploaded with it, to resolve stack traces
+    pub upload_source_maps: bool,
+    /// the dispatch namespace the script is uploaded into, instead of the scripts of the account
+    pub dispatch_namespace: Option<String>,
+    pub annotations: Annotations,
+    pub hooks: Option<Hooks>,
+    /// whether the vars of the deployed script that aren't in `vars` are kept, such as the
+    /// ones set in the dashboard
+    pub keep_vars: bool,
+    /// whether the built script is minified before it is uploaded
+    pub minify: bool,
+}
+
+/// What was changed in an uploaded version, which its deployment history shows.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Annotations {
+    #[serde(rename = "workers/message", skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(rename = "workers/tag", skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.message.is_none() && self.tag.is_none()
+    }
 }
 
 impl Target {
@@ -35,6 +77,36 @@ impl Target {
         self.kv_namespaces.push(kv_namespace);
     }
 
+    /// Checks that `compatibility_date` is a date, returning a warning when it is missing or
+    /// older than the latest one wrangler knows about, as the worker misses out on the
+    /// runtime behaviors added since.
+    pub fn compatibility_date_warning(&self) -> Result<Option<String>> {
+        let date = match &self.compatibility_date {
+            Some(date) => date,
+            None => {
+                return Ok(Some(format!(
+                    "There is no compatibility_date in your configuration file, so the oldest runtime behaviors are used. Add compatibility_date = \"{}\" to opt into the current ones.",
+                    LATEST_COMPATIBILITY_DATE
+                )))
+            }
+        };
+        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            anyhow::bail!(
+                "compatibility_date \"{}\" is not a date like \"{}\"",
+                date,
+                LATEST_COMPATIBILITY_DATE
+            )
+        }
+        // dates in this format compare the same as strings
+        if date.as_str() < LATEST_COMPATIBILITY_DATE {
+            return Ok(Some(format!(
+                "compatibility_date \"{}\" is older than {}, the latest this version of wrangler knows about. Moving it forward opts into the runtime behaviors added since.",
+                date, LATEST_COMPATIBILITY_DATE
+            )));
+        }
+        Ok(None)
+    }
+
     pub fn package_dir(&self) -> Result<PathBuf, std::io::Error> {
         // if `site` is configured, we want to isolate worker code
         // and build artifacts away from static site application code.
@@ -1,6 +1,8 @@
 use std::str::FromStr;
 
-use crate::deploy::{DeployTarget, ScheduleTarget, ZonedTarget, ZonelessTarget};
+use crate::deploy::{
+    CustomDomainsTarget, DeployTarget, ScheduleTarget, ZonedTarget, ZonelessTarget,
+};
 use crate::settings::toml::route::Route;
 use crate::settings::toml::Manifest;
 
@@ -185,6 +187,122 @@ fn it_can_get_a_scheduled_no_workers_dev_no_zoned() {
     assert_eq!(actual_deployments, expected_deployments);
 }
 
+#[test]
+fn it_leaves_out_routes_and_workers_dev_from_unrouted_deployments() {
+    let script_name = "routes_and_schedule";
+
+    let crons = vec!["0 * * * *".to_owned()];
+
+    let mut test_toml = WranglerToml::zoned_single_route(script_name, ZONE_ID, PATTERN);
+    test_toml.account_id = Some(ACCOUNT_ID);
+    test_toml.workers_dev = Some(true);
+    test_toml.triggers = Some(Triggers {
+        crons: Some(crons.clone()),
+    });
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let expected_deployments = vec![DeployTarget::Schedule(ScheduleTarget {
+        account_id: ACCOUNT_ID.to_owned(),
+        script_name: script_name.to_owned(),
+        crons,
+    })];
+    let actual_deployments = manifest.get_unrouted_deployments(None).unwrap();
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
+#[test]
+fn it_errors_on_malformed_crons() {
+    let mut test_toml = WranglerToml::webpack("malformed_schedule");
+    test_toml.account_id = Some(ACCOUNT_ID);
+    test_toml.triggers = Some(Triggers {
+        crons: Some(vec!["0 * * *".to_owned()]),
+    });
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    assert!(manifest.get_deployments(None).is_err());
+}
+
+#[test]
+fn it_can_get_custom_domains() {
+    let script_name = "custom_domains";
+
+    let mut test_toml = WranglerToml::webpack(script_name);
+    test_toml.account_id = Some(ACCOUNT_ID);
+    test_toml.custom_domains = Some(vec!["api.example.com"]);
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let expected_deployments = vec![DeployTarget::CustomDomains(CustomDomainsTarget {
+        account_id: ACCOUNT_ID.to_owned(),
+        script_name: script_name.to_owned(),
+        zone_id: None,
+        hostnames: vec!["api.example.com".to_owned()],
+    })];
+    let environment = None;
+    let actual_deployments = manifest.get_deployments(environment).unwrap();
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
+#[test]
+fn it_does_not_inherit_custom_domains() {
+    let script_name = "custom_domains";
+
+    let env_config = EnvConfig {
+        custom_domains: Some(vec!["staging.example.com"]),
+        ..EnvConfig::default()
+    };
+    let mut test_toml = WranglerToml::webpack(script_name);
+    test_toml.account_id = Some(ACCOUNT_ID);
+    test_toml.custom_domains = Some(vec!["api.example.com"]);
+    test_toml
+        .env
+        .get_or_insert_with(Default::default)
+        .insert("b", env_config);
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    let expected_deployments = vec![DeployTarget::CustomDomains(CustomDomainsTarget {
+        account_id: ACCOUNT_ID.to_owned(),
+        script_name: manifest.worker_name(Some("b")),
+        zone_id: None,
+        hostnames: vec!["staging.example.com".to_owned()],
+    })];
+    let actual_deployments = manifest.get_deployments(Some("b")).unwrap();
+
+    assert_eq!(actual_deployments, expected_deployments);
+}
+
+#[test]
+fn it_errors_on_custom_domains_with_paths() {
+    let mut test_toml = WranglerToml::webpack("custom_domains");
+    test_toml.account_id = Some(ACCOUNT_ID);
+    test_toml.custom_domains = Some(vec!["example.com/*"]);
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    assert!(manifest.get_deployments(None).is_err());
+}
+
+#[test]
+fn it_errors_on_custom_domains_without_an_account_id() {
+    let mut test_toml = WranglerToml::webpack("custom_domains");
+    test_toml.custom_domains = Some(vec!["api.example.com"]);
+
+    let toml_string = toml::to_string(&test_toml).unwrap();
+    let manifest = Manifest::from_str(&toml_string).unwrap();
+
+    assert!(manifest.get_deployments(None).is_err());
+}
+
 #[test]
 fn it_can_get_a_scheduled_in_env_no_workers_dev_no_zoned() {
     let script_name = "single_schedule";
@@ -7,6 +7,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use crate::deploy::DeployTarget;
+
 #[path = "../../../../tests/fixtures/mod.rs"]
 mod fixtures;
 use fixtures::{EnvConfig, WranglerToml, TEST_ENV_NAME};
@@ -126,6 +128,294 @@ fn it_uses_env_name_when_provided() {
     assert_eq!(manifest.worker_name(Some(TEST_ENV_NAME)), custom_env_name);
 }
 
+#[test]
+fn it_reads_migrations_for_every_environment() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+
+        [[migrations]]
+        tag = "v1"
+        new_classes = ["Counter"]
+
+        [[migrations]]
+        tag = "v2"
+        renamed_classes = [{ from = "Counter", to = "Count" }]
+
+        [env.staging]
+        "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(Some("staging"), false).unwrap();
+    let migrations = target.migrations.unwrap().migrations;
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(migrations[1].tag.as_deref(), Some("v2"));
+    assert_eq!(
+        migrations[1].migration.durable_objects.renamed_classes[0].to,
+        "Count"
+    );
+    assert!(migrations[1]
+        .migration
+        .durable_objects
+        .new_classes
+        .is_empty());
+}
+
+#[test]
+fn it_reads_compatibility_settings_for_every_environment() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        compatibility_date = "2021-11-10"
+        compatibility_flags = ["formdata_parser_supports_files"]
+
+        [env.staging]
+
+        [env.canary]
+        compatibility_date = "2021-09-14"
+        compatibility_flags = []
+
+        [env.broken]
+        compatibility_date = "yesterday"
+        "#,
+    )
+    .unwrap();
+
+    let staging = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(staging.compatibility_date.as_deref(), Some("2021-11-10"));
+    assert_eq!(
+        staging.compatibility_flags,
+        vec!["formdata_parser_supports_files"]
+    );
+    assert!(staging.compatibility_date_warning().unwrap().is_none());
+
+    let canary = manifest.get_target(Some("canary"), false).unwrap();
+    assert_eq!(canary.compatibility_date.as_deref(), Some("2021-09-14"));
+    assert!(canary.compatibility_flags.is_empty());
+    assert!(canary.compatibility_date_warning().unwrap().is_some());
+
+    let broken = manifest.get_target(Some("broken"), false).unwrap();
+    assert!(broken.compatibility_date_warning().is_err());
+
+    let mut missing = staging;
+    missing.compatibility_date = None;
+    assert!(missing.compatibility_date_warning().unwrap().is_some());
+}
+
+#[test]
+fn it_reads_the_usage_model_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        usage_model = "unbound"
+
+        [env.staging]
+
+        [env.cheap]
+        usage_model = "bundled"
+
+        [env.unset]
+        usage_model = ""
+        "#,
+    )
+    .unwrap();
+
+    let usage_model = |env| target(&manifest, Some(env)).usage_model;
+    assert_eq!(usage_model("staging"), Some(UsageModel::Unbound));
+    assert_eq!(usage_model("cheap"), Some(UsageModel::Bundled));
+    assert_eq!(usage_model("unset"), Some(UsageModel::Unbound));
+    assert!(javascript_manifest(
+        r#"
+        [env.staging]
+        usage_model = "unlimited"
+        "#,
+    )
+    .is_err());
+}
+
+#[test]
+fn it_reads_hooks_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        [hooks]
+        post_publish = "./purge-cache.sh"
+
+        [env.staging]
+
+        [env.production.hooks]
+        pre_publish = "npm test"
+        post_publish = "./notify.sh"
+        "#,
+    )
+    .unwrap();
+
+    let hooks = |env| target(&manifest, Some(env)).hooks.unwrap();
+    assert_eq!(hooks("staging").pre_publish, None);
+    assert_eq!(
+        hooks("staging").post_publish.as_deref(),
+        Some("./purge-cache.sh")
+    );
+    assert_eq!(hooks("production").pre_publish.as_deref(), Some("npm test"));
+    assert_eq!(
+        hooks("production").post_publish.as_deref(),
+        Some("./notify.sh")
+    );
+}
+
+#[test]
+fn it_reads_service_bindings_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        services = [{ binding = "AUTH", service = "auth-worker" }]
+
+        [env.staging]
+        services = [{ binding = "AUTH", service = "auth-worker", environment = "staging" }]
+
+        [env.bare]
+        "#,
+    )
+    .unwrap();
+
+    let services = |env| target(&manifest, env).services;
+    assert_eq!(services(None)[0].binding, "AUTH");
+    assert_eq!(services(None)[0].environment(), "production");
+    assert_eq!(services(Some("staging"))[0].environment(), "staging");
+    assert!(services(Some("bare")).is_empty());
+}
+
+#[test]
+fn it_reads_analytics_engine_datasets_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        analytics_engine_datasets = [{ binding = "EVENTS" }]
+
+        [env.staging]
+        analytics_engine_datasets = [{ binding = "EVENTS", dataset = "staging_events" }]
+
+        [env.bare]
+        "#,
+    )
+    .unwrap();
+
+    let datasets = |env| target(&manifest, env).analytics_engine_datasets;
+    assert_eq!(datasets(None)[0].binding, "EVENTS");
+    assert_eq!(datasets(None)[0].dataset, None);
+    assert_eq!(
+        datasets(Some("staging"))[0].dataset.as_deref(),
+        Some("staging_events")
+    );
+    assert!(datasets(Some("bare")).is_empty());
+}
+
+#[test]
+fn it_inherits_minify_unless_an_environment_overrides_it() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "webpack"
+        minify = true
+
+        [env.debug]
+        minify = false
+
+        [env.staging]
+        "#,
+    )
+    .unwrap();
+
+    assert!(manifest.get_target(None, false).unwrap().minify);
+    assert!(!manifest.get_target(Some("debug"), false).unwrap().minify);
+    assert!(manifest.get_target(Some("staging"), false).unwrap().minify);
+}
+
+#[test]
+fn it_reads_queues_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        account_id = "fakeaccountid"
+        workers_dev = true
+
+        [[queues.producers]]
+        binding = "JOBS"
+        queue = "jobs"
+
+        [[queues.consumers]]
+        queue = "jobs"
+        max_batch_size = 10
+
+        [env.bare]
+        workers_dev = true
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(target(&manifest, None).queue_producers[0].queue, "jobs");
+    let consumers = manifest
+        .get_deployments(None)
+        .unwrap()
+        .into_iter()
+        .filter(|deployment| matches!(deployment, DeployTarget::QueueConsumers(_)))
+        .count();
+    assert_eq!(consumers, 1);
+    assert!(target(&manifest, Some("bare")).queue_producers.is_empty());
+}
+
+#[test]
+fn it_errors_on_invalid_queue_consumers() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+
+        [[queues.consumers]]
+        queue = "jobs"
+        max_batch_size = 1000
+        "#,
+    )
+    .unwrap();
+
+    assert!(manifest.get_target(None, false).is_err());
+}
+
+#[test]
+fn it_reads_unsafe_bindings_for_every_environment() {
+    let manifest = javascript_manifest(
+        r#"
+        [[unsafe.bindings]]
+        type = "some_new_binding"
+        name = "NEW"
+        id = "fakeid"
+
+        [env.bare]
+
+        [env.invalid]
+        unsafe = { bindings = [{ id = "fakeid" }] }
+        "#,
+    )
+    .unwrap();
+
+    let bindings = target(&manifest, None).unsafe_bindings;
+    assert_eq!(bindings[0]["type"], "some_new_binding");
+    assert_eq!(bindings[0]["id"], "fakeid");
+    assert!(target(&manifest, Some("bare")).unsafe_bindings.is_empty());
+    assert!(manifest.get_target(Some("invalid"), false).is_err());
+}
+
+/// Parses the wrangler.toml of a JavaScript worker named "worker", with these fields too.
+fn javascript_manifest(fields: &str) -> Result<Manifest, toml::de::Error> {
+    Manifest::from_str(&format!(
+        "name = \"worker\"\ntype = \"javascript\"\n{}",
+        fields
+    ))
+}
+
+/// The target of an environment, or of the top level of wrangler.toml for `None`.
+fn target(manifest: &Manifest, environment: Option<&str>) -> Target {
+    manifest.get_target(environment, false).unwrap()
+}
+
 fn base_fixture_path() -> PathBuf {
     let current_dir = env::current_dir().unwrap();
 
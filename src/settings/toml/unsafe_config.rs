@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Configuration that wrangler passes on to the API without understanding it, for
+/// features it doesn't support yet.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Unsafe {
+    /// bindings added verbatim to the metadata of the upload, such as binding types
+    /// this version of wrangler doesn't know about
+    #[serde(default)]
+    pub bindings: Vec<serde_json::Value>,
+}
+
+impl Unsafe {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for binding in self.bindings.iter() {
+            let has = |key: &str| binding.get(key).map_or(false, |value| value.is_string());
+            if !has("name") || !has("type") {
+                anyhow::bail!(
+                    "[[unsafe.bindings]] need a name and a type, like every binding of the upload: {}",
+                    binding
+                )
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::settings::toml::Site;
+
+/// Where the worker that serves the files of `wrangler publish --assets` is generated.
+const ASSETS_ENTRY_POINT: &str = ".wrangler/assets-worker";
+
+const ASSETS_WORKER: &str = include_str!("assets_worker.js");
+
+/// The directory of the generated files, which shouldn't be committed.
+const GENERATED_DIR: &str = ".wrangler";
+
+/// Generates a worker that serves the files of a directory, and the site that uploads them,
+/// so a directory can be published without a [site] section or a worker of its own.
+pub fn site(bucket: &Path) -> Result<Site> {
+    if !bucket.is_dir() {
+        anyhow::bail!(
+            "--assets has to be a directory of files to publish, {} is not",
+            bucket.display()
+        )
+    }
+
+    let site = Site {
+        bucket: bucket.to_path_buf(),
+        entry_point: Some(ASSETS_ENTRY_POINT.into()),
+        include: None,
+        exclude: None,
+    };
+
+    // the worker is written on every publish, so it is the one of the installed wrangler
+    let entry_point = site.entry_point()?;
+    fs::create_dir_all(&entry_point)?;
+    fs::write(entry_point.join("index.js"), ASSETS_WORKER)?;
+    fs::write(
+        entry_point.join("package.json"),
+        serde_json::json!({ "private": true, "main": "index.js" }).to_string(),
+    )?;
+    ignore_generated_dir(Path::new(".gitignore"))?;
+
+    Ok(site)
+}
+
+/// Adds the directory of the generated worker to the .gitignore of the project, if it has one
+/// that doesn't list it yet.
+fn ignore_generated_dir(gitignore: &Path) -> Result<()> {
+    let contents = match fs::read_to_string(gitignore) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let ignored = contents.lines().any(|line| {
+        let line = line.trim().trim_start_matches('/').trim_end_matches('/');
+        line == GENERATED_DIR
+    });
+    if !ignored {
+        let mut file = OpenOptions::new().append(true).open(gitignore)?;
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            writeln!(file)?;
+        }
+        writeln!(file, "{}", GENERATED_DIR)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn it_resolves_request_paths_to_files() {
+        let manifest = serde_json::json!({
+            "index.html": "index.1",
+            "about/index.html": "about/index.2",
+            "style.css": "style.3",
+            "404.html": "404.4",
+        });
+        let paths = ["/", "/about", "/about/", "//style.css", "/missing"];
+        let script = format!(
+            "const context = {{ __STATIC_CONTENT_MANIFEST: {}, addEventListener() {{}} }};
+            require('vm').runInNewContext({}, context);
+            console.log(JSON.stringify({}.map(path => context.resolve(path))));",
+            serde_json::to_string(&manifest.to_string()).unwrap(),
+            serde_json::to_string(ASSETS_WORKER).unwrap(),
+            serde_json::to_string(&paths).unwrap(),
+        );
+        let output = Command::new("node").arg("-e").arg(script).output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+
+        let resolved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            resolved,
+            serde_json::json!([
+                { "path": "index.html", "key": "index.1", "status": 200 },
+                { "path": "about/index.html", "key": "about/index.2", "status": 200 },
+                { "path": "about/index.html", "key": "about/index.2", "status": 200 },
+                { "path": "style.css", "key": "style.3", "status": 200 },
+                { "path": "404.html", "key": "404.4", "status": 404 },
+            ])
+        );
+    }
+
+    #[test]
+    fn it_ignores_the_generated_dir_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let gitignore = dir.path().join(".gitignore");
+
+        ignore_generated_dir(&gitignore).unwrap();
+        assert!(!gitignore.exists());
+
+        fs::write(&gitignore, "node_modules").unwrap();
+        ignore_generated_dir(&gitignore).unwrap();
+        ignore_generated_dir(&gitignore).unwrap();
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            "node_modules\n.wrangler\n"
+        );
+    }
+}
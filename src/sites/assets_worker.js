@@ -0,0 +1,79 @@
+// Generated by `wrangler publish --assets`, serving the files of a directory from Workers KV.
+const manifest = JSON.parse(__STATIC_CONTENT_MANIFEST)
+
+const CONTENT_TYPES = {
+  css: 'text/css; charset=utf-8',
+  gif: 'image/gif',
+  htm: 'text/html; charset=utf-8',
+  html: 'text/html; charset=utf-8',
+  ico: 'image/x-icon',
+  jpeg: 'image/jpeg',
+  jpg: 'image/jpeg',
+  js: 'application/javascript; charset=utf-8',
+  json: 'application/json; charset=utf-8',
+  mjs: 'application/javascript; charset=utf-8',
+  png: 'image/png',
+  svg: 'image/svg+xml',
+  txt: 'text/plain; charset=utf-8',
+  wasm: 'application/wasm',
+  webp: 'image/webp',
+  woff: 'font/woff',
+  woff2: 'font/woff2',
+  xml: 'application/xml; charset=utf-8',
+}
+
+addEventListener('fetch', event => {
+  event.respondWith(handleRequest(event.request))
+})
+
+async function handleRequest(request) {
+  if (request.method !== 'GET' && request.method !== 'HEAD') {
+    return new Response('Method Not Allowed', { status: 405, headers: { allow: 'GET, HEAD' } })
+  }
+
+  let path
+  try {
+    path = decodeURIComponent(new URL(request.url).pathname)
+  } catch (e) {
+    // a malformed escape sequence, such as a lone %, can't name a file
+    return new Response('Bad Request', { status: 400 })
+  }
+
+  const asset = resolve(path)
+  const body = asset === null ? null : await __STATIC_CONTENT.get(asset.key, 'arrayBuffer')
+  if (body === null) {
+    return new Response('Not Found', { status: 404 })
+  }
+
+  const extension = asset.path.split('.').pop().toLowerCase()
+  return new Response(request.method === 'HEAD' ? null : body, {
+    status: asset.status,
+    headers: {
+      'content-type': CONTENT_TYPES[extension] || 'application/octet-stream',
+      // the key changes with the contents of a file, but not its path
+      'cache-control': 'public, max-age=0, must-revalidate',
+    },
+  })
+}
+
+// Finds the file that is served for a decoded request path, or null when there's none.
+function resolve(path) {
+  path = path.replace(/^\/+/, '')
+  if (path === '' || path.endsWith('/')) {
+    path += 'index.html'
+  }
+
+  let status = 200
+  let key = manifest[path]
+  if (key === undefined && manifest[path + '/index.html'] !== undefined) {
+    path += '/index.html'
+    key = manifest[path]
+  }
+  if (key === undefined && manifest['404.html'] !== undefined) {
+    status = 404
+    path = '404.html'
+    key = manifest[path]
+  }
+
+  return key === undefined ? null : { path, key, status }
+}
@@ -1,5 +1,6 @@
 extern crate base64;
 
+pub mod assets;
 mod manifest;
 mod sync;
 
@@ -319,21 +320,9 @@ mod tests {
 
     fn make_target(site: Site) -> Target {
         Target {
-            account_id: None.into(),
-            kv_namespaces: Vec::new(),
-            durable_objects: None,
-            migrations: None,
-            name: "".to_string(),
             target_type: TargetType::JavaScript,
-            webpack_config: None,
             site: Some(site),
-            build: None,
-            vars: None,
-            text_blobs: None,
-            usage_model: None,
-            wasm_modules: None,
-            compatibility_date: None,
-            compatibility_flags: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -25,6 +25,28 @@ pub fn get_user_input_multi_line(prompt_string: &str) -> String {
     input
 }
 
+// Reads everything piped into stdin without a prompt, exactly as it was piped in. Returns None
+// when stdin is a terminal instead.
+pub fn read_piped_input() -> Result<Option<String>> {
+    if atty::is(Stream::Stdin) {
+        return Ok(None);
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    Ok(Some(input))
+}
+
+// Removes the one newline at the very end of input, such as the one `echo` adds.
+pub fn strip_final_newline(mut input: String) -> String {
+    if input.ends_with('\n') {
+        input.pop();
+        if input.ends_with('\r') {
+            input.pop();
+        }
+    }
+    input
+}
+
 fn strip_trailing_whitespace(mut input: String) -> String {
     input.truncate(input.trim_end().len());
     input
@@ -62,4 +84,15 @@ mod tests {
         let truncated_str = strip_trailing_whitespace(test_str);
         assert_eq!(truncated_str, "mysecret")
     }
+
+    #[test]
+    fn it_strips_only_the_final_newline() {
+        let pem = "-----BEGIN KEY-----\nabc\n-----END KEY-----\n".to_string();
+        assert_eq!(
+            strip_final_newline(pem),
+            "-----BEGIN KEY-----\nabc\n-----END KEY-----"
+        );
+        assert_eq!(strip_final_newline("secret\r\n".to_string()), "secret");
+        assert_eq!(strip_final_newline("secret\n\n".to_string()), "secret\n");
+    }
 }
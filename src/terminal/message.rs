@@ -8,6 +8,46 @@ pub enum Output {
     Json,
     PlainText,
 }
+
+/// Messages that go with a command's output: to stderr with `Output::Json`, which keeps
+/// stdout for the JSON document, or to stdout otherwise.
+impl Output {
+    pub fn message(self, msg: &str) {
+        match self {
+            Output::Json => StdErr::message(msg),
+            Output::PlainText => StdOut::message(msg),
+        }
+    }
+
+    pub fn info(self, msg: &str) {
+        match self {
+            Output::Json => StdErr::info(msg),
+            Output::PlainText => StdOut::info(msg),
+        }
+    }
+
+    pub fn warn(self, msg: &str) {
+        match self {
+            Output::Json => StdErr::warn(msg),
+            Output::PlainText => StdOut::warn(msg),
+        }
+    }
+
+    pub fn success(self, msg: &str) {
+        match self {
+            Output::Json => StdErr::success(msg),
+            Output::PlainText => StdOut::success(msg),
+        }
+    }
+
+    pub fn working(self, msg: &str) {
+        match self {
+            Output::Json => StdErr::working(msg),
+            Output::PlainText => StdOut::working(msg),
+        }
+    }
+}
+
 pub trait Message {
     fn message(msg: &str);
 
@@ -2,6 +2,7 @@ mod modules_worker;
 mod plain_text;
 mod project_assets;
 mod service_worker;
+mod source_map;
 mod text_blob;
 mod wasm_module;
 
@@ -14,11 +15,13 @@ use std::path::PathBuf;
 use crate::settings::binding;
 use crate::settings::toml::{Target, TargetType, UploadFormat, UsageModel};
 use crate::sites::AssetManifest;
+use crate::terminal::message::{Message, StdErr};
 use crate::wranglerjs;
 
 use plain_text::PlainText;
 pub use project_assets::{ModuleConfig, ModuleType};
 use project_assets::{ModulesAssets, ServiceWorkerAssets};
+use source_map::SourceMap;
 use text_blob::TextBlob;
 use wasm_module::WasmModule;
 
@@ -31,18 +34,9 @@ pub fn build(
     session_config: Option<serde_json::Value>,
 ) -> Result<Form> {
     let target_type = &target.target_type;
-    let compatibility_date = target.compatibility_date.clone();
-    let compatibility_flags = target.compatibility_flags.clone();
-    let kv_namespaces = &target.kv_namespaces;
-    let durable_object_classes = target
-        .durable_objects
-        .as_ref()
-        .and_then(|d| d.classes.clone())
-        .unwrap_or_default();
     let mut text_blobs: Vec<TextBlob> = Vec::new();
     let mut plain_texts: Vec<PlainText> = Vec::new();
     let mut wasm_modules: Vec<WasmModule> = Vec::new();
-    let usage_model = target.usage_model;
 
     if let Some(blobs) = &target.text_blobs {
         for (key, blob_path) in blobs.iter() {
@@ -84,18 +78,19 @@ pub fn build(
             let wasm_module = WasmModule::new(path, binding)?;
             wasm_modules.push(wasm_module);
             let script_path = PathBuf::from("./worker/generated/script.js");
+            if target.upload_source_maps {
+                StdErr::warn("upload_source_maps is not supported for Rust projects yet");
+            }
+            let source_map = None;
 
-            let assets = ServiceWorkerAssets {
+            let assets = ServiceWorkerAssets::new(
+                target,
                 script_path,
-                compatibility_date,
-                compatibility_flags,
                 wasm_modules,
-                kv_namespaces: kv_namespaces.to_vec(),
-                durable_object_classes,
                 text_blobs,
                 plain_texts,
-                usage_model,
-            };
+                source_map,
+            );
 
             service_worker::build_form(&assets, session_config)
         }
@@ -106,38 +101,39 @@ pub fn build(
                     let package_dir = target.package_dir()?;
                     let package = Package::new(&package_dir)?;
                     let script_path = package_dir.join(package.main(&package_dir)?);
+                    let source_map = source_map(target, &script_path)?;
 
-                    let assets = ServiceWorkerAssets {
+                    let assets = ServiceWorkerAssets::new(
+                        target,
                         script_path,
-                        compatibility_date,
-                        compatibility_flags,
                         wasm_modules,
-                        kv_namespaces: kv_namespaces.to_vec(),
-                        durable_object_classes,
                         text_blobs,
                         plain_texts,
-                        usage_model,
-                    };
+                        source_map,
+                    );
 
                     service_worker::build_form(&assets, session_config)
                 }
                 UploadFormat::Modules { main, dir, rules } => {
+                    if target.text_blobs.is_some() || target.wasm_modules.is_some() {
+                        StdErr::warn("text_blobs and wasm_modules are not bound with the modules format, import them from your modules instead");
+                    }
                     let migration = match &target.migrations {
-                        Some(migrations) => Some(migrations.api_migration()?),
+                        Some(migrations) => migrations.api_migration()?,
                         None => None,
                     };
 
                     let module_config = ModuleConfig::new(main, dir, rules);
-                    let assets = ModulesAssets::new(
-                        compatibility_date,
-                        compatibility_flags,
+                    let mut assets = ModulesAssets::new(
+                        target,
                         module_config.get_modules()?,
-                        kv_namespaces.to_vec(),
-                        durable_object_classes,
                         migration,
                         plain_texts,
-                        usage_model,
                     )?;
+                    if target.upload_source_maps {
+                        let main = &assets.manifest.modules[&assets.manifest.main];
+                        assets.source_map = SourceMap::new(&assets.manifest.main, &main.path)?;
+                    }
 
                     modules_worker::build_form(&assets, session_config)
                 }
@@ -147,18 +143,16 @@ pub fn build(
                 let package_dir = target.package_dir()?;
                 let package = Package::new(&package_dir)?;
                 let script_path = package.main(&package_dir)?;
+                let source_map = source_map(target, &script_path)?;
 
-                let assets = ServiceWorkerAssets {
+                let assets = ServiceWorkerAssets::new(
+                    target,
                     script_path,
-                    compatibility_date,
-                    compatibility_flags,
                     wasm_modules,
-                    kv_namespaces: kv_namespaces.to_vec(),
-                    durable_object_classes,
                     text_blobs,
                     plain_texts,
-                    usage_model,
-                };
+                    source_map,
+                );
 
                 service_worker::build_form(&assets, session_config)
             }
@@ -170,6 +164,7 @@ pub fn build(
             let bundle = wranglerjs::Bundle::new(&package_dir);
 
             let script_path = bundle.script_path();
+            let source_map = source_map(target, &script_path)?;
 
             if bundle.has_wasm() {
                 let path = bundle.wasm_path();
@@ -178,23 +173,33 @@ pub fn build(
                 wasm_modules.push(wasm_module);
             }
 
-            let assets = ServiceWorkerAssets {
+            let assets = ServiceWorkerAssets::new(
+                target,
                 script_path,
-                compatibility_date,
-                compatibility_flags,
                 wasm_modules,
-                kv_namespaces: kv_namespaces.to_vec(),
-                durable_object_classes,
                 text_blobs,
                 plain_texts,
-                usage_model,
-            };
+                source_map,
+            );
 
             service_worker::build_form(&assets, session_config)
         }
     }
 }
 
+fn source_map(target: &Target, script_path: &Path) -> Result<Option<SourceMap>> {
+    if !target.upload_source_maps {
+        return Ok(None);
+    }
+    let script_name = script_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            anyhow::anyhow!("filename should not be empty: {}", script_path.display())
+        })?;
+    SourceMap::new(script_name, script_path)
+}
+
 fn get_asset_manifest_blob(asset_manifest: AssetManifest) -> Result<String> {
     let asset_manifest = serde_json::to_string(&asset_manifest)?;
     Ok(asset_manifest)
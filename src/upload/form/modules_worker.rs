@@ -6,7 +6,9 @@ use serde::Serialize;
 
 use crate::settings::binding::Binding;
 use crate::settings::toml::migrations::ApiMigration;
+use crate::settings::toml::Annotations;
 
+use super::project_assets::{add_unsafe_bindings, keep_bindings};
 use super::{ModulesAssets, UsageModel};
 
 #[derive(Serialize, Debug)]
@@ -19,6 +21,10 @@ struct Metadata {
     pub compatibility_date: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub compatibility_flags: Vec<String>,
+    #[serde(skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keep_bindings: Vec<&'static str>,
 }
 
 pub fn build_form(
@@ -48,18 +54,24 @@ fn add_files(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
             .file_name(name.clone());
         form = form.part(name.clone(), part);
     }
+    if let Some(source_map) = &assets.source_map {
+        form = form.part(source_map.name.clone(), source_map.part()?);
+    }
     Ok(form)
 }
 
 fn add_metadata(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
-    let metadata_json = serde_json::json!(&Metadata {
+    let mut metadata_json = serde_json::json!(&Metadata {
         main_module: assets.manifest.main.clone(),
         bindings: assets.bindings(),
         migrations: assets.migration.clone(),
         usage_model: assets.usage_model,
         compatibility_date: assets.compatibility_date.clone(),
         compatibility_flags: assets.compatibility_flags.clone(),
+        annotations: assets.annotations.clone(),
+        keep_bindings: keep_bindings(assets.keep_vars),
     });
+    add_unsafe_bindings(&mut metadata_json, &assets.unsafe_bindings);
 
     let metadata = Part::text(metadata_json.to_string())
         .file_name("metadata.json")
@@ -9,12 +9,14 @@ use serde::{Deserialize, Serialize};
 use super::binding::Binding;
 use super::filestem_from_path;
 use super::plain_text::PlainText;
+use super::source_map::SourceMap;
 use super::text_blob::TextBlob;
 use super::wasm_module::WasmModule;
 use super::UsageModel;
 
 use crate::settings::toml::{
-    migrations::ApiMigration, DurableObjectsClass, KvNamespace, ModuleRule,
+    migrations::ApiMigration, AnalyticsEngineDataset, Annotations, DurableObjectsClass,
+    KvNamespace, ModuleRule, QueueProducer, ServiceBinding, Target,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -26,12 +28,81 @@ pub struct ServiceWorkerAssets {
     pub wasm_modules: Vec<WasmModule>,
     pub kv_namespaces: Vec<KvNamespace>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
+    pub services: Vec<ServiceBinding>,
+    pub analytics_engine_datasets: Vec<AnalyticsEngineDataset>,
+    pub queue_producers: Vec<QueueProducer>,
+    pub unsafe_bindings: Vec<serde_json::Value>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
     pub usage_model: Option<UsageModel>,
+    pub source_map: Option<SourceMap>,
+    pub annotations: Annotations,
+    pub keep_vars: bool,
+}
+
+/// Appends the [[unsafe.bindings]] to the bindings of the metadata, which are passed on
+/// without wrangler knowing their types.
+pub fn add_unsafe_bindings(
+    metadata: &mut serde_json::Value,
+    unsafe_bindings: &[serde_json::Value],
+) {
+    if let Some(bindings) = metadata["bindings"].as_array_mut() {
+        bindings.extend(unsafe_bindings.iter().cloned());
+    }
+}
+
+/// The types of the bindings of the deployed script that are kept when `keep_vars` is set.
+const VAR_BINDING_TYPES: &[&str] = &["plain_text", "json"];
+
+/// The types of the bindings of the deployed script that the upload doesn't replace.
+pub fn keep_bindings(keep_vars: bool) -> Vec<&'static str> {
+    if keep_vars {
+        VAR_BINDING_TYPES.to_vec()
+    } else {
+        vec![]
+    }
+}
+
+/// The classes of the [durable_objects] bindings of the target.
+fn durable_object_classes(target: &Target) -> Vec<DurableObjectsClass> {
+    target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.clone())
+        .unwrap_or_default()
 }
 
 impl ServiceWorkerAssets {
+    /// Takes the settings of the upload from the target, along with the files of the
+    /// built project.
+    pub fn new(
+        target: &Target,
+        script_path: PathBuf,
+        wasm_modules: Vec<WasmModule>,
+        text_blobs: Vec<TextBlob>,
+        plain_texts: Vec<PlainText>,
+        source_map: Option<SourceMap>,
+    ) -> Self {
+        Self {
+            script_path,
+            compatibility_date: target.compatibility_date.clone(),
+            compatibility_flags: target.compatibility_flags.clone(),
+            wasm_modules,
+            kv_namespaces: target.kv_namespaces.clone(),
+            durable_object_classes: durable_object_classes(target),
+            services: target.services.clone(),
+            analytics_engine_datasets: target.analytics_engine_datasets.clone(),
+            queue_producers: target.queue_producers.clone(),
+            unsafe_bindings: target.unsafe_bindings.clone(),
+            text_blobs,
+            plain_texts,
+            usage_model: target.usage_model,
+            source_map,
+            annotations: target.annotations.clone(),
+            keep_vars: target.keep_vars,
+        }
+    }
+
     pub fn bindings(&self) -> Vec<Binding> {
         let mut bindings = Vec::new();
 
@@ -47,6 +118,18 @@ impl ServiceWorkerAssets {
             let binding = do_ns.binding();
             bindings.push(binding);
         }
+        for service in &self.services {
+            let binding = service.binding();
+            bindings.push(binding);
+        }
+        for dataset in &self.analytics_engine_datasets {
+            let binding = dataset.binding();
+            bindings.push(binding);
+        }
+        for producer in &self.queue_producers {
+            let binding = producer.binding();
+            bindings.push(binding);
+        }
         for blob in &self.text_blobs {
             let binding = blob.binding();
             bindings.push(binding);
@@ -317,32 +400,44 @@ pub struct ModulesAssets {
     pub manifest: ModuleManifest,
     pub kv_namespaces: Vec<KvNamespace>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
+    pub services: Vec<ServiceBinding>,
+    pub analytics_engine_datasets: Vec<AnalyticsEngineDataset>,
+    pub queue_producers: Vec<QueueProducer>,
+    pub unsafe_bindings: Vec<serde_json::Value>,
     pub migration: Option<ApiMigration>,
     pub plain_texts: Vec<PlainText>,
     pub usage_model: Option<UsageModel>,
+    pub source_map: Option<SourceMap>,
+    pub annotations: Annotations,
+    pub keep_vars: bool,
 }
 
 impl ModulesAssets {
-    #[allow(clippy::too_many_arguments)] // TODO: refactor?
+    /// Takes the settings of the upload from the target, along with the modules of the
+    /// built project.
     pub fn new(
-        compatibility_date: Option<String>,
-        compatibility_flags: Vec<String>,
+        target: &Target,
         manifest: ModuleManifest,
-        kv_namespaces: Vec<KvNamespace>,
-        durable_object_classes: Vec<DurableObjectsClass>,
         migration: Option<ApiMigration>,
         plain_texts: Vec<PlainText>,
-        usage_model: Option<UsageModel>,
     ) -> Result<Self> {
+        validate_main_module(&manifest)?;
         Ok(Self {
-            compatibility_date,
-            compatibility_flags,
+            compatibility_date: target.compatibility_date.clone(),
+            compatibility_flags: target.compatibility_flags.clone(),
             manifest,
-            kv_namespaces,
-            durable_object_classes,
+            kv_namespaces: target.kv_namespaces.clone(),
+            durable_object_classes: durable_object_classes(target),
+            services: target.services.clone(),
+            analytics_engine_datasets: target.analytics_engine_datasets.clone(),
+            queue_producers: target.queue_producers.clone(),
+            unsafe_bindings: target.unsafe_bindings.clone(),
             migration,
             plain_texts,
-            usage_model,
+            usage_model: target.usage_model,
+            source_map: None,
+            annotations: target.annotations.clone(),
+            keep_vars: target.keep_vars,
         })
     }
 
@@ -360,6 +455,18 @@ impl ModulesAssets {
             let binding = class.binding();
             bindings.push(binding);
         }
+        for service in &self.services {
+            let binding = service.binding();
+            bindings.push(binding);
+        }
+        for dataset in &self.analytics_engine_datasets {
+            let binding = dataset.binding();
+            bindings.push(binding);
+        }
+        for producer in &self.queue_producers {
+            let binding = producer.binding();
+            bindings.push(binding);
+        }
         for plain_text in &self.plain_texts {
             let binding = plain_text.binding();
             bindings.push(binding);
@@ -369,6 +476,27 @@ impl ModulesAssets {
     }
 }
 
+// the main module is the one the worker runs, so it has to be uploaded as an ES module
+fn validate_main_module(manifest: &ModuleManifest) -> Result<()> {
+    match manifest.modules.get(&manifest.main) {
+        Some(module) if module.module_type == ModuleType::ESModule => Ok(()),
+        Some(module) => Err(anyhow!(
+            "The main module {} is a {} module, but it has to be an ES module. Name it with .mjs, or add a rule with type = \"ESModule\" for it to [build.upload].",
+            manifest.main,
+            module.module_type.name()
+        )),
+        None if manifest.modules.contains_key(&format!("./{}", manifest.main)) => Err(anyhow!(
+            "The main module {} was not found, module names start with ./, did you mean ./{}?",
+            manifest.main,
+            manifest.main
+        )),
+        None => Err(anyhow!(
+            "The main module {} was not found in the upload dir, check that your build writes it there.",
+            manifest.main
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -500,6 +628,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn main_module_is_validated() {
+        let manifest = |main: &str, module_type: ModuleType| {
+            let mut modules = HashMap::new();
+            modules.insert(
+                "./index.mjs".to_owned(),
+                Module {
+                    path: "/worker/dist/index.mjs".into(),
+                    module_type,
+                },
+            );
+            ModuleManifest {
+                main: main.to_owned(),
+                modules,
+            }
+        };
+
+        assert!(validate_main_module(&manifest("./index.mjs", ModuleType::ESModule)).is_ok());
+        assert!(validate_main_module(&manifest("./index.mjs", ModuleType::CommonJS)).is_err());
+        let missing_prefix = validate_main_module(&manifest("index.mjs", ModuleType::ESModule));
+        assert!(missing_prefix
+            .unwrap_err()
+            .to_string()
+            .contains("did you mean ./index.mjs"));
+        assert!(validate_main_module(&manifest("./other.mjs", ModuleType::ESModule)).is_err());
+    }
+
     #[test]
     fn invalid_globs_fail() {
         let rules = rules![
@@ -513,4 +668,15 @@ mod tests {
                 .expect("error on invalid globs")
         );
     }
+
+    #[test]
+    fn it_appends_unsafe_bindings() {
+        let mut metadata = serde_json::json!({
+            "bindings": [{ "type": "plain_text", "name": "GREETING", "text": "hi" }]
+        });
+        let unsafe_binding = serde_json::json!({ "type": "new_type", "name": "NEW", "id": "1" });
+
+        add_unsafe_bindings(&mut metadata, &[unsafe_binding.clone()]);
+        assert_eq!(metadata["bindings"][1], unsafe_binding);
+    }
 }
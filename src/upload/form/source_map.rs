@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use reqwest::blocking::multipart::Part;
+
+use crate::terminal::message::{Message, StdErr};
+
+const SOURCE_MAPPING_URL: &str = "//# sourceMappingURL=";
+
+/// The source map of a script, uploaded alongside it so that the stack traces of
+/// exceptions point at the original source.
+#[derive(Debug)]
+pub struct SourceMap {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl SourceMap {
+    /// Looks for the source map of the script that is uploaded as `script_name`, warning
+    /// when there is none.
+    pub fn new(script_name: &str, script_path: &Path) -> Result<Option<SourceMap>> {
+        match find(script_path)? {
+            Some(path) => Ok(Some(SourceMap {
+                name: format!("{}.map", script_name),
+                path,
+            })),
+            None => {
+                StdErr::warn(&format!(
+                    "upload_source_maps is enabled, but there is no source map for {}. Make sure your build writes it to a separate file.",
+                    script_path.display()
+                ));
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn part(&self) -> Result<Part> {
+        Ok(Part::file(&self.path)?
+            .file_name(self.name.clone())
+            .mime_str("application/source-map")?)
+    }
+}
+
+/// The source map a script points to with a `sourceMappingURL` comment, or the `.map` file
+/// next to it.
+fn find(script_path: &Path) -> Result<Option<PathBuf>> {
+    let script = fs::read_to_string(script_path)?;
+    let url = script
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix(SOURCE_MAPPING_URL));
+
+    if let Some(url) = url {
+        // inline source maps and ones hosted elsewhere can't be uploaded
+        if !url.contains(':') {
+            let path = script_path.with_file_name(url);
+            if path.is_file() {
+                return Ok(Some(path));
+            }
+        }
+    }
+
+    let mut path = script_path.as_os_str().to_owned();
+    path.push(".map");
+    let path = PathBuf::from(path);
+    Ok(if path.is_file() { Some(path) } else { None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_finds_the_source_map_of_a_script() -> Result<()> {
+        let dir = TempDir::new()?;
+        let script = dir.path().join("index.js");
+
+        fs::write(&script, "foo()")?;
+        assert_eq!(find(&script)?, None);
+
+        fs::write(dir.path().join("index.js.map"), "{}")?;
+        assert_eq!(find(&script)?, Some(dir.path().join("index.js.map")));
+
+        fs::write(&script, "foo()\n//# sourceMappingURL=maps.json\n")?;
+        fs::write(dir.path().join("maps.json"), "{}")?;
+        assert_eq!(find(&script)?, Some(dir.path().join("maps.json")));
+
+        Ok(())
+    }
+}
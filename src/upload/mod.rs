@@ -1,38 +1,102 @@
 pub mod form;
 mod krate;
 pub mod package;
+pub mod size;
 
 use indicatif::{ProgressBar, ProgressStyle};
 pub use package::Package;
+pub use size::SizeReport;
 
 use anyhow::Result;
 use reqwest::blocking::Client;
+use serde::Deserialize;
 
+use crate::http;
 use crate::settings::toml::Target;
 use crate::sites::AssetManifest;
 
+/// Where the scripts of the account, or of the dispatch namespace of the target, are uploaded.
+pub fn scripts_addr(target: &Target) -> Result<String> {
+    let account_id = target.account_id.load()?;
+    Ok(match &target.dispatch_namespace {
+        Some(namespace) => format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/dispatch/namespaces/{}/scripts",
+            account_id, namespace
+        ),
+        None => format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+            account_id
+        ),
+    })
+}
+
+/// Uploads and deploys a script, returning the etag of the uploaded version.
 pub fn script(
     client: &Client,
     target: &Target,
     asset_manifest: Option<AssetManifest>,
-) -> Result<()> {
-    let worker_addr = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+) -> Result<Option<String>> {
+    let worker_addr = format!("{}/{}", scripts_addr(target)?, target.name);
+
+    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
+    let spinner = ProgressBar::new_spinner().with_style(style);
+    spinner.set_message("Uploading script...");
+    spinner.enable_steady_tick(20);
+
+    let res = http::retry::send("Uploading the script", || {
+        let script_upload_form = form::build(target, asset_manifest.clone(), None)?;
+        Ok(client.put(&worker_addr).multipart(script_upload_form))
+    })?;
+
+    spinner.finish_and_clear();
+
+    if !res.status().is_success() {
+        anyhow::bail!(error_msg(res.text()?))
+    }
+
+    // the etag identifies the uploaded version of the script
+    let script: Option<ScriptResponse> = res.json().ok();
+    Ok(script.and_then(|script| script.result.etag))
+}
+
+#[derive(Deserialize)]
+struct ScriptResponse {
+    result: ScriptResult,
+}
+
+#[derive(Deserialize)]
+struct ScriptResult {
+    etag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    result: Version,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    id: String,
+}
+
+/// Uploads a new version of a script without deploying it, returning its id.
+pub fn version(client: &Client, target: &Target) -> Result<String> {
+    let versions_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/versions",
         target.account_id.load()?,
         target.name,
     );
 
-    let script_upload_form = form::build(target, asset_manifest, None)?;
-
     let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
     let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.set_message("Uploading script...");
+    spinner.set_message("Uploading version...");
     spinner.enable_steady_tick(20);
 
-    let res = client
-        .put(&worker_addr)
-        .multipart(script_upload_form)
-        .send()?;
+    // each upload creates a version, so one that failed on the server isn't sent again
+    let res = http::retry::send_non_idempotent("Uploading the version", || {
+        let version_upload_form = form::build(target, None, None)?;
+        Ok(client.post(&versions_addr).multipart(version_upload_form))
+    })?;
 
     spinner.finish_and_clear();
 
@@ -40,7 +104,8 @@ pub fn script(
         anyhow::bail!(error_msg(res.text()?))
     }
 
-    Ok(())
+    let version: VersionResponse = res.json()?;
+    Ok(version.result.id)
 }
 
 fn error_msg(text: String) -> String {
@@ -0,0 +1,227 @@
+use std::fs;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use number_prefix::NumberPrefix;
+use serde::{Deserialize, Serialize};
+
+use super::form::ModuleConfig;
+use super::{krate, Package};
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::{emoji, styles};
+use crate::wranglerjs;
+
+/// The largest a compressed script can be, with all of its modules.
+pub const MAX_SCRIPT_SIZE: u64 = 1 << 20; // 1 MiB
+/// Warn when less than 80 KiB left to grow, ~92% usage
+const WARN_THRESHOLD: u64 = MAX_SCRIPT_SIZE - 81_920;
+
+/// The size of a file that is uploaded with the script.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleSize {
+    pub name: String,
+    pub size: u64,
+    pub compressed: u64,
+}
+
+/// The sizes of everything a build uploads, to check against the size limit before uploading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeReport {
+    pub modules: Vec<ModuleSize>,
+    /// the size of all of the modules compressed together, which is what the limit applies to
+    pub compressed: u64,
+}
+
+impl SizeReport {
+    /// Measures the files a built target would upload.
+    pub fn new(target: &Target) -> Result<SizeReport> {
+        let files = files(target)?
+            .into_iter()
+            .map(|(name, path)| Ok((name, fs::read(path)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(SizeReport::from_files(&files))
+    }
+
+    fn from_files(files: &[(String, Vec<u8>)]) -> SizeReport {
+        let modules = files
+            .iter()
+            .map(|(name, contents)| ModuleSize {
+                name: name.clone(),
+                size: contents.len() as u64,
+                compressed: compressed_size(&[contents.as_slice()]),
+            })
+            .collect();
+        let compressed = compressed_size(
+            &files
+                .iter()
+                .map(|(_, contents)| contents.as_slice())
+                .collect::<Vec<_>>(),
+        );
+        SizeReport {
+            modules,
+            compressed,
+        }
+    }
+
+    pub fn print(&self) {
+        for module in self.modules.iter() {
+            StdErr::info(&format!(
+                "{} {} ({} compressed)",
+                styles::highlight(&module.name),
+                human_size(module.size),
+                human_size(module.compressed)
+            ));
+        }
+        StdErr::info(&format!(
+            "Total compressed size: {}",
+            human_size(self.compressed)
+        ));
+    }
+
+    /// Warns when the script is close to the size limit, and fails when it is past it,
+    /// as it could not be uploaded anyway.
+    pub fn check(&self) -> Result<()> {
+        if self.compressed > MAX_SCRIPT_SIZE {
+            anyhow::bail!(
+                "{} Your built project is {} compressed, which is past the {} size limit. Reduce the size of the largest modules above before publishing.",
+                emoji::WARN,
+                human_size(self.compressed),
+                human_size(MAX_SCRIPT_SIZE)
+            )
+        }
+        if self.compressed >= WARN_THRESHOLD {
+            StdErr::warn(&format!(
+                "Your built project is {} away from reaching the {} size limit.",
+                human_size(MAX_SCRIPT_SIZE - self.compressed),
+                human_size(MAX_SCRIPT_SIZE)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The name and path of each file a built target uploads, alongside its bindings.
+fn files(target: &Target) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+
+    match &target.target_type {
+        TargetType::Rust => {
+            let name = krate::Krate::new("./")?.name.replace("-", "_");
+            files.push((
+                "script".to_owned(),
+                PathBuf::from(format!("./pkg/{}.js", name)),
+            ));
+            files.push((
+                "wasm".to_owned(),
+                PathBuf::from(format!("./pkg/{}_bg.wasm", name)),
+            ));
+        }
+        TargetType::JavaScript => match target.build.as_ref().map(|config| &config.upload) {
+            Some(UploadFormat::Modules { main, dir, rules }) => {
+                let manifest = ModuleConfig::new(main, dir, rules).get_modules()?;
+                let mut modules = manifest.modules.into_iter().collect::<Vec<_>>();
+                modules.sort();
+                files.extend(
+                    modules
+                        .into_iter()
+                        .map(|(name, module)| (name, module.path)),
+                );
+            }
+            Some(UploadFormat::ServiceWorker {}) => {
+                let package_dir = target.package_dir()?;
+                let package = Package::new(&package_dir)?;
+                files.push((
+                    "script".to_owned(),
+                    package_dir.join(package.main(&package_dir)?),
+                ));
+            }
+            None => {
+                let package_dir = target.package_dir()?;
+                let package = Package::new(&package_dir)?;
+                files.push(("script".to_owned(), package.main(&package_dir)?));
+            }
+        },
+        TargetType::Webpack => {
+            let bundle = wranglerjs::Bundle::new(&target.package_dir()?);
+            files.push(("script".to_owned(), bundle.script_path()));
+            if bundle.has_wasm() {
+                files.push((bundle.get_wasm_binding(), bundle.wasm_path()));
+            }
+        }
+    }
+
+    if let Some(modules) = &target.wasm_modules {
+        files.extend(
+            modules
+                .iter()
+                .map(|(key, path)| (key.clone(), path.clone())),
+        );
+    }
+    if let Some(blobs) = &target.text_blobs {
+        files.extend(blobs.iter().map(|(key, path)| (key.clone(), path.clone())));
+    }
+    Ok(files)
+}
+
+// an approximation of how scripts are compressed
+fn compressed_size(contents: &[&[u8]]) -> u64 {
+    let mut e = ZlibEncoder::new(Vec::new(), Compression::default());
+    for contents in contents {
+        e.write_all(contents).expect("could not write buffer");
+    }
+    e.finish().expect("failed to compress project").len() as u64
+}
+
+/// A size in bytes, in the binary unit that reads best.
+pub fn human_size(bytes: u64) -> String {
+    match NumberPrefix::binary(bytes as f64) {
+        NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
+        NumberPrefix::Prefixed(prefix, n) => format!("{:.0} {}B", n, prefix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bytes that barely compress, from a linear congruential generator
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state: u32 = 1;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_measures_each_module() {
+        let report = SizeReport::from_files(&[
+            ("script".to_owned(), b"abcdefg".to_vec()),
+            ("wasm".to_owned(), b"123456".to_vec()),
+        ]);
+
+        assert_eq!(report.modules.len(), 2);
+        assert_eq!(report.modules[0].name, "script");
+        assert_eq!(report.modules[0].size, 7);
+        assert_eq!(report.modules[0].compressed, 15);
+        assert_eq!(report.compressed, 21);
+        assert!(report.check().is_ok());
+    }
+
+    #[test]
+    fn it_fails_past_the_size_limit() {
+        let report = SizeReport::from_files(&[(
+            "script".to_owned(),
+            noise(MAX_SCRIPT_SIZE as usize + 4096),
+        )]);
+
+        assert!(report.compressed > MAX_SCRIPT_SIZE);
+        assert!(report.check().is_err());
+    }
+}
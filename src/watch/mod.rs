@@ -3,10 +3,11 @@ use ignore::overrides::OverrideBuilder;
 use ignore::WalkBuilder;
 pub use watcher::wait_for_changes;
 
+use crate::build::{command, wasm_pack_args};
+use crate::build_target;
 use crate::settings::toml::{Target, TargetType};
 use crate::terminal::message::{Message, StdOut};
 use crate::wranglerjs;
-use crate::{build::command, build_target};
 use crate::{commands, install};
 
 use anyhow::Result;
@@ -23,11 +24,13 @@ const RUST_PATH: &str = "./";
 const RUST_IGNORE: &[&str] = &["pkg", "target", "worker/generated"];
 
 // watch a project for changes and re-build it when necessary,
-// outputting a build event to tx.
+// outputting a build event to tx. `dev` builds Rust projects the way
+// `build_dev_target` does, for `wrangler dev`.
 pub fn watch_and_build(
     target: &Target,
     tx: Option<mpsc::Sender<()>>,
     refresh_session_sender: Option<Sender<Option<()>>>,
+    dev: bool,
 ) -> Result<()> {
     let target_type = &target.target_type;
     let build = target.build.clone();
@@ -96,7 +99,7 @@ pub fn watch_and_build(
         }
         TargetType::Rust => {
             let binary_path = install::install_wasm_pack()?;
-            let args = ["build", "--target", "no-modules"];
+            let args = wasm_pack_args(dev);
 
             thread::spawn::<_, Result<()>>(move || {
                 let (watcher_tx, watcher_rx) = mpsc::channel();
@@ -134,6 +137,7 @@ pub fn watch_and_build(
                         Ok(_path) => {
                             let command = command(&args, &binary_path);
                             let command_name = format!("{:?}", command);
+                            StdOut::working("Rebuilding your project...");
                             if commands::run(command, &command_name).is_ok() {
                                 if let Some(tx) = tx.clone() {
                                     tx.send(())?;
@@ -44,10 +44,11 @@ impl Bundle {
             wasm_file.write_all(&wasm)?;
         }
 
+        // lines written before the script shift its source map
+        let mut prelude_lines = 0;
         if self.has_wasm() {
-            script_file.write_all(
-                format!(
-                    r#"
+            let prelude = format!(
+                r#"
                         WebAssembly.instantiateStreaming =
                             async function instantiateStreaming(req, importObject) {{
                           const module = {};
@@ -57,13 +58,25 @@ impl Bundle {
                           }}
                         }};
                     "#,
-                    self.get_wasm_binding()
-                )
-                .as_bytes(),
-            )?;
+                self.get_wasm_binding()
+            );
+            // the script starts on its own line, so that its columns in the source map stay put
+            let prelude = prelude.trim_end_matches(' ');
+            script_file.write_all(prelude.as_bytes())?;
+            prelude_lines = prelude.matches('\n').count();
         }
         script_file.write_all(wranglerjs_output.script.as_bytes())?;
 
+        match &wranglerjs_output.source_map {
+            Some(source_map) => fs::write(
+                self.source_map_path(),
+                shift_source_map(source_map, prelude_lines)?,
+            )?,
+            // don't upload the source map of an earlier build
+            None if self.source_map_path().exists() => fs::remove_file(self.source_map_path())?,
+            None => {}
+        }
+
         Ok(())
     }
 
@@ -82,6 +95,25 @@ impl Bundle {
     pub fn script_path(&self) -> PathBuf {
         PathBuf::from(&self.out).join("script.js")
     }
+
+    pub fn source_map_path(&self) -> PathBuf {
+        PathBuf::from(&self.out).join("script.js.map")
+    }
+}
+
+// Moves the mappings of a source map down by some lines, as each `;` in them starts the
+// mappings of the next line of the script.
+fn shift_source_map(source_map: &str, lines: usize) -> Result<String> {
+    if lines == 0 {
+        return Ok(source_map.to_owned());
+    }
+    let mut source_map: serde_json::Value = serde_json::from_str(source_map)?;
+    if let Some(mappings) = source_map.get_mut("mappings") {
+        if let Some(existing) = mappings.as_str() {
+            *mappings = serde_json::Value::String(format!("{}{}", ";".repeat(lines), existing));
+        }
+    }
+    Ok(source_map.to_string())
 }
 
 #[cfg(test)]
@@ -106,6 +138,8 @@ mod tests {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
             script: "foo".to_string(),
+            source_map: None,
+            unminified_size: None,
             wasm: None,
         };
         let bundle = Bundle::new_at(out.clone());
@@ -123,6 +157,8 @@ mod tests {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
             script: "".to_string(),
+            source_map: None,
+            unminified_size: None,
             wasm: Some("abc".to_string()),
         };
         let bundle = Bundle::new_at(out.clone());
@@ -134,11 +170,39 @@ mod tests {
         cleanup(out);
     }
 
+    #[test]
+    fn it_writes_the_bundle_source_map() {
+        let out = create_temp_dir("it_writes_the_bundle_source_map");
+        let wranglerjs_output = WranglerjsOutput {
+            errors: vec![],
+            script: "foo".to_string(),
+            source_map: Some(r#"{"version":3,"mappings":"AAAA"}"#.to_string()),
+            unminified_size: None,
+            wasm: Some("abc".to_string()),
+        };
+        let bundle = Bundle::new_at(out.clone());
+
+        bundle.write(&wranglerjs_output).unwrap();
+        let script = fs::read_to_string(bundle.script_path()).unwrap();
+        let source_map: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(bundle.source_map_path()).unwrap()).unwrap();
+        // the script starts on the line after the Wasm prelude
+        let script_line = script.lines().position(|line| line == "foo").unwrap();
+        assert_eq!(
+            source_map["mappings"],
+            format!("{}AAAA", ";".repeat(script_line))
+        );
+
+        cleanup(out);
+    }
+
     #[test]
     fn it_has_errors() {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec!["a".to_string(), "b".to_string()],
             script: "".to_string(),
+            source_map: None,
+            unminified_size: None,
             wasm: None,
         };
         assert!(wranglerjs_output.has_errors());
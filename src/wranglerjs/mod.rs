@@ -181,6 +181,14 @@ fn setup_build(target: &Target) -> Result<(Command, PathBuf, Bundle)> {
 
     command.arg(format!("--wasm-binding={}", bundle.get_wasm_binding()));
 
+    if target.upload_source_maps {
+        command.arg("--source-map=1");
+    }
+
+    if target.minify {
+        command.arg("--minify=1");
+    }
+
     let custom_webpack_config_path = match &target.webpack_config {
         Some(webpack_config) => Some(PathBuf::from(&webpack_config)),
         None => {
@@ -1,7 +1,7 @@
 use crate::terminal::emoji;
+use crate::upload::size::human_size;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
-use number_prefix::NumberPrefix;
 use serde::Deserialize;
 use std::io::prelude::*;
 
@@ -12,6 +12,12 @@ use std::io::prelude::*;
 pub struct WranglerjsOutput {
     pub wasm: Option<String>,
     pub script: String,
+    // The source map of the script, when it was asked for
+    #[serde(default)]
+    pub source_map: Option<String>,
+    // The size of the script before it was minified, when it was asked for
+    #[serde(default)]
+    pub unminified_size: Option<u64>,
     // Errors emitted by {wranglerjs}, if any
     pub errors: Vec<String>,
 }
@@ -40,6 +46,16 @@ impl WranglerjsOutput {
         e.finish().expect("failed to compress project").len() as u64
     }
 
+    /// How much minifying shrunk the script, when it was minified.
+    pub fn minified_size(&self) -> Option<String> {
+        let unminified_size = self.unminified_size?;
+        Some(format!(
+            "Minified the script from {} to {}",
+            human_size(unminified_size),
+            human_size(self.script.len() as u64)
+        ))
+    }
+
     fn project_size_message(compressed_size: u64) -> String {
         const MAX_PROJECT_SIZE: u64 = 1 << 20; // 1 MiB
         const WARN_THRESHOLD: u64 = MAX_PROJECT_SIZE - 81_920; // Warn when less than 80 KiB left to grow, ~92% usage
@@ -47,25 +63,13 @@ impl WranglerjsOutput {
 
         let bytes_left = MAX_PROJECT_SIZE.checked_sub(compressed_size);
 
-        let human_size = match NumberPrefix::binary(compressed_size as f64) {
-            NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
-            NumberPrefix::Prefixed(prefix, n) => format!("{:.0} {}B", n, prefix),
-        };
-
-        let human_leftover = if let Some(bytes_left) = bytes_left {
-            let msg = match NumberPrefix::binary(bytes_left as f64) {
-                NumberPrefix::Standalone(bytes) => format!("{} bytes", bytes),
-                NumberPrefix::Prefixed(prefix, n) => format!("{:.0} {}B", n, prefix),
-            };
-            Some(msg)
-        } else {
-            None
-        };
+        let size = human_size(compressed_size);
+        let human_leftover = bytes_left.map(human_size);
 
         match compressed_size {
-            WARN_THRESHOLD..=MAX_PROJECT_SIZE => format!("{}. {2} Your built project is {} away from reaching the 1MiB size limit. {2}", human_size, human_leftover.expect("failed to get leftover bytes"), emoji::WARN),
-            0..=MAX_BEFORE_WARN => format!("{}.", human_size),
-            _ => format!("{}. {1} Your built project has grown past the 1MiB size limit and may fail to deploy. {1}", human_size, emoji::WARN)
+            WARN_THRESHOLD..=MAX_PROJECT_SIZE => format!("{}. {2} Your built project is {} away from reaching the 1MiB size limit. {2}", size, human_leftover.expect("failed to get leftover bytes"), emoji::WARN),
+            0..=MAX_BEFORE_WARN => format!("{}.", size),
+            _ => format!("{}. {1} Your built project has grown past the 1MiB size limit and may fail to deploy. {1}", size, emoji::WARN)
         }
     }
 
@@ -93,6 +97,8 @@ mod tests {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
             script: "abcdefg".to_string(),
+            source_map: None,
+            unminified_size: None,
             wasm: Some("123456".to_string()),
         };
 
@@ -104,9 +110,29 @@ mod tests {
         let wranglerjs_output = WranglerjsOutput {
             errors: vec![],
             script: "abcdefg".to_string(),
+            source_map: None,
+            unminified_size: None,
             wasm: None,
         };
 
         assert_eq!(wranglerjs_output.project_size_bytes(), 15);
     }
+
+    #[test]
+    fn it_reports_the_minified_size() {
+        let mut wranglerjs_output = WranglerjsOutput {
+            errors: vec![],
+            script: "a".repeat(2048),
+            source_map: None,
+            unminified_size: None,
+            wasm: None,
+        };
+        assert_eq!(wranglerjs_output.minified_size(), None);
+
+        wranglerjs_output.unminified_size = Some(10240);
+        assert_eq!(
+            wranglerjs_output.minified_size().unwrap(),
+            "Minified the script from 10 KiB to 2 KiB"
+        );
+    }
 }
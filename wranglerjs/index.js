@@ -91,6 +91,17 @@ function filterByExtension(ext) {
   config.output.filename = WEBPACK_OUTPUT_FILENAME;
   config.output.sourceMapFilename = WEBPACK_OUTPUT_SOURCEMAPFILENAME;
 
+  // Source maps are uploaded with the script, so they have to be separate files
+  // rather than inlined into it.
+  if (args["source-map"] === "1") {
+    if (config.devtool && config.devtool !== "source-map") {
+      warn(
+        "webpack's devtool is being set to source-map because upload_source_maps is enabled"
+      );
+    }
+    config.devtool = "source-map";
+  }
+
   const compiler = webpack(config);
   const fullConfig = compiler.options;
 
@@ -106,6 +117,8 @@ function filterByExtension(ext) {
       const bundle = {
         wasm: null,
         script: "",
+        source_map: null,
+        unminified_size: null,
         errors: jsonStats.errors,
       };
 
@@ -120,6 +133,34 @@ function filterByExtension(ext) {
         return acc + asset.source();
       }, "");
 
+      if (assets[WEBPACK_OUTPUT_SOURCEMAPFILENAME] !== undefined) {
+        bundle.source_map = assets[WEBPACK_OUTPUT_SOURCEMAPFILENAME]
+          .source()
+          .toString();
+      }
+
+      // webpack's own minification would hide the size of the script before it, so the
+      // bundle is minified here instead
+      if (args["minify"] === "1") {
+        bundle.unminified_size = Buffer.byteLength(bundle.script);
+        const minified = require("terser").minify(bundle.script, {
+          sourceMap:
+            bundle.source_map === null
+              ? false
+              : {
+                  content: bundle.source_map,
+                  url: WEBPACK_OUTPUT_SOURCEMAPFILENAME,
+                },
+        });
+        if (minified.error) {
+          throw error("could not minify the script: " + minified.error.message);
+        }
+        bundle.script = minified.code;
+        if (bundle.source_map !== null) {
+          bundle.source_map = minified.map;
+        }
+      }
+
       if (hasWasmModule === true) {
         bundle.wasm = Buffer.from(assets[wasmModuleAsset].source()).toString(
           "base64"